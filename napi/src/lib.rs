@@ -0,0 +1,67 @@
+//! Node.js bindings: the checks, sorting and statistics of the command line
+//! tool on in-memory strings, for build plugins and lint rules that would
+//! otherwise spawn the binary.
+
+use cvr_i18n::api;
+use cvr_i18n::config::Config;
+use napi::{Error, Result};
+use napi_derive::napi;
+use serde_json::Value;
+
+fn js_error(e: String) -> Error {
+    Error::from_reason(e)
+}
+
+/// The text of a `cvr-i18n.json`, or the defaults.
+fn parse_config(config: Option<String>) -> Result<Config> {
+    match config {
+        Some(c) => serde_json::from_str(&c).map_err(|e| js_error(format!("parse config: {}", e))),
+        None => Ok(Config::default()),
+    }
+}
+
+/// Every check on the locale file content `text` (`locale` is its locale
+/// code), against the base file content `base`; `config` is the text of a
+/// `cvr-i18n.json`. Returns `[{ rule, key, severity, message }]`.
+#[napi]
+pub fn check(
+    locale: String,
+    text: String,
+    base: Option<String>,
+    config: Option<String>,
+) -> Result<Value> {
+    let config = parse_config(config)?;
+    let issues = api::validate(&locale, &text, base.as_deref(), &config).map_err(js_error)?;
+    serde_json::to_value(issues).map_err(|e| js_error(e.to_string()))
+}
+
+/// `text` with its keys in the order of `base`, then of the order file
+/// content `order`; `unknownKeys` is `top`, `bottom`, `alpha` (default) or
+/// `error`. Written in the `fmt` style of `config`, the text of a
+/// `cvr-i18n.json`, as the command line writes it.
+#[napi]
+pub fn sort(
+    text: String,
+    base: Option<String>,
+    order: Option<String>,
+    unknown_keys: Option<String>,
+    config: Option<String>,
+) -> Result<String> {
+    let config = parse_config(config)?;
+    api::sort(
+        &text,
+        base.as_deref(),
+        order.as_deref(),
+        unknown_keys.as_deref().unwrap_or("alpha"),
+        &config.fmt,
+    )
+    .map_err(js_error)
+}
+
+/// `{ keys, translated, missing, empty, extra, coverage }` of `text` against
+/// `base`.
+#[napi]
+pub fn stats(text: String, base: Option<String>) -> Result<Value> {
+    let stats = api::stats(&text, base.as_deref()).map_err(js_error)?;
+    serde_json::to_value(stats).map_err(|e| js_error(e.to_string()))
+}
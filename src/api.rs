@@ -0,0 +1,213 @@
+//! Checks and statistics for one locale held in memory: no directories, no
+//! files written and no report output. This is what the WebAssembly build
+//! exposes.
+
+use crate::config::{Config, FmtStyle};
+use crate::order::{self, Unknown};
+use crate::rules::{self, Finding, Rule, Severity};
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+
+/// One problem found by [`validate`].
+#[derive(Serialize)]
+pub struct Issue {
+    pub rule: &'static str,
+    pub key: String,
+    pub severity: &'static str,
+    pub message: String,
+}
+
+impl From<Finding> for Issue {
+    fn from(f: Finding) -> Self {
+        Issue {
+            rule: f.rule,
+            key: f.key,
+            severity: f.severity.as_str(),
+            message: f.message,
+        }
+    }
+}
+
+/// Missing and extra top-level keys of `path` compared with `base`, for the
+/// `missing-key` and `extra-key` checks named in `checks`.
+pub fn key_findings(
+    path: &Path,
+    keys: &HashSet<String>,
+    base: &Value,
+    bp: &Path,
+    checks: &[&str],
+) -> Vec<Finding> {
+    let base_keys = crate::keys_from_value(base);
+    let mut out = Vec::new();
+    if checks.contains(&"missing-key") {
+        for k in base_keys.iter().filter(|k| !keys.contains(*k)) {
+            out.push(Finding::new(
+                path,
+                "missing-key",
+                k.clone(),
+                Severity::Error,
+                format!("missing (present in {})", bp.display()),
+            ));
+        }
+    }
+    if checks.contains(&"extra-key") {
+        let known: HashSet<&String> = base_keys.iter().collect();
+        let mut extra: Vec<&String> = keys.iter().filter(|k| !known.contains(k)).collect();
+        extra.sort();
+        for k in extra {
+            out.push(Finding::new(
+                path,
+                "extra-key",
+                k.clone(),
+                Severity::Warning,
+                format!("not in {}", bp.display()),
+            ));
+        }
+    }
+    out
+}
+
+/// Duplicate, missing and extra keys and every lint rule for the locale
+/// `locale` whose file content is `text`, compared with the base file
+/// content `base` when given. Fails when either text is not a JSON object.
+/// The `escapes` rule ignores `unescape-unicode` and `fmt.escape-slash`
+/// here, checking against the default style.
+pub fn validate(
+    locale: &str,
+    text: &str,
+    base: Option<&str>,
+    config: &Config,
+) -> Result<Vec<Issue>, String> {
+    let path = PathBuf::from(format!("{}.json", locale));
+    let bp = Path::new("base");
+    let base = base
+        .map(|b| serde_json::from_str::<Value>(b).map_err(|e| format!("parse base: {}", e)))
+        .transpose()?;
+    let (dups, syntax) = crate::find_duplicates(&path, text);
+    if let Some(e) = syntax {
+        return Err(e);
+    }
+    let mut out: Vec<Issue> = dups
+        .into_iter()
+        .map(|(key, c)| Issue {
+            rule: "duplicate-key",
+            key,
+            severity: Severity::Error.as_str(),
+            message: format!("key appears {} times", c),
+        })
+        .collect();
+    if config.normalize_keys {
+        out.extend(
+            crate::find_lookalikes(text)
+                .into_iter()
+                .map(|(key, like)| Issue {
+                    rule: "duplicate-key",
+                    message: crate::lookalike_message(&key, &like),
+                    key,
+                    severity: Severity::Error.as_str(),
+                }),
+        );
+    }
+    if let Some(base) = &base {
+        let keys = crate::scan_keys(&path, text)?;
+        let checks = ["missing-key", "extra-key"];
+        out.extend(
+            key_findings(&path, &keys, base, bp, &checks)
+                .into_iter()
+                .map(Issue::from),
+        );
+    }
+    let all: Vec<&Rule> = rules::RULES.iter().collect();
+    let (findings, _, err) = rules::lint_file(&all, &path, text, base.as_ref(), config, false);
+    if let Some(e) = err {
+        return Err(e);
+    }
+    out.extend(findings.into_iter().map(Issue::from));
+    Ok(out)
+}
+
+/// The locale file content `text` with its top-level keys in the order of
+/// `base`, then of the order file content `order`, as `--sort` writes it
+/// in `style`; `unknown` is an `--unknown-keys` value.
+pub fn sort(
+    text: &str,
+    base: Option<&str>,
+    order: Option<&str>,
+    unknown: &str,
+    style: &FmtStyle,
+) -> Result<String, String> {
+    let Value::Object(map) = serde_json::from_str(text).map_err(|e| format!("parse: {}", e))?
+    else {
+        return Err("root is not an object".to_string());
+    };
+    let base_keys = match base {
+        Some(b) => crate::keys_from_value(
+            &serde_json::from_str(b).map_err(|e| format!("parse base: {}", e))?,
+        ),
+        None => Vec::new(),
+    };
+    let patterns = order.map(order::parse).unwrap_or_default();
+    match order::sort_object(map, &base_keys, &patterns, Unknown::parse(unknown)) {
+        Ok((sorted, _)) => Ok(crate::json::to_string(&Value::Object(sorted), style)),
+        Err(rejected) => Err(format!("keys not in the base: {}", rejected.join(", "))),
+    }
+}
+
+/// Translation progress of one locale, counted over string values by
+/// dotted key.
+#[derive(Serialize)]
+pub struct Stats {
+    /// Strings in the locale.
+    pub keys: usize,
+    /// Base strings the locale has a non-empty translation for.
+    pub translated: usize,
+    /// Base strings the locale lacks.
+    pub missing: usize,
+    /// Base strings the locale has but leaves empty.
+    pub empty: usize,
+    /// Strings the base does not have.
+    pub extra: usize,
+    /// `translated` as a percentage of the base strings.
+    pub coverage: f64,
+}
+
+/// [`Stats`] of the locale file content `text` against the base file
+/// content `base`, or against itself when there is no base.
+pub fn stats(text: &str, base: Option<&str>) -> Result<Stats, String> {
+    let doc: Value = serde_json::from_str(text).map_err(|e| format!("parse: {}", e))?;
+    let base: Option<Value> = base
+        .map(|b| serde_json::from_str(b).map_err(|e| format!("parse base: {}", e)))
+        .transpose()?;
+    Ok(stats_of(&doc, base.as_ref()))
+}
+
+/// [`stats`] of parsed documents.
+pub fn stats_of(doc: &Value, base: Option<&Value>) -> Stats {
+    let entries: HashMap<String, &str> = rules::string_entries(doc).into_iter().collect();
+    let base_entries = rules::string_entries(base.unwrap_or(doc));
+    let known: HashSet<&str> = base_entries.iter().map(|(k, _)| k.as_str()).collect();
+    let mut stats = Stats {
+        keys: entries.len(),
+        translated: 0,
+        missing: 0,
+        empty: 0,
+        extra: entries
+            .keys()
+            .filter(|k| !known.contains(k.as_str()))
+            .count(),
+        coverage: 100.0,
+    };
+    for (k, _) in &base_entries {
+        match entries.get(k) {
+            None => stats.missing += 1,
+            Some(v) if v.trim().is_empty() => stats.empty += 1,
+            Some(_) => stats.translated += 1,
+        }
+    }
+    if !base_entries.is_empty() {
+        stats.coverage = stats.translated as f64 * 100.0 / base_entries.len() as f64;
+    }
+    stats
+}
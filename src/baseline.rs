@@ -0,0 +1,90 @@
+//! Coverage baseline of `check`: the base strings each locale had
+//! translated when the baseline was recorded. A locale regresses when it
+//! loses one of them while the base still has it; new base strings never
+//! count, so adding a key to `en.json` does not fail a PR while deleting a
+//! translation does.
+
+use crate::changelog::{self, Strings};
+use crate::layout;
+use crate::locales::LocaleSet;
+use crate::rules::{Finding, Severity};
+use crate::write;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Translated keys by locale, keyed like [`Strings`].
+pub type Baseline = BTreeMap<String, BTreeSet<String>>;
+
+pub fn load(path: &Path) -> Result<Baseline, String> {
+    let text = fs::read_to_string(path).map_err(|e| format!("read {}: {}", path.display(), e))?;
+    serde_json::from_str(&text).map_err(|e| format!("parse {}: {}", path.display(), e))
+}
+
+/// The base strings and the translated keys of every locale in `wanted`.
+pub fn current(
+    dir: &Path,
+    base: &Path,
+    wanted: &BTreeSet<String>,
+    locale_set: &mut LocaleSet,
+) -> Result<(Strings, Baseline), String> {
+    let (base_strings, locales) = changelog::locales(dir, base, locale_set)?;
+    let now = locales
+        .into_iter()
+        .filter(|(locale, _)| wanted.contains(locale))
+        .map(|(locale, own)| {
+            let done = base_strings
+                .keys()
+                .filter(|k| changelog::translated(&own, k))
+                .cloned()
+                .collect();
+            (locale, done)
+        })
+        .collect();
+    Ok((base_strings, now))
+}
+
+pub fn save(path: &Path, now: &Baseline) -> Result<(), String> {
+    let text = serde_json::to_string_pretty(now).map_err(|e| e.to_string())? + "\n";
+    write::write_file(path, &text)
+}
+
+/// The locale file a key of `locale` belongs in: `dir/zh.json`, or
+/// `dir/zh/<namespace>.json` when namespaced.
+fn file_of(dir: &Path, locale: &str, key: &str) -> PathBuf {
+    match key.split_once(':') {
+        Some((ns, _)) if layout::namespaced() => dir.join(locale).join(format!("{}.json", ns)),
+        _ => dir.join(format!("{}.json", locale)),
+    }
+}
+
+/// A `coverage-regression` finding for every key translated in `before`
+/// that the base still has and the locale no longer translates.
+pub fn regressions(dir: &Path, before: &Baseline, now: &Baseline, base: &Strings) -> Vec<Finding> {
+    let mut out = Vec::new();
+    for (locale, done) in now {
+        let Some(was) = before.get(locale) else {
+            continue;
+        };
+        for k in was
+            .iter()
+            .filter(|k| base.contains_key(*k) && !done.contains(*k))
+        {
+            let key = match k.split_once(':') {
+                Some((_, k)) if layout::namespaced() => k.to_string(),
+                _ => k.clone(),
+            };
+            out.push(Finding {
+                locale: locale.clone(),
+                ..Finding::new(
+                    &file_of(dir, locale, k),
+                    "coverage-regression",
+                    key,
+                    Severity::Error,
+                    "translated in the coverage baseline, now missing or empty".to_string(),
+                )
+            });
+        }
+    }
+    out
+}
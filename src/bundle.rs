@@ -0,0 +1,465 @@
+//! `bundle`: a zip archive handing the untranslated strings of one locale to
+//! an external translator.
+//!
+//! The archive holds `<locale>.json` (the strings to fill in, by dotted
+//! key), the base strings under the base file's name, `context.csv` with
+//! the descriptions and context URLs of those keys from the base's metadata
+//! sidecar, the configured glossary,
+//! `README.txt` with instructions and `manifest.json` recording what was
+//! handed off. `bundle import` merges the returned archive back.
+
+use crate::config::Config;
+use crate::locales::LocaleSet;
+use crate::meta;
+use crate::report;
+use crate::rules::{self, Check, Entry, Severity};
+use crate::write;
+use clap::ArgMatches;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::collections::HashMap;
+use std::fs;
+use std::io::{Cursor, Read, Write};
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+use zip::write::SimpleFileOptions;
+
+pub const MANIFEST: &str = "manifest.json";
+
+/// What a bundle hands off, for `bundle import` to check the returned files
+/// against.
+#[derive(Serialize, Deserialize)]
+pub struct Manifest {
+    pub locale: String,
+    /// File name of the base strings in the archive.
+    pub base: String,
+    /// Seconds since the Unix epoch.
+    pub created: u64,
+    /// Keys the locale lacks or leaves empty.
+    pub missing: Vec<String>,
+    /// Keys whose value is still the base text.
+    pub untranslated: Vec<String>,
+}
+
+/// The keys of `base` that `current` lacks, leaves empty or has not
+/// translated, in base order.
+pub fn pending(base: &Value, current: Option<&Value>) -> (Vec<String>, Vec<String>) {
+    let current: HashMap<String, &str> = current
+        .map(|c| rules::string_entries(c).into_iter().collect())
+        .unwrap_or_default();
+    let mut missing = Vec::new();
+    let mut untranslated = Vec::new();
+    for (k, en) in rules::string_entries(base) {
+        match current.get(&k) {
+            None => missing.push(k),
+            Some(v) if v.trim().is_empty() => missing.push(k),
+            Some(v) if *v == en && !en.trim().is_empty() => untranslated.push(k),
+            Some(_) => {}
+        }
+    }
+    (missing, untranslated)
+}
+
+const CONTEXT: &str = "context.csv";
+
+fn instructions(m: &Manifest, described: bool, glossary: Option<&str>) -> String {
+    let mut s = format!(
+        "Translation handoff: {locale}\n\
+         \n\
+         {locale}.json holds {n} strings to translate: {missing} not yet \
+         translated (empty) and {untranslated} still in the original language.\n\
+         {base} has the original text of each string under the same key.\n",
+        locale = m.locale,
+        n = m.missing.len() + m.untranslated.len(),
+        missing = m.missing.len(),
+        untranslated = m.untranslated.len(),
+        base = m.base,
+    );
+    if described {
+        s.push_str(&format!(
+            "{} describes where and how strings are used, with links to screenshots or docs.\n",
+            CONTEXT
+        ));
+    }
+    if let Some(g) = glossary {
+        s.push_str(&format!(
+            "{} lists how product terms are translated; please follow it.\n",
+            g
+        ));
+    }
+    s.push_str(
+        "\n\
+         - Only change the values in the locale file; keep every key as it is.\n\
+         - Keep placeholders such as {{name}} and %s exactly as in the original.\n\
+         - Leave a value empty if you are unsure of it.\n\
+         - Return this archive with the locale file filled in; do not rename it.\n",
+    );
+    s
+}
+
+fn archive(files: &[(String, Vec<u8>)]) -> Result<Vec<u8>, String> {
+    let mut zip = zip::ZipWriter::new(Cursor::new(Vec::new()));
+    for (name, contents) in files {
+        zip.start_file(name, SimpleFileOptions::default())
+            .and_then(|_| zip.write_all(contents).map_err(Into::into))
+            .map_err(|e| format!("{}: {}", name, e))?;
+    }
+    zip.finish()
+        .map(Cursor::into_inner)
+        .map_err(|e| e.to_string())
+}
+
+pub fn run(
+    dir: &Path,
+    base: &Path,
+    m: &ArgMatches,
+    config: &Config,
+    locale_set: &mut LocaleSet,
+) -> i32 {
+    if let Err(e) = crate::layout::require_flat("bundle") {
+        eprintln!("{}", e);
+        return 2;
+    }
+    let locale = m.get_one::<String>("locale").unwrap();
+    let output = m
+        .get_one::<String>("output")
+        .map(PathBuf::from)
+        .unwrap_or_else(|| PathBuf::from(format!("{}-handoff.zip", locale)));
+    let base_v = match locale_set.value(base) {
+        Ok(v) => v,
+        Err(e) => {
+            eprintln!("Failed to read {}: {}", base.display(), e);
+            return 2;
+        }
+    };
+    let path = dir.join(format!("{}.json", locale));
+    let current = if path.exists() {
+        match locale_set.value(&path) {
+            Ok(v) => Some(v),
+            Err(e) => {
+                report::error(&path, e);
+                return 2;
+            }
+        }
+    } else {
+        None
+    };
+    let (missing, untranslated) = pending(&base_v, current.as_deref());
+    if missing.is_empty() && untranslated.is_empty() {
+        report::info(format!("Nothing to hand off for {}", locale));
+        return 0;
+    }
+    let base_strings: HashMap<String, &str> = rules::string_entries(&base_v).into_iter().collect();
+    let current_strings: HashMap<String, &str> = current
+        .as_deref()
+        .map(|c| rules::string_entries(c).into_iter().collect())
+        .unwrap_or_default();
+    let metadata = match meta::load(base, locale_set) {
+        Ok(d) => d,
+        Err(e) => {
+            eprintln!("Failed to read key metadata: {}", e);
+            return 2;
+        }
+    };
+    let mut todo = Map::new();
+    let mut source = Map::new();
+    let mut context = String::from("key,source,description,context\n");
+    let mut described = false;
+    for k in missing.iter().chain(&untranslated) {
+        let value = current_strings.get(k).copied().unwrap_or("");
+        todo.insert(k.clone(), Value::String(value.trim().to_string()));
+        source.insert(k.clone(), Value::String(base_strings[k].to_string()));
+        if let Some(meta) = metadata.get(k) {
+            context.push_str(&format!(
+                "{},{},{},{}\n",
+                report::csv_field(k),
+                report::csv_field(base_strings[k]),
+                report::csv_field(&meta.description),
+                report::csv_field(&meta.context.join(" "))
+            ));
+            described = true;
+        }
+    }
+    let manifest = Manifest {
+        locale: locale.clone(),
+        base: base
+            .file_name()
+            .map(|n| n.to_string_lossy().into_owned())
+            .unwrap_or_else(|| "base.json".to_string()),
+        created: SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |d| d.as_secs()),
+        missing,
+        untranslated,
+    };
+    let json = |v: &Value| serde_json::to_string_pretty(v).unwrap().into_bytes();
+    let mut files = vec![
+        (format!("{}.json", locale), json(&Value::Object(todo))),
+        (manifest.base.clone(), json(&Value::Object(source))),
+    ];
+    if described {
+        files.push((CONTEXT.to_string(), context.into_bytes()));
+    }
+    let mut glossary_name = None;
+    if let Some(g) = &config.glossary {
+        match fs::read(g) {
+            Ok(bytes) => {
+                let ext = g.extension().and_then(|e| e.to_str()).unwrap_or("txt");
+                let name = format!("glossary.{}", ext);
+                files.push((name.clone(), bytes));
+                glossary_name = Some(name);
+            }
+            Err(e) => {
+                eprintln!("Failed to read glossary {}: {}", g.display(), e);
+                return 2;
+            }
+        }
+    }
+    files.push((
+        "README.txt".to_string(),
+        instructions(&manifest, described, glossary_name.as_deref()).into_bytes(),
+    ));
+    files.push((
+        MANIFEST.to_string(),
+        serde_json::to_string_pretty(&manifest)
+            .unwrap()
+            .into_bytes(),
+    ));
+    match archive(&files).and_then(|bytes| write::write_bytes(&output, &bytes)) {
+        Ok(()) => {
+            report::info(format!(
+                "{} {} missing and {} untranslated keys of {} into {}",
+                write::verb("Bundled", "bundle"),
+                manifest.missing.len(),
+                manifest.untranslated.len(),
+                locale,
+                output.display()
+            ));
+            0
+        }
+        Err(e) => {
+            eprintln!("Failed to write {}: {}", output.display(), e);
+            2
+        }
+    }
+}
+
+/// Rules a returned translation must pass to be merged.
+const IMPORT_RULES: &[&str] = &["placeholders", "printf"];
+
+fn read_entry(zip: &mut zip::ZipArchive<fs::File>, name: &str) -> Result<String, String> {
+    let mut s = String::new();
+    zip.by_name(name)
+        .map_err(|e| e.to_string())
+        .and_then(|mut f| f.read_to_string(&mut s).map_err(|e| e.to_string()))
+        .map_err(|e| format!("{}: {}", name, e))?;
+    Ok(s)
+}
+
+/// The manifest of the bundle at `path`.
+pub fn manifest(path: &Path) -> Result<Manifest, String> {
+    let mut zip = fs::File::open(path)
+        .map_err(|e| e.to_string())
+        .and_then(|f| zip::ZipArchive::new(f).map_err(|e| e.to_string()))
+        .map_err(|e| format!("{}: {}", path.display(), e))?;
+    serde_json::from_str(&read_entry(&mut zip, MANIFEST)?)
+        .map_err(|e| format!("parse {}: {}", MANIFEST, e))
+}
+
+fn read_map(zip: &mut zip::ZipArchive<fs::File>, name: &str) -> Result<Map<String, Value>, String> {
+    match serde_json::from_str(&read_entry(zip, name)?) {
+        Ok(Value::Object(map)) => Ok(map),
+        Ok(_) => Err(format!("{}: root is not an object", name)),
+        Err(e) => Err(format!("parse {}: {}", name, e)),
+    }
+}
+
+/// Sets the dotted `key` in `doc` to `value`, splitting the key where
+/// `base` nests objects so keys that contain dots stay whole.
+pub fn set_path(doc: &mut Value, base: &Value, key: &str, value: Value) {
+    let (Value::Object(map), Value::Object(base_map)) = (doc, base) else {
+        return;
+    };
+    if base_map.contains_key(key) {
+        map.insert(key.to_string(), value);
+        return;
+    }
+    for (k, child) in base_map {
+        if let Some(rest) = key
+            .strip_prefix(k.as_str())
+            .and_then(|r| r.strip_prefix('.'))
+            && child.is_object()
+        {
+            let slot = map
+                .entry(k.clone())
+                .or_insert_with(|| Value::Object(Map::new()));
+            if !slot.is_object() {
+                *slot = Value::Object(Map::new());
+            }
+            set_path(slot, child, rest, value);
+            return;
+        }
+    }
+}
+
+/// Why a returned value is not merged: `Err` for an invalid translation,
+/// `Ok(Some)` for one that is skipped, `Ok(None)` to accept it.
+fn review(
+    entry: &Entry,
+    value: &Value,
+    sent: Option<&Value>,
+) -> Result<Option<&'static str>, String> {
+    let Some(base) = entry.base else {
+        return Ok(Some("no longer in the base"));
+    };
+    let Some(v) = value.as_str() else {
+        return Err("not a string".to_string());
+    };
+    if v.trim().is_empty() {
+        return Ok(Some("left empty"));
+    }
+    if sent.and_then(Value::as_str).is_some_and(|s| s != base) {
+        return Ok(Some("base text changed since the bundle"));
+    }
+    if v == base {
+        return Ok(Some("unchanged from the base"));
+    }
+    let entry = Entry { value: v, ..*entry };
+    let problems: Vec<String> = IMPORT_RULES
+        .iter()
+        .filter_map(|id| rules::find_rule(id))
+        .filter_map(|r| match r.check {
+            Check::Value(check) => Some(check(&entry)),
+            _ => None,
+        })
+        .flatten()
+        .filter(|h| h.severity == Severity::Error)
+        .map(|h| h.message)
+        .collect();
+    if problems.is_empty() {
+        Ok(None)
+    } else {
+        Err(problems.join("; "))
+    }
+}
+
+fn print_entries(path: &Path, label: &str, entries: &[(String, String)]) {
+    if entries.is_empty() {
+        return;
+    }
+    report::header(path, label, entries.len());
+    for (k, why) in entries {
+        report::detail(format!("  {}: {}", k, why));
+    }
+}
+
+/// `bundle import`: merges the translations of a returned bundle that keep
+/// the handed-off keys and placeholders into the locale file, and reports
+/// the rest.
+pub fn import(
+    dir: &Path,
+    base: &Path,
+    m: &ArgMatches,
+    config: &Config,
+    locale_set: &mut LocaleSet,
+) -> i32 {
+    if let Err(e) = crate::layout::require_flat("bundle") {
+        eprintln!("{}", e);
+        return 2;
+    }
+    let archive_path = Path::new(m.get_one::<String>("archive").unwrap());
+    let opened = fs::File::open(archive_path)
+        .map_err(|e| e.to_string())
+        .and_then(|f| zip::ZipArchive::new(f).map_err(|e| e.to_string()))
+        .map_err(|e| format!("{}: {}", archive_path.display(), e))
+        .and_then(|mut zip| {
+            let manifest: Manifest = serde_json::from_str(&read_entry(&mut zip, MANIFEST)?)
+                .map_err(|e| format!("parse {}: {}", MANIFEST, e))?;
+            let returned = read_map(&mut zip, &format!("{}.json", manifest.locale))?;
+            let sent = read_map(&mut zip, &manifest.base)?;
+            Ok((manifest, returned, sent))
+        });
+    let (manifest, returned, sent) = match opened {
+        Ok(o) => o,
+        Err(e) => {
+            eprintln!("Invalid bundle: {}", e);
+            return 2;
+        }
+    };
+    let base_v = match locale_set.value(base) {
+        Ok(v) => v,
+        Err(e) => {
+            eprintln!("Failed to read {}: {}", base.display(), e);
+            return 2;
+        }
+    };
+    if let Err(e) = crate::layout::check_code(&manifest.locale) {
+        eprintln!("Invalid bundle: {}", e);
+        return 2;
+    }
+    let path = dir.join(format!("{}.json", manifest.locale));
+    let mut doc = if path.exists() {
+        match locale_set.value(&path) {
+            Ok(v) => (*v).clone(),
+            Err(e) => {
+                report::error(&path, e);
+                return 2;
+            }
+        }
+    } else {
+        Value::Object(Map::new())
+    };
+    let base_strings: HashMap<String, &str> = rules::string_entries(&base_v).into_iter().collect();
+    let handed: Vec<&String> = manifest
+        .missing
+        .iter()
+        .chain(&manifest.untranslated)
+        .collect();
+    let mut accepted = Vec::new();
+    let mut skipped = Vec::new();
+    let mut invalid: Vec<(String, String)> = returned
+        .keys()
+        .filter(|k| !handed.contains(k))
+        .map(|k| (k.clone(), "not a key that was handed off".to_string()))
+        .collect();
+    for k in handed {
+        let Some(value) = returned.get(k) else {
+            invalid.push((k.clone(), "missing from the returned file".to_string()));
+            continue;
+        };
+        let entry = Entry {
+            locale: &manifest.locale,
+            key: k,
+            value: "",
+            base: base_strings.get(k).copied(),
+            config,
+        };
+        match review(&entry, value, sent.get(k)) {
+            Ok(None) => accepted.push((k.clone(), value.clone())),
+            Ok(Some(why)) => skipped.push((k.clone(), why.to_string())),
+            Err(why) => invalid.push((k.clone(), why)),
+        }
+    }
+    let merged = accepted.len();
+    for (k, v) in accepted {
+        set_path(&mut doc, &base_v, &k, v);
+    }
+    print_entries(&path, "SKIPPED", &skipped);
+    print_entries(&path, "INVALID", &invalid);
+    if merged > 0 {
+        if let Err(e) = crate::write_json(&path, &doc, &config.fmt) {
+            report::error(&path, e);
+            return 2;
+        }
+        locale_set.forget(&path);
+    }
+    report::info(format!(
+        "{} {} translations into {}, {} skipped, {} invalid",
+        write::verb("Merged", "merge"),
+        merged,
+        path.display(),
+        skipped.len(),
+        invalid.len()
+    ));
+    if invalid.is_empty() { 0 } else { 1 }
+}
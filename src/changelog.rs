@@ -0,0 +1,309 @@
+//! `changelog`: base keys added, removed or reworded between two git refs,
+//! the list translators need for a release, and `release-notes`: that list
+//! rendered as a Markdown to-do for each locale.
+
+use crate::layout;
+use crate::locales::LocaleSet;
+use crate::report::{self, Format};
+use crate::rules;
+use crate::write;
+use clap::ArgMatches;
+use serde_json::Value;
+use std::collections::{BTreeMap, HashSet};
+use std::fmt::Write as _;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// Base strings by dotted key, as `namespace:key` when namespaced.
+pub type Strings = BTreeMap<String, String>;
+
+/// Stdout of `git <args>` run in `dir`.
+fn git(dir: &Path, args: &[&str]) -> Result<String, String> {
+    let out = Command::new("git")
+        .arg("-C")
+        .arg(dir)
+        .args(args)
+        .output()
+        .map_err(|e| format!("run git: {}", e))?;
+    if !out.status.success() {
+        return Err(String::from_utf8_lossy(&out.stderr).trim().to_string());
+    }
+    String::from_utf8(out.stdout).map_err(|e| e.to_string())
+}
+
+fn parent(path: &Path) -> &Path {
+    match path.parent() {
+        Some(p) if !p.as_os_str().is_empty() => p,
+        _ => Path::new("."),
+    }
+}
+
+/// The strings of the JSON file `name` in `dir` at `rev`.
+fn strings_at(dir: &Path, name: &str, rev: &str) -> Result<Vec<(String, String)>, String> {
+    let text = git(dir, &["show", &format!("{}:./{}", rev, name)])?;
+    let v: Value =
+        serde_json::from_str(&text).map_err(|e| format!("parse {} at {}: {}", name, rev, e))?;
+    Ok(rules::string_entries(&v)
+        .into_iter()
+        .map(|(k, s)| (k, s.to_string()))
+        .collect())
+}
+
+/// The base strings at `rev`. `base` is the base file, or the base locale
+/// directory when namespaced, in a git work tree.
+pub fn base_at(base: &Path, rev: &str) -> Result<Strings, String> {
+    if !layout::namespaced() {
+        let name = base.file_name().unwrap_or_default().to_string_lossy();
+        return Ok(strings_at(parent(base), &name, rev)?.into_iter().collect());
+    }
+    let mut out = Strings::new();
+    for name in git(base, &["ls-tree", "--name-only", rev, "./"])?.lines() {
+        let Some(ns) = name.strip_suffix(".json") else {
+            continue;
+        };
+        for (k, s) in strings_at(base, name, rev)? {
+            out.insert(format!("{}:{}", ns, k), s);
+        }
+    }
+    Ok(out)
+}
+
+/// How the base strings changed from one ref to another.
+#[derive(Default)]
+pub struct Changes {
+    /// New keys and their text.
+    pub added: Vec<(String, String)>,
+    /// Dropped keys and their last text.
+    pub removed: Vec<(String, String)>,
+    /// Keys whose text changed: old text, then new.
+    pub changed: Vec<(String, String, String)>,
+}
+
+pub fn diff(from: &Strings, to: &Strings) -> Changes {
+    let mut out = Changes::default();
+    for (k, new) in to {
+        match from.get(k) {
+            None => out.added.push((k.clone(), new.clone())),
+            Some(old) if old != new => out.changed.push((k.clone(), old.clone(), new.clone())),
+            Some(_) => {}
+        }
+    }
+    for (k, old) in from {
+        if !to.contains_key(k) {
+            out.removed.push((k.clone(), old.clone()));
+        }
+    }
+    out
+}
+
+/// A string as a JSON literal, so whitespace and newlines stay visible.
+pub fn quoted(s: &str) -> String {
+    Value::String(s.to_string()).to_string()
+}
+
+fn print(changes: &Changes, from: &str, to: &str) {
+    if report::style().format == Format::Csv {
+        println!("change,key,old,new");
+        for (k, new) in &changes.added {
+            println!("added,{},,{}", report::csv_field(k), report::csv_field(new));
+        }
+        for (k, old) in &changes.removed {
+            println!(
+                "removed,{},{},",
+                report::csv_field(k),
+                report::csv_field(old)
+            );
+        }
+        for (k, old, new) in &changes.changed {
+            println!(
+                "changed,{},{},{}",
+                report::csv_field(k),
+                report::csv_field(old),
+                report::csv_field(new)
+            );
+        }
+        return;
+    }
+    if !changes.added.is_empty() {
+        report::line(format!("ADDED ({}):", changes.added.len()));
+        for (k, new) in &changes.added {
+            report::detail(format!("  {}: {}", k, quoted(new)));
+        }
+    }
+    if !changes.removed.is_empty() {
+        report::line(format!("REMOVED ({}):", changes.removed.len()));
+        for (k, old) in &changes.removed {
+            report::detail(format!("  {}: {}", k, quoted(old)));
+        }
+    }
+    if !changes.changed.is_empty() {
+        report::line(format!("CHANGED ({}):", changes.changed.len()));
+        for (k, old, new) in &changes.changed {
+            report::detail(format!("  {}: {} -> {}", k, quoted(old), quoted(new)));
+        }
+    }
+    report::info(format!(
+        "{} added, {} removed, {} changed from {} to {}",
+        changes.added.len(),
+        changes.removed.len(),
+        changes.changed.len(),
+        from,
+        to
+    ));
+}
+
+/// The changes of the base between `m`'s `--from` and `--to`.
+fn changes(base: &Path, m: &ArgMatches) -> Result<Changes, String> {
+    let from = m.get_one::<String>("from").unwrap();
+    let to = m.get_one::<String>("to").unwrap();
+    let old = base_at(base, from)?;
+    Ok(diff(&old, &base_at(base, to)?))
+}
+
+pub fn run(base: &Path, m: &ArgMatches) -> i32 {
+    match changes(base, m) {
+        Ok(c) => print(
+            &c,
+            m.get_one::<String>("from").unwrap(),
+            m.get_one::<String>("to").unwrap(),
+        ),
+        Err(e) => {
+            eprintln!("Failed to read {} from git: {}", base.display(), e);
+            return 2;
+        }
+    }
+    0
+}
+
+/// Strings of the base and of every other locale in the work tree, keyed
+/// like [`Strings`].
+pub fn locales(
+    dir: &Path,
+    base: &Path,
+    locale_set: &mut LocaleSet,
+) -> Result<(Strings, BTreeMap<String, Strings>), String> {
+    let mut base_strings = Strings::new();
+    let mut out: BTreeMap<String, Strings> = BTreeMap::new();
+    for p in layout::files(dir) {
+        let v = locale_set
+            .value(&p)
+            .map_err(|e| format!("{}: {}", p.display(), e))?;
+        let strings = if layout::is_base(base, &p) {
+            &mut base_strings
+        } else {
+            out.entry(rules::locale_of(&p)).or_default()
+        };
+        let ns = layout::namespace_of(&p);
+        for (k, s) in rules::string_entries(&v) {
+            let key = match &ns {
+                Some(ns) => format!("{}:{}", ns, k),
+                None => k,
+            };
+            strings.insert(key, s.to_string());
+        }
+    }
+    Ok((base_strings, out))
+}
+
+pub fn translated(strings: &Strings, key: &str) -> bool {
+    strings.get(key).is_some_and(|s| !s.trim().is_empty())
+}
+
+/// The Markdown release notes of `locale`: how much is untranslated now,
+/// the new strings it still lacks and the reworded strings to review.
+fn notes(locale: &str, release: &str, changes: &Changes, base: &Strings, own: &Strings) -> String {
+    let missing = base.keys().filter(|k| !translated(own, k)).count();
+    let done = if base.is_empty() {
+        100.0
+    } else {
+        (base.len() - missing) as f64 * 100.0 / base.len() as f64
+    };
+    let added: Vec<&(String, String)> = changes
+        .added
+        .iter()
+        .filter(|(k, _)| !translated(own, k))
+        .collect();
+    let mut out = format!("# {}: translating {}\n\n", locale, release);
+    let _ = writeln!(
+        out,
+        "{} of {} strings are untranslated ({:.1}% done).",
+        missing,
+        base.len(),
+        done
+    );
+    if added.is_empty() && changes.changed.is_empty() {
+        out.push_str("\nNothing new to translate for this release.\n");
+        return out;
+    }
+    if !added.is_empty() {
+        let _ = writeln!(out, "\n## New strings ({})\n", added.len());
+        for (k, new) in added {
+            let _ = writeln!(out, "- `{}`: {}", k, quoted(new));
+        }
+    }
+    if !changes.changed.is_empty() {
+        let _ = writeln!(
+            out,
+            "\n## Changed English text ({})\n\nCheck that the translation still matches.\n",
+            changes.changed.len()
+        );
+        for (k, old, new) in &changes.changed {
+            let _ = writeln!(out, "- `{}`: {} → {}", k, quoted(old), quoted(new));
+        }
+    }
+    out
+}
+
+pub fn run_notes(dir: &Path, base: &Path, m: &ArgMatches, locale_set: &mut LocaleSet) -> i32 {
+    let changes = match changes(base, m) {
+        Ok(c) => c,
+        Err(e) => {
+            eprintln!("Failed to read {} from git: {}", base.display(), e);
+            return 2;
+        }
+    };
+    let (base_strings, mut all) = match locales(dir, base, locale_set) {
+        Ok(l) => l,
+        Err(e) => {
+            eprintln!("Failed to read {}", e);
+            return 2;
+        }
+    };
+    // Locales left out with --exclude/--only.
+    let included: HashSet<String> = crate::target_files(dir, None)
+        .iter()
+        .map(|p| rules::locale_of(p))
+        .collect();
+    all.retain(|l, _| included.contains(l));
+    let wanted = m.get_one::<String>("locale").unwrap();
+    if wanted != "all" {
+        all.retain(|l, _| l == wanted);
+        if all.is_empty() {
+            eprintln!("No locale {} in {}", wanted, dir.display());
+            return 2;
+        }
+    }
+    let release = m
+        .get_one::<String>("release")
+        .or(m.get_one::<String>("to"))
+        .unwrap();
+    let output = m.get_one::<String>("output").map(PathBuf::from);
+    for (locale, own) in &all {
+        let text = notes(locale, release, &changes, &base_strings, own);
+        let Some(out_dir) = &output else {
+            report::line(text);
+            continue;
+        };
+        let path = out_dir.join(format!("{}.md", locale));
+        if let Err(e) = write::write_creating_dirs(&path, &text) {
+            eprintln!("Failed to write {}: {}", path.display(), e);
+            return 2;
+        }
+        report::info(format!(
+            "{} {}",
+            write::verb("Wrote", "write"),
+            path.display()
+        ));
+    }
+    0
+}
@@ -0,0 +1,494 @@
+//! `check`: every configured check over every locale file in one pass, with
+//! one combined report and exit code.
+
+use crate::config::Config;
+use crate::locales::LocaleSet;
+use crate::report::{self, Thresholds};
+use crate::rules::{self, Finding, Fix, Rule, Severity};
+use crate::write;
+use crate::{api, baseline, copied, layout, meta, plugins, pretty, regional};
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::time::Instant;
+
+/// Checks that are not lint rules: id, severity and description.
+pub const CHECKS: &[(&str, Severity, &str)] = &[
+    (
+        "duplicate-key",
+        Severity::Error,
+        "Keys repeated in the same object (-k)",
+    ),
+    (
+        "missing-key",
+        Severity::Error,
+        "Keys of the base a locale lacks (-m)",
+    ),
+    (
+        "extra-key",
+        Severity::Warning,
+        "Keys a locale has that the base does not",
+    ),
+    (
+        "format",
+        Severity::Warning,
+        "Files not in the canonical fmt form",
+    ),
+    (
+        "regional-variant",
+        Severity::Warning,
+        "Keys a sibling regional locale has and this one lacks",
+    ),
+    (
+        "min-coverage",
+        Severity::Error,
+        "Locales completed below min-coverage (--min-coverage)",
+    ),
+    (
+        "coverage-regression",
+        Severity::Error,
+        "Strings translated in the coverage baseline and no longer",
+    ),
+    (
+        "copied-locale",
+        Severity::Warning,
+        "Locales mostly identical to the base (copy-threshold)",
+    ),
+    (
+        "meta-orphan",
+        Severity::Error,
+        "Descriptions in the base's .meta.json of keys it does not have",
+    ),
+    (
+        "meta-undocumented",
+        Severity::Warning,
+        "Base keys without a description in its .meta.json",
+    ),
+];
+
+/// The checks named in the config's `check` list, or all of them.
+fn selected(config: &Config) -> Result<(Vec<&'static str>, Vec<&'static Rule>), String> {
+    if config.check.is_empty() {
+        let checks = CHECKS.iter().map(|c| c.0).collect();
+        return Ok((checks, rules::RULES.iter().collect()));
+    }
+    let mut checks = Vec::new();
+    let mut lints = Vec::new();
+    for id in &config.check {
+        if let Some(c) = CHECKS.iter().find(|c| c.0 == id) {
+            checks.push(c.0);
+        } else if let Some(r) = rules::find_rule(id) {
+            lints.push(r);
+        } else {
+            return Err(format!("unknown check: {}", id));
+        }
+    }
+    Ok((checks, lints))
+}
+
+/// The checks of [`CHECKS`] that [`fix_file`] fixes, for `rules` to list.
+const FIXABLE_CHECKS: &[&str] = &["duplicate-key", "format"];
+
+/// What `--fix` makes of one file: a byte order mark stripped, the raw-text
+/// fixes, repeated keys collapsed to their last value, the value fixes and,
+/// with `format`, the canonical layout, which also sorts keys when the
+/// `fmt` style orders them. Returns the new text and what was fixed, or
+/// `None` when nothing changed.
+fn fix_file(
+    p: &Path,
+    text: &str,
+    base: Option<&Value>,
+    checks: &[&str],
+    lints: &[&Rule],
+    config: &Config,
+) -> Option<(String, Vec<String>)> {
+    let mut out = text.to_string();
+    let mut fixed = Vec::new();
+    if let Some(rest) = out.strip_prefix('\u{feff}') {
+        out = rest.to_string();
+        fixed.push("byte order mark".to_string());
+    }
+    for rule in lints {
+        if let Some(Fix::Text(f)) = rule.fix
+            && let Some(t) = f(&out)
+        {
+            out = t;
+            fixed.push(rule.id.to_string());
+        }
+    }
+    // Unparsable, the text fixes are all there is to keep.
+    if let Ok(mut doc) = serde_json::from_str::<Value>(&out) {
+        let mut rewrite = false;
+        let (dups, _) = crate::find_duplicates(p, &out);
+        if checks.contains(&"duplicate-key") && !dups.is_empty() {
+            fixed.push(format!("duplicate-key ({})", dups.len()));
+            rewrite = true;
+        }
+        for rule in lints
+            .iter()
+            .filter(|r| !matches!(r.fix, None | Some(Fix::Text(_))))
+        {
+            let keys = rules::apply_fixes(&[*rule], p, &mut doc, base, config);
+            if !keys.is_empty() {
+                fixed.push(format!("{} ({})", rule.id, keys.len()));
+                rewrite = true;
+            }
+        }
+        if checks.contains(&"format") {
+            // The base file orders itself.
+            let formatted = pretty::canonical(&doc, Some(base.unwrap_or(&doc)), &config.fmt);
+            if rewrite || formatted != out {
+                if !rewrite {
+                    fixed.push("format".to_string());
+                }
+                out = formatted;
+            }
+        } else if rewrite {
+            out = pretty::to_string(&doc, &config.fmt);
+        }
+    }
+    (out != text).then_some((out, fixed))
+}
+
+/// `--fix` over `files`, base files first so the others are fixed against
+/// the fixed base, before anything is checked. Unreadable files are left
+/// for the checks to report.
+fn fix_files(
+    files: &[PathBuf],
+    base_path: &Path,
+    checks: &[&str],
+    lints: &[&Rule],
+    config: &Config,
+    locale_set: &mut LocaleSet,
+) -> Result<(), String> {
+    let mut ordered: Vec<&PathBuf> = files.iter().collect();
+    ordered.sort_by_key(|p| !layout::is_base(base_path, p));
+    let (mut fixes, mut fixed_files) = (0, 0);
+    for p in ordered {
+        let Ok(text) = locale_set.text(p) else {
+            continue;
+        };
+        let base = if layout::is_base(base_path, p) {
+            None
+        } else {
+            locale_set.value(&layout::base_for(base_path, p)).ok()
+        };
+        let Some((out, fixed)) = fix_file(p, &text, base.as_deref(), checks, lints, config) else {
+            continue;
+        };
+        write::write_file(p, &out).map_err(|e| format!("{}: {}", p.display(), e))?;
+        locale_set.forget(p);
+        report::info(format!(
+            "{} {}: {}",
+            write::verb("Fixed", "fix"),
+            p.display(),
+            fixed.join(", ")
+        ));
+        fixes += fixed.len();
+        fixed_files += 1;
+    }
+    report::info(format!(
+        "{} {} fixes in {} files",
+        write::verb("Applied", "apply"),
+        fixes,
+        fixed_files
+    ));
+    Ok(())
+}
+
+pub fn run(
+    dir: &Path,
+    base_path: &Path,
+    config: &Config,
+    locale_set: &mut LocaleSet,
+    thresholds: &Thresholds,
+    update_baseline: bool,
+    fix: bool,
+) -> i32 {
+    let (checks, lints) = match selected(config) {
+        Ok(s) => s,
+        Err(e) => {
+            eprintln!("Invalid config: {}", e);
+            return 2;
+        }
+    };
+    let mut plugins = match &config.plugins {
+        Some(d) => match plugins::load(d) {
+            Ok(p) => p,
+            Err(e) => {
+                eprintln!("Invalid config: {}", e);
+                return 2;
+            }
+        },
+        None => Vec::new(),
+    };
+    let mut files = crate::target_files(dir, None);
+    let mut all = Vec::new();
+    if layout::namespaced() && checks.contains(&"missing-key") {
+        all.extend(crate::check_namespace_files(dir, base_path));
+        files.retain(|p| layout::base_for(base_path, p).exists());
+    }
+    if fix && let Err(e) = fix_files(&files, base_path, &checks, &lints, config, locale_set) {
+        eprintln!("Failed to write {}", e);
+        return 2;
+    }
+    let mut unreadable = 0;
+    // Findings across files, shown with the file they belong to.
+    let mut cross_file = Vec::new();
+    if checks.contains(&"regional-variant") {
+        let (found, failed) = regional::findings(dir, base_path, locale_set);
+        cross_file.extend(found);
+        unreadable += failed;
+    }
+    if checks.contains(&"copied-locale") {
+        let (found, failed) = copied::findings(dir, base_path, config, locale_set);
+        cross_file.extend(found);
+        unreadable += failed;
+    }
+    if checks.contains(&"meta-orphan") || checks.contains(&"meta-undocumented") {
+        let (found, failed) = meta::findings(dir, base_path, locale_set);
+        let found: Vec<Finding> = found
+            .into_iter()
+            .filter(|f| checks.contains(&f.rule))
+            .collect();
+        for group in found.chunk_by(|a, b| a.file == b.file) {
+            crate::print_findings(&group[0].file, "PROBLEMS", group);
+        }
+        all.extend(found);
+        unreadable += failed;
+    }
+    if let Some(path) = &config.coverage_baseline
+        && (update_baseline || checks.contains(&"coverage-regression"))
+    {
+        let locales = files
+            .iter()
+            .filter(|p| !layout::is_base(base_path, p))
+            .map(|p| rules::locale_of(p))
+            .collect();
+        let (base_strings, now) = match baseline::current(dir, base_path, &locales, locale_set) {
+            Ok(current) => current,
+            Err(e) => {
+                eprintln!("Failed to compute coverage for the baseline: {}", e);
+                return 2;
+            }
+        };
+        if update_baseline {
+            if let Err(e) = baseline::save(path, &now) {
+                eprintln!("Failed to write {}: {}", path.display(), e);
+                return 2;
+            }
+            report::info(format!(
+                "{} coverage baseline {}",
+                write::verb("Wrote", "write"),
+                path.display()
+            ));
+        } else {
+            match baseline::load(path) {
+                Ok(before) => {
+                    cross_file.extend(baseline::regressions(dir, &before, &now, &base_strings))
+                }
+                Err(e) => {
+                    eprintln!("Failed to read the coverage baseline: {}", e);
+                    return 2;
+                }
+            }
+        }
+    }
+    // Base strings translated and in all, per locale, for min-coverage.
+    let mut coverage: BTreeMap<String, (usize, usize)> = BTreeMap::new();
+    let checked = files.len();
+    report::progress_start(files.len());
+    for p in files {
+        let start = Instant::now();
+        report::progress_file(&p);
+        let text = match locale_set.text(&p) {
+            Ok(t) => t,
+            Err(e) => {
+                unreadable += 1;
+                report::error(&p, e);
+                report::progress_inc();
+                continue;
+            }
+        };
+        let mut findings = Vec::new();
+        let (dups, syntax) = crate::find_duplicates(&p, &text);
+        if checks.contains(&"duplicate-key") {
+            for (k, c) in dups {
+                findings.push(Finding::new(
+                    &p,
+                    "duplicate-key",
+                    k,
+                    Severity::Error,
+                    format!("key appears {} times", c),
+                ));
+            }
+            if config.normalize_keys {
+                for (k, like) in crate::find_lookalikes(&text) {
+                    findings.push(Finding::new(
+                        &p,
+                        "duplicate-key",
+                        k.clone(),
+                        Severity::Error,
+                        crate::lookalike_message(&k, &like),
+                    ));
+                }
+            }
+        }
+        let bp = layout::base_for(base_path, &p);
+        let base = if layout::is_base(base_path, &p) {
+            None
+        } else {
+            locale_set.value(&bp).ok()
+        };
+        if syntax.is_none()
+            && let Some(base) = &base
+            && let Ok(keys) = crate::scan_keys(&p, &text)
+        {
+            findings.extend(api::key_findings(&p, &keys, base, &bp, &checks));
+        }
+        if (config.min_coverage.is_some() || !config.min_coverage_locales.is_empty())
+            && let Some(base) = &base
+            && let Ok(v) = locale_set.value(&p)
+        {
+            let s = api::stats_of(&v, Some(base));
+            let c = coverage.entry(rules::locale_of(&p)).or_default();
+            c.0 += s.translated;
+            c.1 += s.translated + s.missing + s.empty;
+        }
+        let (lint_findings, _, err) =
+            rules::lint_file(&lints, &p, &text, base.as_deref(), config, false);
+        findings.extend(lint_findings);
+        let mut err = err;
+        if !plugins.is_empty()
+            && err.is_none()
+            && let Ok(v) = locale_set.value(&p)
+        {
+            match plugins::run(&mut plugins, &p, &v, base.as_deref()) {
+                Ok(found) => findings.extend(found),
+                Err(e) => err = Some(e),
+            }
+        }
+        if checks.contains(&"format")
+            && let Ok(v) = locale_set.value(&p)
+        {
+            let base_v = locale_set.value(&bp).ok();
+            if pretty::canonical(&v, base_v.as_deref(), &config.fmt) != *text {
+                findings.push(Finding::new(
+                    &p,
+                    "format",
+                    String::new(),
+                    Severity::Warning,
+                    "not in the canonical format (run fmt)".to_string(),
+                ));
+            }
+        }
+        let (here, rest): (Vec<Finding>, Vec<Finding>) =
+            cross_file.into_iter().partition(|f| f.file == p);
+        cross_file = rest;
+        findings.extend(here);
+        crate::print_findings(&p, "PROBLEMS", &findings);
+        if let Some(e) = syntax.or(err) {
+            unreadable += 1;
+            report::error(&p, e);
+        }
+        all.extend(findings);
+        report::timing(&p, start);
+        report::progress_inc();
+    }
+    report::progress_finish();
+    cross_file.extend(below_coverage(dir, &coverage, config));
+    // Namespaced locales are reported against their directory.
+    for f in cross_file {
+        crate::print_findings(&f.file, "PROBLEMS", std::slice::from_ref(&f));
+        all.push(f);
+    }
+    report::emit(&all);
+    let errors = all.iter().filter(|f| f.severity == Severity::Error).count();
+    report::headline(
+        checked,
+        &format!(
+            "{} errors and {} warnings in {} files",
+            errors,
+            all.len() - errors,
+            report::files_with(&all)
+        ),
+        unreadable,
+    );
+    if unreadable > 0 {
+        return 2;
+    }
+    thresholds.exit_code(errors, all.len() - errors)
+}
+
+/// A `min-coverage` finding for every locale completed below its own
+/// minimum, against its file, or its directory when namespaced.
+fn below_coverage(
+    dir: &Path,
+    coverage: &BTreeMap<String, (usize, usize)>,
+    config: &Config,
+) -> Vec<Finding> {
+    let mut out = Vec::new();
+    for (locale, &(translated, total)) in coverage {
+        let Some(min) = config.min_coverage_for(locale, &rules::language_of(locale)) else {
+            continue;
+        };
+        let percent = if total == 0 {
+            100.0
+        } else {
+            translated as f64 * 100.0 / total as f64
+        };
+        if percent >= min {
+            continue;
+        }
+        let at = if layout::namespaced() {
+            dir.join(locale)
+        } else {
+            dir.join(format!("{}.json", locale))
+        };
+        out.push(Finding {
+            locale: locale.clone(),
+            ..Finding::new(
+                &at,
+                "min-coverage",
+                String::new(),
+                Severity::Error,
+                format!(
+                    "{:.1}% translated ({} of {}), below {}%",
+                    percent, translated, total, min
+                ),
+            )
+        });
+    }
+    out
+}
+
+/// `rules`: every check and lint rule with its severity, whether `--fix`
+/// handles it, and what it looks for.
+pub fn list() -> i32 {
+    let mut rows: Vec<(&str, Severity, bool, &str)> = CHECKS
+        .iter()
+        .map(|&(id, severity, description)| {
+            (id, severity, FIXABLE_CHECKS.contains(&id), description)
+        })
+        .collect();
+    rows.extend(
+        rules::RULES
+            .iter()
+            .map(|r| (r.id, r.severity, r.fix.is_some(), r.description)),
+    );
+    let width = rows.iter().map(|r| r.0.len()).max().unwrap_or(0);
+    println!(
+        "{:width$}  {:8}  {:3}  DESCRIPTION",
+        "ID", "SEVERITY", "FIX"
+    );
+    for (id, severity, fixable, description) in rows {
+        println!(
+            "{:width$}  {:8}  {:3}  {}",
+            id,
+            severity.as_str(),
+            if fixable { "yes" } else { "no" },
+            description
+        );
+    }
+    0
+}
@@ -0,0 +1,296 @@
+//! `chrome`: the WebExtension `_locales/<locale>/messages.json` format, so
+//! the browser extension can share the translations kept here.
+//!
+//! Each string becomes a message named after its dotted key, with every
+//! character other than ASCII letters, digits and `_` replaced by `_`.
+//! `{{name}}` placeholders become `$name$` with a `placeholders` entry
+//! pointing at the substitution of the same position in the base string,
+//! so `chrome.i18n.getMessage` takes them in base order in every locale.
+//! The base's key descriptions become message descriptions. `chrome import`
+//! maps messages back through the base's keys.
+
+use crate::config::Config;
+use crate::interop::{self, placeholder_names, read_base};
+use crate::locales::LocaleSet;
+use crate::rules::{self, placeholders};
+use crate::{json, layout, meta, report, write};
+use clap::ArgMatches;
+use serde_json::{Map, Value, json};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+const MESSAGES: &str = "messages.json";
+
+/// The message name of a dotted key.
+fn message_name(key: &str) -> String {
+    key.chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect()
+}
+
+/// Chrome's locale directory name for a locale code: `zh_CN` for `zh-CN`.
+fn chrome_locale(locale: &str) -> String {
+    locale.replace('-', "_")
+}
+
+/// `value` as a message of `base` (the base string of the same key).
+fn message(value: &str, base: &str, description: Option<&str>) -> Value {
+    let mut order: Vec<&str> = Vec::new();
+    for name in placeholder_names(base)
+        .into_iter()
+        .chain(placeholder_names(value))
+    {
+        if !order.contains(&name) {
+            order.push(name);
+        }
+    }
+    let mut text = String::new();
+    let mut from = 0;
+    for (start, end) in placeholders(value) {
+        text.push_str(&value[from..start].replace('$', "$$"));
+        text.push('$');
+        text.push_str(&message_name(value[start + 2..end - 2].trim()));
+        text.push('$');
+        from = end;
+    }
+    text.push_str(&value[from..].replace('$', "$$"));
+
+    let mut out = Map::new();
+    out.insert("message".to_string(), Value::String(text));
+    if let Some(d) = description.filter(|d| !d.is_empty()) {
+        out.insert("description".to_string(), Value::String(d.to_string()));
+    }
+    let used = placeholder_names(value);
+    let slots: Map<String, Value> = order
+        .iter()
+        .enumerate()
+        .filter(|(_, name)| used.contains(name))
+        .map(|(i, name)| {
+            (
+                message_name(name),
+                json!({"content": format!("${}", i + 1)}),
+            )
+        })
+        .collect();
+    if !slots.is_empty() {
+        out.insert("placeholders".to_string(), Value::Object(slots));
+    }
+    Value::Object(out)
+}
+
+/// The string a message stands for, given the placeholders of the base
+/// string of its key. `None` when the message has no text.
+fn from_message(msg: &Value, base: &str) -> Option<String> {
+    let text = msg.get("message")?.as_str()?;
+    let names: HashMap<String, &str> = placeholder_names(base)
+        .into_iter()
+        .map(|n| (message_name(n).to_lowercase(), n))
+        .collect();
+    let mut out = String::new();
+    let mut rest = text;
+    while let Some(i) = rest.find('$') {
+        out.push_str(&rest[..i]);
+        rest = &rest[i + 1..];
+        if let Some(r) = rest.strip_prefix('$') {
+            out.push('$');
+            rest = r;
+            continue;
+        }
+        let name = rest
+            .find('$')
+            .map(|end| &rest[..end])
+            .filter(|n| n.chars().all(|c| c.is_ascii_alphanumeric() || c == '_'));
+        match name {
+            Some(n) => {
+                match names.get(&n.to_lowercase()) {
+                    Some(orig) => out.push_str(&format!("{{{{{}}}}}", orig)),
+                    None => out.push_str(&format!("${}$", n)),
+                }
+                rest = &rest[n.len() + 1..];
+            }
+            None => out.push('$'),
+        }
+    }
+    out.push_str(rest);
+    Some(out)
+}
+
+/// Keys of `base` whose message names collide, Chrome's names being case
+/// insensitive.
+fn clashes(base: &[(String, String)]) -> Vec<String> {
+    let mut seen: HashMap<String, &str> = HashMap::new();
+    let mut out = Vec::new();
+    for (k, _) in base {
+        if let Some(other) = seen.insert(message_name(k).to_lowercase(), k) {
+            out.push(format!("{} and {} are both {}", other, k, message_name(k)));
+        }
+    }
+    out
+}
+
+/// `chrome export <DIR>`: writes `<DIR>/<locale>/messages.json` for every
+/// locale, leaving out strings the locale lacks or has empty so Chrome
+/// falls back to the default locale.
+pub fn run_export(
+    dir: &Path,
+    base: &Path,
+    m: &ArgMatches,
+    config: &Config,
+    locale_set: &mut LocaleSet,
+) -> i32 {
+    if let Err(e) = layout::require_flat("chrome") {
+        eprintln!("{}", e);
+        return 2;
+    }
+    let out_dir = Path::new(m.get_one::<String>("dir").unwrap());
+    let (base_strings, metadata) = match read_base(base, locale_set)
+        .and_then(|b| meta::load(base, locale_set).map(|meta| (b, meta)))
+    {
+        Ok(loaded) => loaded,
+        Err(e) => {
+            eprintln!("Failed to read {}: {}", base.display(), e);
+            return 2;
+        }
+    };
+    let clashes = clashes(&base_strings);
+    if !clashes.is_empty() {
+        report::header(base, "MESSAGE NAME CLASH", clashes.len());
+        for c in &clashes {
+            report::detail(format!("  {}", c));
+        }
+        return 2;
+    }
+    let mut failed = 0;
+    let files = crate::target_files(dir, None);
+    for p in &files {
+        let v = match locale_set.value(p) {
+            Ok(v) => v,
+            Err(e) => {
+                report::error(p, e);
+                failed += 1;
+                continue;
+            }
+        };
+        let strings: HashMap<String, &str> = rules::string_entries(&v).into_iter().collect();
+        let mut messages = Map::new();
+        for (k, b) in &base_strings {
+            let Some(s) = strings.get(k).filter(|s| !s.is_empty()) else {
+                continue;
+            };
+            let description = metadata.get(k).map(|m| m.description.as_str());
+            messages.insert(message_name(k), message(s, b, description));
+        }
+        let target = out_dir
+            .join(chrome_locale(&layout::locale_of(p)))
+            .join(MESSAGES);
+        let note = format!(" ({} messages)", messages.len());
+        let text = json::to_string(&Value::Object(messages), &config.fmt);
+        if !interop::export(&target, &text, &note) {
+            failed += 1;
+        }
+    }
+    if failed > 0 { 2 } else { 0 }
+}
+
+/// `chrome import <DIR>`: merges the messages of every
+/// `<DIR>/<locale>/messages.json` into the locale files, mapping message
+/// names back to the base's keys. Messages of no base key are reported.
+pub fn run_import(
+    dir: &Path,
+    base: &Path,
+    m: &ArgMatches,
+    config: &Config,
+    locale_set: &mut LocaleSet,
+) -> i32 {
+    if let Err(e) = layout::require_flat("chrome") {
+        eprintln!("{}", e);
+        return 2;
+    }
+    let in_dir = Path::new(m.get_one::<String>("dir").unwrap());
+    let (base_v, base_strings) = match locale_set
+        .value(base)
+        .and_then(|v| read_base(base, locale_set).map(|s| (v, s)))
+    {
+        Ok(loaded) => loaded,
+        Err(e) => {
+            eprintln!("Failed to read {}: {}", base.display(), e);
+            return 2;
+        }
+    };
+    let by_name: HashMap<String, &(String, String)> = base_strings
+        .iter()
+        .map(|entry| (message_name(&entry.0).to_lowercase(), entry))
+        .collect();
+    let mut sources: Vec<PathBuf> = fs::read_dir(in_dir)
+        .map(|read| read.flatten().map(|e| e.path().join(MESSAGES)).collect())
+        .unwrap_or_default();
+    sources.retain(|p| p.is_file());
+    sources.sort();
+    if sources.is_empty() {
+        eprintln!("No <locale>/{} found in {}", MESSAGES, in_dir.display());
+        return 2;
+    }
+    let (mut failed, mut unknown_total) = (0, 0);
+    for src in &sources {
+        let chrome = src
+            .parent()
+            .and_then(|d| d.file_name())
+            .map(|n| n.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        let path = match layout::file_for_code(dir, &chrome) {
+            Ok(path) => path,
+            Err(e) => {
+                report::error(src, e);
+                failed += 1;
+                continue;
+            }
+        };
+        if layout::is_base(base, &path) {
+            report::detail(format!("Skipped {} (the base locale)", src.display()));
+            continue;
+        }
+        let messages = match fs::read_to_string(src)
+            .map_err(|e| e.to_string())
+            .and_then(|t| serde_json::from_str::<Value>(&t).map_err(|e| e.to_string()))
+        {
+            Ok(Value::Object(map)) => map,
+            Ok(_) => {
+                report::error(src, "root is not an object");
+                failed += 1;
+                continue;
+            }
+            Err(e) => {
+                report::error(src, e);
+                failed += 1;
+                continue;
+            }
+        };
+        let (mut values, mut unknown) = (Vec::new(), Vec::new());
+        for (name, msg) in &messages {
+            let Some((key, b)) = by_name.get(&name.to_lowercase()).copied() else {
+                unknown.push(name.clone());
+                continue;
+            };
+            match from_message(msg, b) {
+                Some(value) => values.push((key.clone(), value)),
+                None => unknown.push(name.clone()),
+            }
+        }
+        unknown_total += unknown.len();
+        interop::report_unknown(src, "UNKNOWN MESSAGES", &unknown);
+        match interop::merge(&path, &base_v, values, config, locale_set) {
+            Ok(merged) => report::info(format!(
+                "{} {} messages into {}",
+                write::verb("Merged", "merge"),
+                merged,
+                path.display()
+            )),
+            Err(e) => {
+                report::error(&path, e);
+                failed += 1;
+            }
+        }
+    }
+    interop::exit_code(failed, unknown_total)
+}
@@ -0,0 +1,317 @@
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+pub const DEFAULT_CONFIG: &str = "cvr-i18n.json";
+
+/// Project configuration, read from `cvr-i18n.json` in the working directory
+/// or the file given with `-c`.
+#[derive(Deserialize, Default)]
+#[serde(default, deny_unknown_fields, rename_all = "kebab-case")]
+pub struct Config {
+    /// Punctuation style per locale or language code, overriding the
+    /// built-in defaults.
+    pub punctuation: HashMap<String, PunctuationStyle>,
+    /// Full-width punctuation and CJK/Latin spacing style per locale or
+    /// language code.
+    pub cjk: HashMap<String, CjkStyle>,
+    /// How many `\n` line breaks a translation may differ from the base by.
+    pub newline_tolerance: usize,
+    /// Globs (relative to the locales directory) of files to skip.
+    pub exclude: Vec<String>,
+    /// Locale codes to leave out of checks, e.g. frozen or pseudo locales.
+    pub exclude_locales: Vec<String>,
+    /// The canonical form `fmt` rewrites locale files into.
+    pub fmt: FmtStyle,
+    /// Checks and lint rules `check` runs; all of them when empty.
+    pub check: Vec<String>,
+    /// Whether duplicate-key detection also reports keys that differ only
+    /// in Unicode normalization or invisible characters
+    /// (`--normalize-keys`).
+    pub normalize_keys: bool,
+    /// Whether the `escapes` rule reports `\uXXXX` escapes of printable
+    /// non-ASCII characters, which `--fix` writes as the characters.
+    pub unescape_unicode: bool,
+    /// What `--fix` does with `null` values.
+    pub null_fix: NullFix,
+    /// Markers the `markers` rule flags in values, instead of `TODO`,
+    /// `FIXME`, `XXX` and `__MISSING__`.
+    pub markers: Option<Vec<String>>,
+    /// Sequences the `repeated-punctuation` rule flags, instead of doubled
+    /// `?`, `!`, `,`, `;`, `.` and their full-width forms.
+    pub repeated_punctuation: Option<Vec<String>>,
+    /// Casing per locale or language code, then per key prefix
+    /// (`buttons` covers `buttons.save`), that the `casing` rule expects.
+    pub casing: HashMap<String, HashMap<String, Casing>>,
+    /// dayjs patterns per locale or language code that the `date-format`
+    /// rule accepts in place of the base's (`DD.MM.YYYY` for `de`).
+    pub date_formats: HashMap<String, Vec<String>>,
+    /// Globs of links a translation may point elsewhere for, e.g. its own
+    /// docs (`https://clash-verge-rev.github.io/*`), as long as the link it
+    /// uses matches the same glob.
+    pub localized_urls: Vec<String>,
+    /// Completion percentage below which `check` fails a locale
+    /// (`--min-coverage`).
+    pub min_coverage: Option<f64>,
+    /// Completion percentage per locale or language code, overriding
+    /// `min-coverage` for that locale.
+    pub min_coverage_locales: HashMap<String, f64>,
+    /// Translated keys per locale recorded with `check --update-baseline`;
+    /// `check` fails when a locale loses one of them.
+    pub coverage_baseline: Option<PathBuf>,
+    /// Percentage of strings identical to the base above which `--copied`
+    /// flags a locale as copied and never translated; 50 when unset.
+    pub copy_threshold: Option<f64>,
+    /// Size in bytes above which a locale file is refused; 16 MiB when
+    /// unset.
+    pub max_file_size: Option<u64>,
+    /// Nesting of objects and arrays beyond which a locale file is refused;
+    /// 64 when unset.
+    pub max_depth: Option<usize>,
+    /// Glossary file `bundle` includes for translators.
+    pub glossary: Option<PathBuf>,
+    /// Directory of WebAssembly plugins whose checks `check` also runs.
+    pub plugins: Option<PathBuf>,
+    /// When chat notifications of `--webhook` are worth posting.
+    pub notify: NotifyConfig,
+}
+
+#[derive(Deserialize, Clone)]
+#[serde(default, deny_unknown_fields, rename_all = "kebab-case")]
+pub struct NotifyConfig {
+    /// Where the last posted status is kept, to tell which missing keys are
+    /// new.
+    pub state: PathBuf,
+    /// Post only when a locale's completion moved by at least this many
+    /// percentage points...
+    pub min_change: Option<f64>,
+    /// ...or it gained at least this many missing keys. Without either
+    /// threshold every run posts.
+    pub min_new_missing: Option<usize>,
+    /// Where `--telegram` sends its messages.
+    pub telegram: TelegramConfig,
+}
+
+#[derive(Deserialize, Clone)]
+#[serde(default, deny_unknown_fields, rename_all = "kebab-case")]
+pub struct TelegramConfig {
+    /// Environment variable holding the bot token, kept out of the config.
+    pub token_env: String,
+    /// Bot API server, for a self-hosted one.
+    pub api_url: String,
+    /// Chat that gets the summary of every locale.
+    pub chat: Option<String>,
+    /// Chat per locale code that gets pinged about that locale's new
+    /// missing keys.
+    pub locales: HashMap<String, String>,
+}
+
+impl Default for TelegramConfig {
+    fn default() -> Self {
+        TelegramConfig {
+            token_env: "TELEGRAM_BOT_TOKEN".to_string(),
+            api_url: "https://api.telegram.org".to_string(),
+            chat: None,
+            locales: HashMap::new(),
+        }
+    }
+}
+
+impl Default for NotifyConfig {
+    fn default() -> Self {
+        NotifyConfig {
+            state: PathBuf::from(".cvr-i18n-notify.json"),
+            min_change: None,
+            min_new_missing: None,
+            telegram: TelegramConfig::default(),
+        }
+    }
+}
+
+#[derive(Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "kebab-case")]
+pub enum KeyOrder {
+    /// Leave keys where they are.
+    #[default]
+    Keep,
+    /// The base file's order, then other keys alphabetically.
+    Base,
+    /// Alphabetical at every level.
+    Alpha,
+}
+
+#[derive(Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "kebab-case")]
+pub enum NullFix {
+    /// Delete the key, so the missing-key check reports it.
+    #[default]
+    Remove,
+    /// Replace the value with an empty string.
+    Blank,
+}
+
+#[derive(Deserialize, Clone)]
+#[serde(default, deny_unknown_fields, rename_all = "kebab-case")]
+pub struct FmtStyle {
+    /// Spaces per indentation level.
+    pub indent: usize,
+    /// Write non-ASCII characters as `\uXXXX` escapes.
+    pub escape_unicode: bool,
+    /// Write `/` as `\/`, as some serializers do.
+    pub escape_slash: bool,
+    /// End the file with a newline.
+    pub final_newline: bool,
+    pub key_order: KeyOrder,
+    /// Indented, or on one line for shipping (`--output-style`).
+    pub output_style: OutputStyle,
+}
+
+#[derive(Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "kebab-case")]
+pub enum OutputStyle {
+    #[default]
+    Pretty,
+    /// No whitespace between tokens, as minified bundles ship.
+    Compact,
+}
+
+impl Default for FmtStyle {
+    fn default() -> Self {
+        FmtStyle {
+            indent: 2,
+            escape_unicode: false,
+            escape_slash: false,
+            final_newline: true,
+            key_order: KeyOrder::Keep,
+            output_style: OutputStyle::Pretty,
+        }
+    }
+}
+
+#[derive(Deserialize, Clone, Default)]
+#[serde(default, deny_unknown_fields)]
+pub struct PunctuationStyle {
+    /// Opening and closing double quotation marks, e.g. `["„", "“"]`.
+    pub quotes: Option<(String, String)>,
+    /// Apostrophe used inside words, `'` or `’`.
+    pub apostrophe: Option<String>,
+    /// Ellipsis spelling (`…`, `……` or `...`); three dots are normalized to it.
+    pub ellipsis: Option<String>,
+}
+
+#[derive(Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum Spacing {
+    Require,
+    Forbid,
+    Ignore,
+}
+
+#[derive(Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum Casing {
+    /// `Save Profile`: every word capitalized but short function words.
+    Title,
+    /// `Save profile`: only the first word capitalized.
+    Sentence,
+}
+
+#[derive(Deserialize, Clone, Copy)]
+#[serde(deny_unknown_fields)]
+pub struct CjkStyle {
+    /// Whether punctuation next to CJK text must be full-width.
+    pub fullwidth: bool,
+    /// Whether a space is required (or forbidden) between CJK and Latin text.
+    pub spacing: Spacing,
+}
+
+pub fn load(path: &Path) -> Result<Config, String> {
+    let s = fs::read_to_string(path).map_err(|e| format!("read {}: {}", path.display(), e))?;
+    serde_json::from_str(&s).map_err(|e| format!("parse {}: {}", path.display(), e))
+}
+
+fn builtin_punctuation(language: &str) -> Option<PunctuationStyle> {
+    let (quotes, ellipsis) = match language {
+        "de" => (Some(("„", "“")), None),
+        "fr" | "ru" | "uk" => (Some(("«", "»")), None),
+        "zh" => (None, Some("……")),
+        "ja" => (None, Some("…")),
+        _ => return None,
+    };
+    Some(PunctuationStyle {
+        quotes: quotes.map(|(o, c)| (o.to_string(), c.to_string())),
+        apostrophe: None,
+        ellipsis: ellipsis.map(str::to_string),
+    })
+}
+
+fn builtin_cjk(language: &str) -> Option<CjkStyle> {
+    match language {
+        "zh" => Some(CjkStyle {
+            fullwidth: true,
+            spacing: Spacing::Require,
+        }),
+        "ja" => Some(CjkStyle {
+            fullwidth: true,
+            spacing: Spacing::Ignore,
+        }),
+        _ => None,
+    }
+}
+
+impl Config {
+    /// Punctuation style for `locale`: an exact config entry, then the
+    /// language's config entry, then the built-in default.
+    pub fn punctuation_for(&self, locale: &str, language: &str) -> Option<PunctuationStyle> {
+        self.punctuation
+            .get(locale)
+            .or_else(|| self.punctuation.get(language))
+            .cloned()
+            .or_else(|| builtin_punctuation(language))
+    }
+
+    /// The date patterns `locale` may use instead of the base's: an exact
+    /// config entry, else the language's.
+    pub fn date_formats_for(&self, locale: &str, language: &str) -> &[String] {
+        self.date_formats
+            .get(locale)
+            .or_else(|| self.date_formats.get(language))
+            .map_or(&[], Vec::as_slice)
+    }
+
+    /// The completion `check` requires of `locale`: an exact config entry,
+    /// then the language's, then `min-coverage`.
+    pub fn min_coverage_for(&self, locale: &str, language: &str) -> Option<f64> {
+        self.min_coverage_locales
+            .get(locale)
+            .or_else(|| self.min_coverage_locales.get(language))
+            .copied()
+            .or(self.min_coverage)
+    }
+
+    /// The casing `key` must have in `locale`: from the exact config entry,
+    /// else the language's, the longest prefix of the key wins.
+    pub fn casing_for(&self, locale: &str, language: &str, key: &str) -> Option<Casing> {
+        let prefixes = self
+            .casing
+            .get(locale)
+            .or_else(|| self.casing.get(language))?;
+        prefixes
+            .iter()
+            .filter(|(p, _)| {
+                let p = p.trim_end_matches('.');
+                p.is_empty() || key == p || key.starts_with(&format!("{}.", p))
+            })
+            .max_by_key(|(p, _)| p.len())
+            .map(|(_, c)| *c)
+    }
+
+    pub fn cjk_for(&self, locale: &str, language: &str) -> Option<CjkStyle> {
+        self.cjk
+            .get(locale)
+            .or_else(|| self.cjk.get(language))
+            .copied()
+            .or_else(|| builtin_cjk(language))
+    }
+}
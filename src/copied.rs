@@ -0,0 +1,123 @@
+//! `--copied`: locales where most strings are still identical to the base,
+//! typically bootstrapped by copying `en.json` and never translated, so a
+//! maintainer can decide whether to ship or drop them.
+
+use crate::config::Config;
+use crate::layout;
+use crate::locales::LocaleSet;
+use crate::report::{self, Thresholds};
+use crate::rules::{self, Finding, Severity};
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+
+pub const DEFAULT_THRESHOLD: f64 = 50.0;
+
+/// Strings of one locale compared with the base.
+#[derive(Default)]
+struct Copies {
+    /// Non-empty base strings the locale has.
+    compared: usize,
+    /// Those still identical to the base.
+    identical: usize,
+}
+
+/// Locales over the config's threshold, reported against the locale file,
+/// or its directory when namespaced, and how many files could not be read.
+pub fn findings(
+    dir: &Path,
+    base: &Path,
+    config: &Config,
+    locale_set: &mut LocaleSet,
+) -> (Vec<Finding>, usize) {
+    let threshold = config.copy_threshold.unwrap_or(DEFAULT_THRESHOLD);
+    let mut by_locale: BTreeMap<PathBuf, Copies> = BTreeMap::new();
+    let mut unreadable = 0;
+    for p in crate::target_files(dir, None) {
+        if layout::is_base(base, &p) {
+            continue;
+        }
+        let bp = layout::base_for(base, &p);
+        if !bp.exists() {
+            // An extra namespace file: nothing to compare it with.
+            continue;
+        }
+        let (doc, base_v) = match (locale_set.value(&p), locale_set.value(&bp)) {
+            (Ok(d), Ok(b)) => (d, b),
+            (Err(e), _) | (_, Err(e)) => {
+                unreadable += 1;
+                report::error(&p, e);
+                continue;
+            }
+        };
+        let own: HashMap<String, &str> = rules::string_entries(&doc).into_iter().collect();
+        let at = if layout::namespaced() {
+            p.parent().unwrap_or(dir).to_path_buf()
+        } else {
+            p.clone()
+        };
+        let copies = by_locale.entry(at).or_default();
+        for (k, en) in rules::string_entries(&base_v) {
+            if en.trim().is_empty() {
+                continue;
+            }
+            if let Some(v) = own.get(&k) {
+                copies.compared += 1;
+                copies.identical += usize::from(*v == en);
+            }
+        }
+    }
+    let mut out = Vec::new();
+    for (at, c) in by_locale {
+        if c.compared == 0 {
+            continue;
+        }
+        let share = c.identical as f64 * 100.0 / c.compared as f64;
+        if share > threshold {
+            let locale = if layout::namespaced() {
+                at.file_name()
+                    .unwrap_or_default()
+                    .to_string_lossy()
+                    .into_owned()
+            } else {
+                rules::locale_of(&at)
+            };
+            out.push(Finding {
+                locale,
+                ..Finding::new(
+                    &at,
+                    "copied-locale",
+                    String::new(),
+                    Severity::Warning,
+                    format!(
+                        "{:.1}% of {} strings identical to the base (over {}%)",
+                        share, c.compared, threshold
+                    ),
+                )
+            });
+        }
+    }
+    (out, unreadable)
+}
+
+pub fn run(
+    dir: &Path,
+    base: &Path,
+    config: &Config,
+    locale_set: &mut LocaleSet,
+    thresholds: &Thresholds,
+) -> i32 {
+    let (found, unreadable) = findings(dir, base, config, locale_set);
+    for f in &found {
+        crate::print_findings(&f.file, "COPIED", std::slice::from_ref(f));
+    }
+    report::emit(&found);
+    report::headline(
+        crate::target_files(dir, None).len(),
+        &format!("{} locales mostly identical to the base", found.len()),
+        unreadable,
+    );
+    if unreadable > 0 {
+        return 2;
+    }
+    thresholds.exit_code(0, found.len())
+}
@@ -0,0 +1,113 @@
+//! `daemon`: running `check` every `--interval`, optionally after pulling
+//! the repository, and notifying only when the results changed. With
+//! `--metrics` the results of the last run are also served to Prometheus.
+
+use crate::config::Config;
+use crate::locales::LocaleSet;
+use crate::report::{self, Thresholds};
+use crate::{check, metrics, notify, stats};
+use clap::ArgMatches;
+use std::path::Path;
+use std::process::Command;
+use std::thread;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// `90`, `90s`, `15m`, `1h` or `1d` as a duration.
+fn parse_interval(s: &str) -> Result<Duration, String> {
+    let (n, unit) = match s.find(|c: char| !c.is_ascii_digit()) {
+        Some(i) => s.split_at(i),
+        None => (s, "s"),
+    };
+    let n: u64 = n.parse().map_err(|_| format!("invalid interval: {}", s))?;
+    let unit_secs = match unit {
+        "s" => 1,
+        "m" => 60,
+        "h" => 3600,
+        "d" => 86400,
+        _ => return Err(format!("invalid interval unit in {} (use s, m, h or d)", s)),
+    };
+    let secs = n
+        .checked_mul(unit_secs)
+        .ok_or_else(|| format!("interval too long: {}", s))?;
+    if secs == 0 {
+        return Err("interval must be positive".to_string());
+    }
+    Ok(Duration::from_secs(secs))
+}
+
+/// `git pull --ff-only` in the repository holding `dir`.
+fn pull(dir: &Path) -> Result<(), String> {
+    let out = Command::new("git")
+        .arg("-C")
+        .arg(dir)
+        .args(["pull", "--ff-only", "--quiet"])
+        .output()
+        .map_err(|e| format!("run git: {}", e))?;
+    if out.status.success() {
+        Ok(())
+    } else {
+        Err(String::from_utf8_lossy(&out.stderr).trim().to_string())
+    }
+}
+
+pub fn run(
+    dir: &Path,
+    base: &Path,
+    m: &ArgMatches,
+    config: &Config,
+    thresholds: &Thresholds,
+) -> i32 {
+    let interval = match parse_interval(m.get_one::<String>("interval").unwrap()) {
+        Ok(i) => i,
+        Err(e) => {
+            eprintln!("{}", e);
+            return 2;
+        }
+    };
+    let shared = metrics::Shared::default();
+    if let Some(addr) = m.get_one::<String>("metrics") {
+        if let Err(e) = metrics::serve(addr, shared.clone()) {
+            eprintln!("{}", e);
+            return 2;
+        }
+        report::info(format!("Serving metrics on http://{}/metrics", addr));
+    }
+    notify::only_on_change();
+    loop {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |d| d.as_secs());
+        report::info(format!("[{}] Running checks", stats::timestamp(now)));
+        if m.get_flag("pull")
+            && let Err(e) = pull(dir)
+        {
+            eprintln!("Failed to pull: {}", e);
+        }
+        let mut locale_set = LocaleSet::default();
+        let code = check::run(dir, base, config, &mut locale_set, thresholds, false, false);
+        if m.contains_id("metrics") {
+            *shared.lock().unwrap() = metrics::render(dir, base, code, now, &mut locale_set);
+        }
+        report::info(format!(
+            "Exit code {}, next run in {}",
+            code,
+            m.get_one::<String>("interval").unwrap()
+        ));
+        thread::sleep(interval);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn intervals_parse_without_overflow() {
+        assert_eq!(parse_interval("90"), Ok(Duration::from_secs(90)));
+        assert_eq!(parse_interval("2h"), Ok(Duration::from_secs(7200)));
+        assert!(parse_interval("99999999999999999d").is_err());
+        assert!(parse_interval("99999999999999999999").is_err());
+        assert!(parse_interval("0m").is_err());
+        assert!(parse_interval("5w").is_err());
+    }
+}
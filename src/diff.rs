@@ -0,0 +1,113 @@
+//! `--diff`: a key-level preview of what a command is about to write, so
+//! machine edits (sorting, `--fix`, `set`, `replace`) can be checked
+//! without reading a git diff of reformatted JSON. With `-v` the preview
+//! is shown while writing.
+
+use crate::changelog::quoted;
+use crate::report;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+/// Every leaf of `v` by dotted path, array elements included, in order.
+fn leaves(v: &Value, prefix: &str, out: &mut Vec<(String, String)>) {
+    let child = |k: &dyn std::fmt::Display| {
+        if prefix.is_empty() {
+            k.to_string()
+        } else {
+            format!("{}.{}", prefix, k)
+        }
+    };
+    match v {
+        Value::Object(map) => map.iter().for_each(|(k, v)| leaves(v, &child(k), out)),
+        Value::Array(items) => items
+            .iter()
+            .enumerate()
+            .for_each(|(i, v)| leaves(v, &child(&i), out)),
+        Value::String(s) => out.push((prefix.to_string(), quoted(s))),
+        other => out.push((prefix.to_string(), other.to_string())),
+    }
+}
+
+/// Indices into `seq` of a longest increasing subsequence: the keys that
+/// kept their relative order.
+fn longest_increasing(seq: &[usize]) -> Vec<usize> {
+    // tails[l]: index of the smallest tail of an increasing run of l + 1.
+    let mut tails: Vec<usize> = Vec::new();
+    let mut prev = vec![usize::MAX; seq.len()];
+    for (i, &x) in seq.iter().enumerate() {
+        let at = tails.partition_point(|&t| seq[t] < x);
+        if at > 0 {
+            prev[i] = tails[at - 1];
+        }
+        if at == tails.len() {
+            tails.push(i);
+        } else {
+            tails[at] = i;
+        }
+    }
+    let mut out = Vec::new();
+    let mut i = tails.last().copied().unwrap_or(usize::MAX);
+    while i != usize::MAX {
+        out.push(i);
+        i = prev[i];
+    }
+    out
+}
+
+/// Prints how the JSON file `path` changes if `new` replaces it. Files that
+/// are not JSON, before or after, are left out.
+pub fn show(path: &Path, new: &str) {
+    let old = match fs::read_to_string(path) {
+        Ok(text) => text,
+        Err(_) => "{}".to_string(),
+    };
+    let (Ok(old), Ok(new)) = (
+        serde_json::from_str::<Value>(old.trim_start_matches('\u{feff}')),
+        serde_json::from_str::<Value>(new),
+    ) else {
+        return;
+    };
+    let (mut before, mut after) = (Vec::new(), Vec::new());
+    leaves(&old, "", &mut before);
+    leaves(&new, "", &mut after);
+    let position: HashMap<&str, usize> = before
+        .iter()
+        .enumerate()
+        .map(|(i, (k, _))| (k.as_str(), i))
+        .collect();
+    let kept: Vec<usize> = after
+        .iter()
+        .filter_map(|(k, _)| position.get(k.as_str()).copied())
+        .collect();
+    let mut in_order = vec![false; kept.len()];
+    for i in longest_increasing(&kept) {
+        in_order[i] = true;
+    }
+    let mut lines = Vec::new();
+    let mut common = 0;
+    for (k, v) in &after {
+        let Some(&i) = position.get(k.as_str()) else {
+            lines.push(('+', format!("{}: {}", k, v)));
+            continue;
+        };
+        if before[i].1 != *v {
+            lines.push(('~', format!("{}: {} -> {}", k, before[i].1, v)));
+        } else if !in_order[common] {
+            lines.push(('>', format!("{} (moved)", k)));
+        }
+        common += 1;
+    }
+    let now: HashMap<&str, ()> = after.iter().map(|(k, _)| (k.as_str(), ())).collect();
+    for (k, v) in before.iter().filter(|(k, _)| !now.contains_key(k.as_str())) {
+        lines.push(('-', format!("{}: {}", k, v)));
+    }
+    if lines.is_empty() {
+        return;
+    }
+    report::header(path, "DIFF", lines.len());
+    for (sign, line) in lines {
+        report::change(sign, line);
+    }
+}
@@ -0,0 +1,487 @@
+//! `show`: one key in every locale side by side, for reviewing a string's
+//! translations, and `set` and `remove`: changing one value from the
+//! command line. Keys are dotted paths or JSON pointers, with array
+//! indices (`settings.dns.fallback.0`, `/settings/dns/fallback/0`).
+
+use crate::changelog;
+use crate::config::{Config, OutputStyle};
+use crate::layout;
+use crate::locales::LocaleSet;
+use crate::report::{self, Format};
+use crate::scan::{self, Literal};
+use crate::{pretty, rules, write};
+use clap::ArgMatches;
+use serde_json::{Map, Value};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+/// `KEY` as the dotted path the locale files are scanned by: a JSON
+/// pointer (`/settings/dns/fallback/0`) becomes `settings.dns.fallback.0`.
+/// A namespace prefix stays in front of either form.
+fn key_path(arg: &str) -> String {
+    let (ns, path) = match arg.split_once(':') {
+        Some((ns, p)) if layout::namespaced() => (Some(ns), p),
+        _ => (None, arg),
+    };
+    let path = match path.strip_prefix('/') {
+        Some(pointer) => pointer
+            .split('/')
+            .map(|s| s.replace("~1", "/").replace("~0", "~"))
+            .collect::<Vec<_>>()
+            .join("."),
+        None => path.to_string(),
+    };
+    match ns {
+        Some(ns) => format!("{}:{}", ns, path),
+        None => path,
+    }
+}
+
+/// The string literal at `key` in `text`, array elements included. The
+/// last of repeated keys is the one that counts.
+fn string_at<'a>(text: &'a str, key: &str) -> Option<Literal<'a>> {
+    scan::literals(text)
+        .filter(|l| !l.is_key && l.path == key)
+        .last()
+}
+
+pub fn run_show(dir: &Path, base: &Path, m: &ArgMatches, locale_set: &mut LocaleSet) -> i32 {
+    let key = key_path(m.get_one::<String>("key").unwrap());
+    let (ns, inner) = match key.split_once(':') {
+        Some((ns, k)) if layout::namespaced() => (Some(ns.to_string()), k),
+        _ => (None, key.as_str()),
+    };
+    let mut base_value = None;
+    let mut locales: BTreeMap<String, Option<String>> = BTreeMap::new();
+    for p in layout::files(dir) {
+        let text = match locale_set.text(&p) {
+            Ok(t) => t,
+            Err(e) => {
+                eprintln!("Failed to read {}: {}", p.display(), e);
+                return 2;
+            }
+        };
+        let value = string_at(&text, inner)
+            .filter(|_| layout::namespace_of(&p) == ns)
+            .map(|l| scan::unescape(l.raw));
+        if layout::is_base(base, &p) {
+            base_value = base_value.or(value);
+        } else {
+            // A locale without the key's namespace file still gets a row.
+            let slot = locales.entry(rules::locale_of(&p)).or_default();
+            if value.is_some() {
+                *slot = value;
+            }
+        }
+    }
+    let base_locale = base.file_stem().unwrap_or_default().to_string_lossy();
+    let rows: Vec<(&str, Option<&String>)> = std::iter::once((&*base_locale, base_value.as_ref()))
+        .chain(locales.iter().map(|(l, v)| (l.as_str(), v.as_ref())))
+        .collect();
+    if rows.iter().all(|(_, v)| v.is_none()) {
+        eprintln!("No locale has {}", key);
+        return 2;
+    }
+    if report::style().format == Format::Csv {
+        println!("locale,state,value");
+        for (locale, v) in &rows {
+            let state = match v {
+                None => "missing",
+                Some(v) if v.trim().is_empty() => "empty",
+                Some(_) => "ok",
+            };
+            println!(
+                "{},{},{}",
+                report::csv_field(locale),
+                state,
+                report::csv_field(v.map_or("", String::as_str))
+            );
+        }
+        return 0;
+    }
+    let width = rows.iter().map(|r| r.0.len()).max().unwrap_or(0);
+    for (locale, v) in &rows {
+        let value = match v {
+            Some(v) => changelog::quoted(v),
+            None => "(missing)".to_string(),
+        };
+        report::line(format!("{:width$}  {}", locale, value));
+    }
+    let missing = rows.iter().filter(|(_, v)| v.is_none()).count();
+    report::info(format!(
+        "{} in {} of {} locales",
+        key,
+        rows.len() - missing,
+        rows.len()
+    ));
+    0
+}
+
+/// The file of `locale` that holds `key`, and the key within it: `ns:key`
+/// names the namespace file when namespaced.
+fn locate(dir: &Path, locale: &str, key: &str) -> Result<(PathBuf, String), String> {
+    if !layout::namespaced() {
+        return Ok((dir.join(format!("{}.json", locale)), key.to_string()));
+    }
+    match key.split_once(':') {
+        Some((ns, k)) => Ok((dir.join(locale).join(format!("{}.json", ns)), k.to_string())),
+        None => Err(format!(
+            "{} has no namespace (write it as namespace:key)",
+            key
+        )),
+    }
+}
+
+/// `value` as the content of a JSON string literal in `config`'s style.
+pub fn literal(value: &str, config: &Config) -> String {
+    let quoted = pretty::to_string(&Value::String(value.to_string()), &config.fmt);
+    let quoted = quoted.trim_end();
+    quoted[1..quoted.len() - 1].to_string()
+}
+
+/// Where a new key goes in `map`: after the nearest key preceding it in the
+/// base object that `map` has, else before the nearest following one.
+fn position(map: &Map<String, Value>, key: &str, base: Option<&Value>) -> usize {
+    let Some(base) = base.and_then(Value::as_object) else {
+        return map.len();
+    };
+    let order: Vec<&String> = base.keys().collect();
+    let Some(at) = order.iter().position(|k| *k == key) else {
+        return map.len();
+    };
+    let index = |k: &&String| map.keys().position(|m| m == *k);
+    if let Some(i) = order[..at].iter().rev().find_map(index) {
+        return i + 1;
+    }
+    order[at + 1..].iter().find_map(index).unwrap_or(map.len())
+}
+
+/// A member [`insert`] added: its object's dotted path, its name and
+/// value, and the names of the members now either side of it.
+struct Added {
+    parent: String,
+    name: String,
+    value: Value,
+    before: Option<String>,
+    after: Option<String>,
+}
+
+/// Adds the string `key` to `map`, inside the objects its dotted path
+/// already has, as nested objects or, in a file of flat dotted keys, as
+/// one key.
+fn insert(
+    map: &mut Map<String, Value>,
+    key: &str,
+    value: &str,
+    base: Option<&Value>,
+) -> Result<Added, String> {
+    for (i, _) in key.match_indices('.') {
+        let (head, rest) = (&key[..i], &key[i + 1..]);
+        if let Some(Value::Object(inner)) = map.get_mut(head) {
+            let mut added = insert(inner, rest, value, base.and_then(|b| b.get(head)))?;
+            added.parent = match added.parent.as_str() {
+                "" => head.to_string(),
+                p => format!("{}.{}", head, p),
+            };
+            return Ok(added);
+        }
+    }
+    if map.contains_key(key) {
+        return Err(format!("{} is not a string", key));
+    }
+    let flat = map.keys().any(|k| k.contains('.'));
+    let (name, child) = match key.split_once('.') {
+        // An array or a string is in the way.
+        Some((head, _)) if !flat && map.contains_key(head) => {
+            return Err(format!("{} is not an object", head));
+        }
+        Some((head, rest)) if !flat => {
+            // New containers are objects; where the base has an array, an
+            // object keyed `0`, `1`… would change the locale's shape.
+            let base_inner = base.and_then(|b| b.get(head));
+            if base_inner.is_some_and(Value::is_array) {
+                return Err(format!(
+                    "{} is an array in the base, and --create does not add array elements",
+                    head
+                ));
+            }
+            let mut inner = Map::new();
+            insert(&mut inner, rest, value, base_inner)?;
+            (head, Value::Object(inner))
+        }
+        _ => (key, Value::String(value.to_string())),
+    };
+    let at = position(map, name, base);
+    map.shift_insert(at, name.to_string(), child.clone());
+    let neighbour = |i: usize| map.keys().nth(i).cloned();
+    Ok(Added {
+        parent: String::new(),
+        name: name.to_string(),
+        value: child,
+        before: at.checked_sub(1).and_then(neighbour),
+        after: neighbour(at + 1),
+    })
+}
+
+/// The byte range of the value of the member whose key is `k`.
+fn value_span(text: &str, k: &Literal) -> Option<(usize, usize)> {
+    let after = k.offset + k.raw.len() + 1;
+    let colon = after + text[after..].find(':')?;
+    let rest = &text[colon + 1..];
+    let value = text.len() - rest.trim_start().len();
+    // The value may be an object or array; the parser finds its end.
+    let mut values = serde_json::Deserializer::from_str(&text[value..]).into_iter::<Value>();
+    values.next()?.ok()?;
+    Some((value, value + values.byte_offset()))
+}
+
+/// `text` with the member `added` written next to a neighbour, copying the
+/// neighbour's indentation and colon spacing so no other line changes.
+/// `None` when the object had no other member to copy them from.
+fn splice(text: &str, added: &Added, config: &Config) -> Option<String> {
+    let key_at = |name: &str| {
+        let path = match added.parent.as_str() {
+            "" => name.to_string(),
+            p => format!("{}.{}", p, name),
+        };
+        scan::literals(text)
+            .filter(|l| l.is_key && l.path == path)
+            .last()
+    };
+    let (neighbour, after_it) = match (&added.before, &added.after) {
+        (Some(b), _) => (key_at(b)?, true),
+        (None, Some(a)) => (key_at(a)?, false),
+        (None, None) => return None,
+    };
+    let start = neighbour.offset - 1;
+    let (value_start, value_end) = value_span(text, &neighbour)?;
+    let gap = &text[text[..start].trim_end().len()..start];
+    let colon = &text[neighbour.offset + neighbour.raw.len() + 1..value_start];
+    let indent = gap.rsplit('\n').next().unwrap_or("");
+    // A neighbour on the same line means a compact file.
+    let mut style = config.fmt.clone();
+    if !gap.contains('\n') {
+        style.output_style = OutputStyle::Compact;
+    }
+    let value = pretty::to_string(&added.value, &style)
+        .trim_end()
+        .replace('\n', &format!("\n{}", indent));
+    let member = format!("\"{}\"{}{}", literal(&added.name, config), colon, value);
+    Some(if after_it {
+        format!(
+            "{},{}{}{}",
+            &text[..value_end],
+            gap,
+            member,
+            &text[value_end..]
+        )
+    } else {
+        format!("{}{},{}{}", &text[..start], member, gap, &text[start..])
+    })
+}
+
+/// The new text of `path` with `key` set to `value`, and whether the key
+/// was added; `None` when it already has that value.
+fn set_in(
+    path: &Path,
+    base: &Path,
+    key: &str,
+    value: &str,
+    create: bool,
+    config: &Config,
+    locale_set: &mut LocaleSet,
+) -> Result<Option<(String, bool)>, String> {
+    let text = locale_set.text(path)?;
+    let found = string_at(&text, key).map(|l| (l.offset, l.raw));
+    if let Some((offset, raw)) = found {
+        if scan::unescape(raw) == value {
+            return Ok(None);
+        }
+        // Splicing the literal leaves the rest of the file as it was.
+        let out = format!(
+            "{}{}{}",
+            &text[..offset],
+            literal(value, config),
+            &text[offset + raw.len()..]
+        );
+        return Ok(Some((out, false)));
+    }
+    if !create {
+        return Err(format!("no string {} (add it with --create)", key));
+    }
+    let mut doc = (*locale_set.value(path)?).clone();
+    let Value::Object(map) = &mut doc else {
+        return Err("root is not an object".to_string());
+    };
+    let base_v = locale_set.value(&layout::base_for(base, path)).ok();
+    let added = insert(map, key, value, base_v.as_deref())?;
+    // Only an object with no other member is written out whole.
+    let out = splice(&text, &added, config).unwrap_or_else(|| pretty::to_string(&doc, &config.fmt));
+    Ok(Some((out, true)))
+}
+
+pub fn run_set(
+    dir: &Path,
+    base: &Path,
+    m: &ArgMatches,
+    config: &Config,
+    locale_set: &mut LocaleSet,
+) -> i32 {
+    let locale = m.get_one::<String>("locale").unwrap();
+    let key = &key_path(m.get_one::<String>("key").unwrap());
+    let value = m.get_one::<String>("value").unwrap();
+    let (path, inner) = match locate(dir, locale, key) {
+        Ok(l) => l,
+        Err(e) => {
+            eprintln!("{}", e);
+            return 2;
+        }
+    };
+    if !path.exists() {
+        eprintln!(
+            "No locale file {} (create it with new-locale)",
+            path.display()
+        );
+        return 2;
+    }
+    match set_in(
+        &path,
+        base,
+        &inner,
+        value,
+        m.get_flag("create"),
+        config,
+        locale_set,
+    ) {
+        Ok(None) => {
+            report::info(format!(
+                "{} in {} is already {}",
+                key,
+                path.display(),
+                changelog::quoted(value)
+            ));
+            0
+        }
+        Ok(Some((text, added))) => {
+            if let Err(e) = write::write_file(&path, &text) {
+                eprintln!("Failed to write {}: {}", path.display(), e);
+                return 2;
+            }
+            let done = if added {
+                write::verb("Added", "add")
+            } else {
+                write::verb("Updated", "update")
+            };
+            report::info(format!("{} {} in {}", done, key, path.display()));
+            0
+        }
+        Err(e) => {
+            report::error(&path, e);
+            2
+        }
+    }
+}
+
+/// The byte range of the member `key` in `text`, or of the string element
+/// when `key` indexes an array, with the comma separating it from its
+/// neighbours, so cutting it leaves the rest laid out as before.
+fn member_span(text: &str, key: &str) -> Option<(usize, usize)> {
+    let found: Vec<Literal> = scan::literals(text).filter(|l| l.path == key).collect();
+    let (start, end) = match found.iter().rev().find(|l| l.is_key) {
+        Some(k) => (k.offset - 1, value_span(text, k)?.1),
+        None => {
+            let v = found.last()?;
+            (v.offset - 1, v.offset + v.raw.len() + 1)
+        }
+    };
+    let before = text[..start].trim_end();
+    if let Some(next) = text[end..].trim_start().strip_prefix(',') {
+        // On one line (`["a", "b"]`) the space after the comma goes too.
+        let next = if text[before.len()..start].contains('\n') {
+            next
+        } else {
+            next.trim_start_matches([' ', '\t'])
+        };
+        Some((before.len(), text.len() - next.len()))
+    } else if before.ends_with(',') {
+        Some((before.len() - 1, end))
+    } else {
+        Some((before.len(), end))
+    }
+}
+
+pub fn run_remove(dir: &Path, m: &ArgMatches, locale_set: &mut LocaleSet) -> i32 {
+    let locale = m.get_one::<String>("locale").unwrap();
+    let key = &key_path(m.get_one::<String>("key").unwrap());
+    let (path, inner) = match locate(dir, locale, key) {
+        Ok(l) => l,
+        Err(e) => {
+            eprintln!("{}", e);
+            return 2;
+        }
+    };
+    if !path.exists() {
+        eprintln!("No locale file {}", path.display());
+        return 2;
+    }
+    let text = match locale_set.text(&path) {
+        Ok(t) => t,
+        Err(e) => {
+            report::error(&path, e);
+            return 2;
+        }
+    };
+    let Some((start, end)) = member_span(&text, &inner) else {
+        report::error(&path, format!("no key {}", inner));
+        return 2;
+    };
+    let out = format!("{}{}", &text[..start], &text[end..]);
+    if let Err(e) = write::write_file(&path, &out) {
+        eprintln!("Failed to write {}: {}", path.display(), e);
+        return 2;
+    }
+    report::info(format!(
+        "{} {} from {}",
+        write::verb("Removed", "remove"),
+        key,
+        path.display()
+    ));
+    0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn add(text: &str, key: &str, base: &Value) -> Option<String> {
+        let Value::Object(mut map) = serde_json::from_str(text).unwrap() else {
+            panic!("not an object");
+        };
+        let added = insert(&mut map, key, "new", Some(base)).unwrap();
+        splice(text, &added, &Config::default())
+    }
+
+    #[test]
+    fn create_changes_only_the_new_lines() {
+        let base = json!({"a": {"x": "", "y": "", "z": ""}, "b": ""});
+        let text = "{\n    \"a\": {\n        \"x\" : \"1\",\n        \"z\" : \"3\"\n    },\n    \"b\": \"2\"\n}\n";
+        assert_eq!(
+            add(text, "a.y", &base).unwrap(),
+            "{\n    \"a\": {\n        \"x\" : \"1\",\n        \"y\" : \"new\",\n        \"z\" : \"3\"\n    },\n    \"b\": \"2\"\n}\n"
+        );
+        let text = "{\n  \"b\": \"2\"\n}\n";
+        assert_eq!(
+            add(text, "a.x", &base).unwrap(),
+            "{\n  \"a\": {\n    \"x\": \"new\"\n  },\n  \"b\": \"2\"\n}\n"
+        );
+        assert_eq!(add("{}", "b", &base), None);
+    }
+
+    #[test]
+    fn create_refuses_index_segments_of_base_arrays() {
+        let base = json!({"dns": {"fallback": ["a"]}});
+        let mut map = Map::new();
+        assert!(insert(&mut map, "dns.fallback.0", "x", Some(&base)).is_err());
+    }
+}
@@ -0,0 +1,270 @@
+//! The handoff directory: the `<locale>_missing.json` files `-m --export`
+//! writes (`<locale>_<namespace>_missing.json` when namespaced), listing
+//! the keys a locale lacks, and the `<locale>-handoff.zip` archives of
+//! `bundle`. `clean` removes them once they are no longer wanted, and
+//! `verify-export` checks them against the locale files.
+
+use crate::bundle;
+use crate::layout;
+use crate::locales::LocaleSet;
+use crate::report::{self, Thresholds};
+use crate::rules::{Finding, Severity};
+use crate::write;
+use clap::ArgMatches;
+use serde_json::Value;
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+const MISSING_SUFFIX: &str = "_missing.json";
+const BUNDLE_SUFFIX: &str = "-handoff.zip";
+
+/// The name of the missing-keys export of the locale file `p`.
+pub fn file_name(p: &Path) -> String {
+    match layout::namespace_of(p) {
+        Some(ns) => format!("{}_{}{}", layout::locale_of(p), ns, MISSING_SUFFIX),
+        None => format!("{}{}", layout::locale_of(p), MISSING_SUFFIX),
+    }
+}
+
+/// The missing-keys exports and bundles in `export_dir`, by name.
+fn artifacts(export_dir: &Path) -> Vec<PathBuf> {
+    let Ok(entries) = fs::read_dir(export_dir) else {
+        return Vec::new();
+    };
+    let mut out: Vec<PathBuf> = entries
+        .filter_map(|e| e.ok().map(|e| e.path()))
+        .filter(|p| {
+            let name = p.file_name().unwrap_or_default().to_string_lossy();
+            p.is_file() && (name.ends_with(MISSING_SUFFIX) || name.ends_with(BUNDLE_SUFFIX))
+        })
+        .collect();
+    out.sort();
+    out
+}
+
+/// The keys of a missing-keys export: a list, or with `--locations` an
+/// object by key.
+fn exported_keys(path: &Path) -> Result<Vec<String>, String> {
+    let text = fs::read_to_string(path).map_err(|e| format!("read {}: {}", path.display(), e))?;
+    match serde_json::from_str(&text) {
+        Ok(Value::Array(keys)) => Ok(keys
+            .into_iter()
+            .filter_map(|k| k.as_str().map(str::to_string))
+            .collect()),
+        Ok(Value::Object(map)) => Ok(map.keys().cloned().collect()),
+        Ok(_) => Err(format!("{}: not a list of keys", path.display())),
+        Err(e) => Err(format!("parse {}: {}", path.display(), e)),
+    }
+}
+
+/// The base keys the locale file `p` lacks now, as `-m` reports them.
+pub fn missing_now(
+    p: &Path,
+    base: &Path,
+    locale_set: &mut LocaleSet,
+) -> Result<Vec<String>, String> {
+    let keys = crate::scan_keys(p, &locale_set.text(p)?)?;
+    let base_v = locale_set.value(&layout::base_for(base, p))?;
+    Ok(crate::keys_from_value(&base_v)
+        .into_iter()
+        .filter(|k| !keys.contains(k))
+        .collect())
+}
+
+/// The keys handed off in the export or bundle `artifact`, and those of
+/// them the locale still has to translate. A locale no longer there has
+/// nothing left.
+fn handed_off(
+    dir: &Path,
+    base: &Path,
+    artifact: &Path,
+    locale_set: &mut LocaleSet,
+) -> Result<(Vec<String>, Vec<String>), String> {
+    let name = artifact.file_name().unwrap_or_default().to_string_lossy();
+    if name.ends_with(BUNDLE_SUFFIX) {
+        let manifest = bundle::manifest(artifact)?;
+        let handed: Vec<String> = manifest
+            .missing
+            .into_iter()
+            .chain(manifest.untranslated)
+            .collect();
+        let path = dir.join(format!("{}.json", manifest.locale));
+        if !path.exists() {
+            return Ok((handed, Vec::new()));
+        }
+        let base_v = locale_set.value(base)?;
+        let current = locale_set.value(&path)?;
+        let (missing, untranslated) = bundle::pending(&base_v, Some(&current));
+        let still = handed
+            .iter()
+            .filter(|k| missing.contains(k) || untranslated.contains(k))
+            .cloned()
+            .collect();
+        return Ok((handed, still));
+    }
+    let handed = exported_keys(artifact)?;
+    let Some(p) = crate::target_files(dir, None)
+        .into_iter()
+        .find(|p| file_name(p) == name)
+    else {
+        return Ok((handed, Vec::new()));
+    };
+    let now = missing_now(&p, base, locale_set)?;
+    let still = handed.iter().filter(|k| now.contains(k)).cloned().collect();
+    Ok((handed, still))
+}
+
+/// `clean`: removes the exports and bundles in the handoff directory or,
+/// with `--stale`, only those with no key still untranslated.
+pub fn run_clean(dir: &Path, base: &Path, m: &ArgMatches, locale_set: &mut LocaleSet) -> i32 {
+    let export_dir = Path::new(m.get_one::<String>("export_dir").unwrap());
+    if !export_dir.is_dir() {
+        eprintln!("Directory does not exist: {}", export_dir.display());
+        return 2;
+    }
+    let stale_only = m.get_flag("stale");
+    let (mut removed, mut kept, mut failed) = (0, 0, 0);
+    for a in artifacts(export_dir) {
+        if stale_only {
+            match handed_off(dir, base, &a, locale_set) {
+                Ok((_, still)) if !still.is_empty() => {
+                    kept += 1;
+                    report::detail(format!(
+                        "Kept {} ({} keys still untranslated)",
+                        a.display(),
+                        still.len()
+                    ));
+                    continue;
+                }
+                Ok(_) => {}
+                Err(e) => {
+                    report::error(&a, e);
+                    failed += 1;
+                    continue;
+                }
+            }
+        }
+        match write::remove(&a) {
+            Ok(()) => {
+                removed += 1;
+                report::info(format!(
+                    "{} {}",
+                    write::verb("Removed", "remove"),
+                    a.display()
+                ));
+            }
+            Err(e) => {
+                report::error(&a, e);
+                failed += 1;
+            }
+        }
+    }
+    report::info(format!(
+        "{} export files {}, {} kept",
+        removed,
+        if write::dry_run() {
+            "to remove"
+        } else {
+            "removed"
+        },
+        kept
+    ));
+    if failed > 0 { 2 } else { 0 }
+}
+
+/// `verify-export`: keys exported or bundled that have been translated
+/// since (`stale-export`), and keys locales lack that no export or bundle
+/// in the handoff directory lists (`unexported-key`).
+pub fn run_verify(
+    dir: &Path,
+    base: &Path,
+    m: &ArgMatches,
+    locale_set: &mut LocaleSet,
+    thresholds: &Thresholds,
+) -> i32 {
+    let export_dir = Path::new(m.get_one::<String>("export_dir").unwrap());
+    if !export_dir.is_dir() {
+        eprintln!("Directory does not exist: {}", export_dir.display());
+        return 2;
+    }
+    let mut found = Vec::new();
+    let mut unreadable = 0;
+    // File name of each export and bundle, with the keys it hands off.
+    let mut handed: Vec<(String, HashSet<String>)> = Vec::new();
+    let artifacts = artifacts(export_dir);
+    for a in &artifacts {
+        let (keys, still) = match handed_off(dir, base, a, locale_set) {
+            Ok(h) => h,
+            Err(e) => {
+                report::error(a, e);
+                unreadable += 1;
+                continue;
+            }
+        };
+        for k in keys.iter().filter(|k| !still.contains(k)) {
+            found.push(Finding::new(
+                a,
+                "stale-export",
+                k.clone(),
+                Severity::Error,
+                "translated since it was exported".to_string(),
+            ));
+        }
+        let name = a.file_name().unwrap_or_default().to_string_lossy();
+        handed.push((name.into_owned(), keys.into_iter().collect()));
+    }
+    let files: Vec<PathBuf> = crate::target_files(dir, None)
+        .into_iter()
+        .filter(|p| !layout::is_base(base, p))
+        .collect();
+    for p in &files {
+        let missing = match missing_now(p, base, locale_set) {
+            Ok(m) => m,
+            Err(e) => {
+                report::error(p, e);
+                unreadable += 1;
+                continue;
+            }
+        };
+        let own = [
+            file_name(p),
+            format!("{}{}", layout::locale_of(p), BUNDLE_SUFFIX),
+        ];
+        let covered = |k: &String| {
+            handed
+                .iter()
+                .any(|(name, keys)| own.contains(name) && keys.contains(k))
+        };
+        for k in missing.iter().filter(|k| !covered(k)) {
+            found.push(Finding::new(
+                p,
+                "unexported-key",
+                k.clone(),
+                Severity::Error,
+                format!(
+                    "missing, and no export in {} lists it",
+                    export_dir.display()
+                ),
+            ));
+        }
+    }
+    for group in found.chunk_by(|a, b| a.file == b.file) {
+        crate::print_findings(&group[0].file, "PROBLEMS", group);
+    }
+    report::emit(&found);
+    let stale = found.iter().filter(|f| f.rule == "stale-export").count();
+    report::headline(
+        artifacts.len() + files.len(),
+        &format!(
+            "{} stale and {} unexported keys",
+            stale,
+            found.len() - stale
+        ),
+        unreadable,
+    );
+    if unreadable > 0 {
+        return 2;
+    }
+    thresholds.exit_code(found.len(), 0)
+}
@@ -0,0 +1,51 @@
+//! Which locale files directory-wide commands look at.
+
+use crate::config::Config;
+use clap::ArgMatches;
+use globset::{Glob, GlobSet, GlobSetBuilder};
+use std::path::Path;
+use std::sync::OnceLock;
+
+static EXCLUDE: OnceLock<GlobSet> = OnceLock::new();
+static EXCLUDE_LOCALES: OnceLock<Vec<String>> = OnceLock::new();
+static ONLY_LOCALES: OnceLock<Vec<String>> = OnceLock::new();
+
+/// Builds the exclude set from `--exclude` and the config's `exclude` list,
+/// the skipped locales from `--exclude-locale` and `exclude-locales`, and
+/// the `--only` subset.
+pub fn init(m: &ArgMatches, config: &Config) -> Result<(), String> {
+    let only = m.get_many::<String>("only").into_iter().flatten();
+    let _ = ONLY_LOCALES.set(only.cloned().collect());
+    let locales = m.get_many::<String>("exclude_locale").into_iter().flatten();
+    let _ = EXCLUDE_LOCALES.set(locales.chain(&config.exclude_locales).cloned().collect());
+
+    let mut builder = GlobSetBuilder::new();
+    let cli = m.get_many::<String>("exclude").into_iter().flatten();
+    for pattern in cli.chain(&config.exclude) {
+        let glob = Glob::new(pattern).map_err(|e| format!("exclude {}: {}", pattern, e))?;
+        builder.add(glob);
+    }
+    let set = builder.build().map_err(|e| e.to_string())?;
+    let _ = EXCLUDE.set(set);
+    Ok(())
+}
+
+/// Whether `path` (inside `dir`) survives the exclude patterns, which match
+/// against the path relative to `dir`, the excluded locales and `--only`.
+pub fn included(dir: &Path, path: &Path) -> bool {
+    let locale = crate::rules::locale_of(path);
+    if ONLY_LOCALES
+        .get()
+        .is_some_and(|l| !l.is_empty() && !l.contains(&locale))
+    {
+        return false;
+    }
+    if EXCLUDE_LOCALES.get().is_some_and(|l| l.contains(&locale)) {
+        return false;
+    }
+    let Some(set) = EXCLUDE.get() else {
+        return true;
+    };
+    let rel = path.strip_prefix(dir).unwrap_or(path);
+    !set.is_match(rel)
+}
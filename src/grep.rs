@@ -0,0 +1,81 @@
+//! `grep`: keys and values matching a pattern across every locale, to find
+//! where a UI string is defined.
+
+use crate::changelog::{self, Strings};
+use crate::locales::LocaleSet;
+use crate::report::{self, Format};
+use clap::ArgMatches;
+use regex::{Regex, RegexBuilder};
+use std::path::Path;
+
+fn pattern(m: &ArgMatches) -> Result<Regex, regex::Error> {
+    let pattern = m.get_one::<String>("pattern").unwrap();
+    let pattern = if m.get_flag("fixed_strings") {
+        regex::escape(pattern)
+    } else {
+        pattern.clone()
+    };
+    RegexBuilder::new(&pattern)
+        .case_insensitive(m.get_flag("ignore_case"))
+        .build()
+}
+
+pub fn run(dir: &Path, base: &Path, m: &ArgMatches, locale_set: &mut LocaleSet) -> i32 {
+    let re = match pattern(m) {
+        Ok(re) => re,
+        Err(e) => {
+            eprintln!("Invalid pattern: {}", e);
+            return 2;
+        }
+    };
+    let (base_strings, mut locales) = match changelog::locales(dir, base, locale_set) {
+        Ok(l) => l,
+        Err(e) => {
+            eprintln!("Failed to read {}", e);
+            return 2;
+        }
+    };
+    let base_locale = base.file_stem().unwrap_or_default().to_string_lossy();
+    locales.insert(base_locale.into_owned(), base_strings);
+    if let Some(wanted) = m.get_many::<String>("locale") {
+        let wanted: Vec<&String> = wanted.collect();
+        locales.retain(|l, _| wanted.contains(&l));
+    }
+    // Neither flag searches both.
+    let keys = m.get_flag("keys") || !m.get_flag("values");
+    let values = m.get_flag("values") || !m.get_flag("keys");
+    let hits: Vec<(&String, &String, &String)> = locales
+        .iter()
+        .flat_map(|(locale, strings): (&String, &Strings)| {
+            strings.iter().map(move |(k, v)| (locale, k, v))
+        })
+        .filter(|(_, k, v)| (keys && re.is_match(k)) || (values && re.is_match(v)))
+        .collect();
+    if report::style().format == Format::Csv {
+        println!("locale,key,value");
+        for (locale, k, v) in &hits {
+            println!(
+                "{},{},{}",
+                report::csv_field(locale),
+                report::csv_field(k),
+                report::csv_field(v)
+            );
+        }
+    } else {
+        let width = hits.iter().map(|h| h.0.len()).max().unwrap_or(0);
+        for (locale, k, v) in &hits {
+            report::line(format!(
+                "{:width$}  {}: {}",
+                locale,
+                k,
+                changelog::quoted(v)
+            ));
+        }
+        report::info(format!(
+            "{} matches in {} locales",
+            hits.len(),
+            locales.len()
+        ));
+    }
+    if hits.is_empty() { 1 } else { 0 }
+}
@@ -0,0 +1,108 @@
+//! What the exports and imports of other tools' formats (`chrome`, `vscode`,
+//! `qt`, `php`) share: reading the base strings, placeholder names, writing
+//! into a new directory and merging imported strings into a locale file.
+
+use crate::config::Config;
+use crate::locales::LocaleSet;
+use crate::rules::{self, placeholders};
+use crate::{bundle, report, write};
+use serde_json::{Map, Value};
+use std::collections::HashMap;
+use std::path::Path;
+
+/// The string entries of the base file, by dotted key in order.
+pub fn read_base(base: &Path, locale_set: &mut LocaleSet) -> Result<Vec<(String, String)>, String> {
+    let v = locale_set.value(base)?;
+    Ok(rules::string_entries(&v)
+        .into_iter()
+        .map(|(k, s)| (k, s.to_string()))
+        .collect())
+}
+
+/// The inner names of the `{{name}}` placeholders of `s`, in order, once
+/// each.
+pub fn placeholder_names(s: &str) -> Vec<&str> {
+    let mut out = Vec::new();
+    for (start, end) in placeholders(s) {
+        let name = s[start + 2..end - 2].trim();
+        if !out.contains(&name) {
+            out.push(name);
+        }
+    }
+    out
+}
+
+/// Writes one exported file, creating its directory, and reports it.
+/// Returns whether it was written.
+pub fn export(target: &Path, text: &str, note: &str) -> bool {
+    match write::write_creating_dirs(target, text) {
+        Ok(()) => {
+            report::info(format!(
+                "{} {}{}",
+                write::verb("Wrote", "write"),
+                target.display(),
+                note
+            ));
+            true
+        }
+        Err(e) => {
+            report::error(target, e);
+            false
+        }
+    }
+}
+
+/// Lists the entries of an imported file that match no base key.
+pub fn report_unknown(src: &Path, label: &str, unknown: &[String]) {
+    if unknown.is_empty() {
+        return;
+    }
+    report::header(src, label, unknown.len());
+    for name in unknown {
+        report::detail(format!("  {}", name));
+    }
+}
+
+/// Sets `values` (dotted key, string) in the locale file `path`, created
+/// when missing, nesting new keys as in the base. Returns how many strings
+/// changed; the file is written only when some did.
+pub fn merge(
+    path: &Path,
+    base_v: &Value,
+    values: Vec<(String, String)>,
+    config: &Config,
+    locale_set: &mut LocaleSet,
+) -> Result<usize, String> {
+    let mut doc = if path.exists() {
+        (*locale_set.value(path)?).clone()
+    } else {
+        Value::Object(Map::new())
+    };
+    let current: HashMap<String, String> = rules::string_entries(&doc)
+        .into_iter()
+        .map(|(k, s)| (k, s.to_string()))
+        .collect();
+    let mut merged = 0;
+    for (key, value) in values {
+        if current.get(&key) != Some(&value) {
+            bundle::set_path(&mut doc, base_v, &key, Value::String(value));
+            merged += 1;
+        }
+    }
+    if merged > 0 {
+        crate::write_json(path, &doc, &config.fmt)?;
+        locale_set.forget(path);
+    }
+    Ok(merged)
+}
+
+/// 2 when any file failed, 1 when any was flagged, 0 otherwise.
+pub fn exit_code(failed: usize, flagged: usize) -> i32 {
+    if failed > 0 {
+        2
+    } else if flagged > 0 {
+        1
+    } else {
+        0
+    }
+}
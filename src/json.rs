@@ -0,0 +1,103 @@
+//! Serializing locale files in the configured style, shared by every
+//! command that writes one.
+
+use crate::config::{FmtStyle, OutputStyle};
+use serde::Serialize;
+use serde_json::Value;
+use serde_json::ser::{CompactFormatter, Formatter, PrettyFormatter};
+use std::io::{self, Write};
+
+/// serde_json's pretty or compact printer, optionally escaping non-ASCII
+/// characters and `/`.
+struct Canonical<F> {
+    inner: F,
+    escape_unicode: bool,
+    escape_slash: bool,
+}
+
+impl<F: Formatter> Formatter for Canonical<F> {
+    fn begin_array<W: ?Sized + Write>(&mut self, w: &mut W) -> io::Result<()> {
+        self.inner.begin_array(w)
+    }
+
+    fn end_array<W: ?Sized + Write>(&mut self, w: &mut W) -> io::Result<()> {
+        self.inner.end_array(w)
+    }
+
+    fn begin_array_value<W: ?Sized + Write>(&mut self, w: &mut W, first: bool) -> io::Result<()> {
+        self.inner.begin_array_value(w, first)
+    }
+
+    fn end_array_value<W: ?Sized + Write>(&mut self, w: &mut W) -> io::Result<()> {
+        self.inner.end_array_value(w)
+    }
+
+    fn begin_object<W: ?Sized + Write>(&mut self, w: &mut W) -> io::Result<()> {
+        self.inner.begin_object(w)
+    }
+
+    fn end_object<W: ?Sized + Write>(&mut self, w: &mut W) -> io::Result<()> {
+        self.inner.end_object(w)
+    }
+
+    fn begin_object_key<W: ?Sized + Write>(&mut self, w: &mut W, first: bool) -> io::Result<()> {
+        self.inner.begin_object_key(w, first)
+    }
+
+    fn begin_object_value<W: ?Sized + Write>(&mut self, w: &mut W) -> io::Result<()> {
+        self.inner.begin_object_value(w)
+    }
+
+    fn end_object_value<W: ?Sized + Write>(&mut self, w: &mut W) -> io::Result<()> {
+        self.inner.end_object_value(w)
+    }
+
+    fn write_string_fragment<W: ?Sized + Write>(&mut self, w: &mut W, s: &str) -> io::Result<()> {
+        if !self.escape_unicode && !self.escape_slash {
+            return w.write_all(s.as_bytes());
+        }
+        for c in s.chars() {
+            if c == '/' && self.escape_slash {
+                w.write_all(b"\\/")?;
+            } else if c.is_ascii() || !self.escape_unicode {
+                w.write_all(c.encode_utf8(&mut [0; 4]).as_bytes())?;
+            } else {
+                let mut buf = [0u16; 2];
+                for unit in c.encode_utf16(&mut buf) {
+                    write!(w, "\\u{:04x}", unit)?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// `v` serialized in `style`.
+pub fn to_string(v: &Value, style: &FmtStyle) -> String {
+    let indent = " ".repeat(style.indent);
+    let mut out = Vec::new();
+    let written = match style.output_style {
+        OutputStyle::Pretty => v.serialize(&mut serde_json::Serializer::with_formatter(
+            &mut out,
+            Canonical {
+                inner: PrettyFormatter::with_indent(indent.as_bytes()),
+                escape_unicode: style.escape_unicode,
+                escape_slash: style.escape_slash,
+            },
+        )),
+        OutputStyle::Compact => v.serialize(&mut serde_json::Serializer::with_formatter(
+            &mut out,
+            Canonical {
+                inner: CompactFormatter,
+                escape_unicode: style.escape_unicode,
+                escape_slash: style.escape_slash,
+            },
+        )),
+    };
+    written.expect("serializing a Value cannot fail");
+    let mut s = String::from_utf8(out).expect("serde_json writes UTF-8");
+    if style.final_newline {
+        s.push('\n');
+    }
+    s
+}
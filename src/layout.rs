@@ -0,0 +1,190 @@
+//! Flat (`locales/en.json`) and namespaced (`locales/en/common.json`)
+//! locale layouts. A directory is namespaced when it has no top-level
+//! `.json` files but its subdirectories do; each subdirectory is then a
+//! locale and each file in it a namespace with its own base file.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+static NAMESPACED: OnceLock<bool> = OnceLock::new();
+
+/// Suffix of the metadata sidecar of a base file (`en.meta.json`).
+const META_SUFFIX: &str = ".meta.json";
+
+fn is_json(p: &Path) -> bool {
+    p.is_file()
+        && p.extension() == Some("json".as_ref())
+        && !p.to_string_lossy().ends_with(META_SUFFIX)
+}
+
+fn entries(dir: &Path) -> Vec<PathBuf> {
+    fs::read_dir(dir)
+        .map(|read| read.flatten().map(|e| e.path()).collect())
+        .unwrap_or_default()
+}
+
+pub fn init(dir: &Path) {
+    let all = entries(dir);
+    let namespaced = !all.iter().any(|p| is_json(p))
+        && all
+            .iter()
+            .any(|p| p.is_dir() && entries(p).iter().any(|f| is_json(f)));
+    let _ = NAMESPACED.set(namespaced);
+}
+
+pub fn namespaced() -> bool {
+    NAMESPACED.get().copied().unwrap_or(false)
+}
+
+/// Fails when the layout is namespaced, for `command`s that only handle
+/// one file per locale.
+pub fn require_flat(command: &str) -> Result<(), String> {
+    if namespaced() {
+        Err(format!(
+            "{} does not support namespaced locale directories yet",
+            command
+        ))
+    } else {
+        Ok(())
+    }
+}
+
+/// Every locale file under `dir`: `dir/*.json`, or `dir/*/*.json` when
+/// namespaced.
+pub fn files(dir: &Path) -> Vec<PathBuf> {
+    if !namespaced() {
+        return entries(dir).into_iter().filter(|p| is_json(p)).collect();
+    }
+    entries(dir)
+        .into_iter()
+        .filter(|p| p.is_dir())
+        .flat_map(|d| entries(&d))
+        .filter(|p| is_json(p))
+        .collect()
+}
+
+/// Locale code of a locale file: its file stem, or the name of its
+/// directory when namespaced.
+pub fn locale_of(path: &Path) -> String {
+    let name = if namespaced() {
+        path.parent().and_then(|d| d.file_name())
+    } else {
+        path.file_stem()
+    };
+    name.map(|s| s.to_string_lossy().into_owned())
+        .unwrap_or_default()
+}
+
+/// The file of a locale named in another tool's spelling (`zh_CN`, `zh-cn`):
+/// an existing file whose code matches ignoring `-`/`_` and case, or
+/// `<dir>/<code>.json`. Fails for codes [`check_code`] rejects.
+pub fn file_for_code(dir: &Path, code: &str) -> Result<PathBuf, String> {
+    check_code(code)?;
+    let code = code.replace('_', "-");
+    Ok(files(dir)
+        .into_iter()
+        .find(|p| locale_of(p).replace('_', "-").eq_ignore_ascii_case(&code))
+        .unwrap_or_else(|| dir.join(format!("{}.json", code))))
+}
+
+/// Fails unless `code` can name a locale file in the locale directory: a
+/// single non-empty path component, so codes read from files (bundle
+/// manifests, `.ts` languages) cannot write elsewhere.
+pub fn check_code(code: &str) -> Result<(), String> {
+    let bad = code.is_empty()
+        || code.starts_with('.')
+        || code.contains(['/', '\\', ':'])
+        || code.contains("..")
+        || Path::new(code).is_absolute();
+    if bad {
+        Err(format!("{:?} is not a valid locale code", code))
+    } else {
+        Ok(())
+    }
+}
+
+/// Namespace of a locale file (`settings` for `zh/settings.json`), if the
+/// layout has namespaces.
+pub fn namespace_of(path: &Path) -> Option<String> {
+    namespaced()
+        .then(|| path.file_stem().map(|s| s.to_string_lossy().into_owned()))
+        .flatten()
+}
+
+/// Default base: `en.json`, or the `en` directory when namespaced.
+pub fn default_base() -> &'static str {
+    if namespaced() { "en" } else { "en.json" }
+}
+
+/// The base file `path` is compared against. `base` is the base file, or
+/// the base locale directory when namespaced.
+pub fn base_for(base: &Path, path: &Path) -> PathBuf {
+    match path.file_name() {
+        Some(name) if namespaced() => base.join(name),
+        _ => base.to_path_buf(),
+    }
+}
+
+/// The metadata sidecar of the base file `base_file`: `en.meta.json` next
+/// to `en.json`, or `en/settings.meta.json` next to `en/settings.json`.
+pub fn meta_for(base_file: &Path) -> PathBuf {
+    base_file.with_extension(&META_SUFFIX[1..])
+}
+
+/// Whether `path` is (one of) the base file(s).
+pub fn is_base(base: &Path, path: &Path) -> bool {
+    path.starts_with(base)
+}
+
+fn namespace_names(dir: &Path) -> Vec<String> {
+    let mut names: Vec<String> = entries(dir)
+        .into_iter()
+        .filter(|p| is_json(p))
+        .filter_map(|p| p.file_name().map(|n| n.to_string_lossy().into_owned()))
+        .collect();
+    names.sort();
+    names
+}
+
+/// A locale directory whose namespace files differ from the base locale's.
+pub struct Parity {
+    pub dir: PathBuf,
+    /// Namespace files the base has and this locale lacks.
+    pub missing: Vec<String>,
+    /// Namespace files this locale has and the base does not.
+    pub extra: Vec<String>,
+}
+
+/// Compares the namespace files of every locale directory in `dir` with
+/// those of the `base` directory.
+pub fn parity(dir: &Path, base: &Path) -> Vec<Parity> {
+    let expected = namespace_names(base);
+    let mut dirs: Vec<PathBuf> = entries(dir)
+        .into_iter()
+        .filter(|p| p.is_dir() && !is_base(base, p))
+        .collect();
+    dirs.sort();
+    let mut out = Vec::new();
+    for d in dirs {
+        let actual = namespace_names(&d);
+        let missing: Vec<String> = expected
+            .iter()
+            .filter(|n| !actual.contains(n))
+            .cloned()
+            .collect();
+        let extra: Vec<String> = actual
+            .iter()
+            .filter(|n| !expected.contains(n))
+            .cloned()
+            .collect();
+        if !missing.is_empty() || !extra.is_empty() {
+            out.push(Parity {
+                dir: d,
+                missing,
+                extra,
+            });
+        }
+    }
+    out
+}
@@ -0,0 +1,110 @@
+//! The checking core of cvr-i18n: scanning, parsing and linting locale
+//! text in memory, shared by the command line tool and the WebAssembly
+//! build.
+
+pub mod api;
+pub mod config;
+pub mod json;
+pub mod layout;
+pub mod order;
+pub mod rules;
+pub mod scan;
+#[cfg(target_arch = "wasm32")]
+mod wasm;
+pub mod write;
+
+use indexmap::IndexMap;
+use serde_json::Value;
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+use unicode_normalization::UnicodeNormalization;
+
+pub fn keys_from_value(v: &Value) -> Vec<String> {
+    if let Value::Object(map) = v {
+        map.keys().cloned().collect()
+    } else {
+        Vec::new()
+    }
+}
+
+/// Validates `text` as a JSON object without building its value tree.
+pub fn check_syntax(path: &Path, text: &str) -> Result<(), String> {
+    serde_json::from_str::<serde::de::IgnoredAny>(text)
+        .map_err(|e| format!("parse {}: {}", path.display(), e))?;
+    if !text.trim_start().starts_with('{') {
+        return Err(format!("{}: root is not an object", path.display()));
+    }
+    Ok(())
+}
+
+/// Top-level keys of a locale file, scanned from the text so large files
+/// are checked without materializing them.
+pub fn scan_keys(path: &Path, text: &str) -> Result<HashSet<String>, String> {
+    check_syntax(path, text)?;
+    Ok(scan::literals(text)
+        .filter(|lit| lit.is_key && lit.object == 1)
+        .map(|lit| lit.path)
+        .collect())
+}
+
+/// Keys that appear more than once, with how many times.
+pub type Duplicates = Vec<(String, usize)>;
+
+/// Keys that appear more than once in the same object, at any depth, by
+/// dotted path with their counts, and the syntax error if the file is not
+/// valid JSON. Duplicates are found on the raw text (parsing keeps only the
+/// last one), so both kinds of problem are reported together.
+pub fn find_duplicates(path: &Path, text: &str) -> (Duplicates, Option<String>) {
+    let mut counts: IndexMap<(usize, String), usize> = IndexMap::new();
+    for lit in scan::literals(text) {
+        if lit.is_key {
+            *counts.entry((lit.object, lit.path)).or_insert(0) += 1;
+        }
+    }
+    let syntax = check_syntax(path, text).err();
+    let dups = counts
+        .into_iter()
+        .filter(|(_, c)| *c > 1)
+        .map(|((_, path), c)| (path, c))
+        .collect();
+    (dups, syntax)
+}
+
+/// Keys that differ from an earlier key of the same object only in Unicode
+/// normalization or invisible characters: each one's dotted path with the
+/// path of the key it looks identical to.
+pub type Lookalikes = Vec<(String, String)>;
+
+/// Keys that equal an earlier key of their object once both are stripped of
+/// invisible characters and NFC-normalized, like `café` typed with a
+/// combining accent next to a precomposed one. Keys repeated exactly are
+/// [`find_duplicates`]' to report.
+pub fn find_lookalikes(text: &str) -> Lookalikes {
+    let mut seen: HashMap<(usize, String), (String, String)> = HashMap::new();
+    let mut out = Vec::new();
+    for lit in scan::literals(text).filter(|l| l.is_key) {
+        let name = scan::unescape(lit.raw);
+        let normalized: String = name
+            .chars()
+            .filter(|c| !rules::is_invisible(*c))
+            .nfc()
+            .collect();
+        match seen.get(&(lit.object, normalized.clone())) {
+            Some((first, path)) if *first != name => out.push((lit.path, path.clone())),
+            Some(_) => {}
+            None => {
+                seen.insert((lit.object, normalized), (name, lit.path));
+            }
+        }
+    }
+    out
+}
+
+/// The message reporting `key` as a lookalike of `like`, one pair of
+/// [`find_lookalikes`].
+pub fn lookalike_message(key: &str, like: &str) -> String {
+    format!(
+        "{:?} looks identical to {:?} (differs only in normalization or invisible characters)",
+        key, like
+    )
+}
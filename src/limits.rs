@@ -0,0 +1,85 @@
+//! Guards against pathological input: a locale file too large to read
+//! whole, or nested too deeply to walk, is refused with a diagnostic naming
+//! the config key to raise, rather than exhausting memory or the stack when
+//! `-d` points at the wrong directory.
+
+use crate::config::Config;
+use std::fs;
+use std::path::Path;
+use std::sync::OnceLock;
+
+/// Bytes, unless `max-file-size` says otherwise.
+const DEFAULT_MAX_FILE_SIZE: u64 = 16 * 1024 * 1024;
+/// Objects and arrays, unless `max-depth` says otherwise.
+const DEFAULT_MAX_DEPTH: usize = 64;
+
+static LIMITS: OnceLock<(u64, usize)> = OnceLock::new();
+
+pub fn init(config: &Config) {
+    let _ = LIMITS.set((
+        config.max_file_size.unwrap_or(DEFAULT_MAX_FILE_SIZE),
+        config.max_depth.unwrap_or(DEFAULT_MAX_DEPTH),
+    ));
+}
+
+fn limits() -> (u64, usize) {
+    LIMITS
+        .get()
+        .copied()
+        .unwrap_or((DEFAULT_MAX_FILE_SIZE, DEFAULT_MAX_DEPTH))
+}
+
+/// How deeply objects and arrays nest in `text`, brackets inside strings
+/// aside. Malformed text is measured all the same.
+fn depth(text: &str) -> usize {
+    let (mut depth, mut deepest) = (0usize, 0);
+    let (mut in_string, mut escaped) = (false, false);
+    for b in text.bytes() {
+        if in_string {
+            match b {
+                _ if escaped => escaped = false,
+                b'\\' => escaped = true,
+                b'"' => in_string = false,
+                _ => {}
+            }
+            continue;
+        }
+        match b {
+            b'"' => in_string = true,
+            b'{' | b'[' => {
+                depth += 1;
+                deepest = deepest.max(depth);
+            }
+            b'}' | b']' => depth = depth.saturating_sub(1),
+            _ => {}
+        }
+    }
+    deepest
+}
+
+/// The text of `path`, refused when it exceeds the limits.
+pub fn read(path: &Path) -> Result<String, String> {
+    let (max_size, max_depth) = limits();
+    let size = fs::metadata(path)
+        .map_err(|e| format!("read {}: {}", path.display(), e))?
+        .len();
+    if size > max_size {
+        return Err(format!(
+            "{} is {} bytes, over the {} byte limit (raise max-file-size)",
+            path.display(),
+            size,
+            max_size
+        ));
+    }
+    let text = fs::read_to_string(path).map_err(|e| format!("read {}: {}", path.display(), e))?;
+    let nested = depth(&text);
+    if nested > max_depth {
+        return Err(format!(
+            "{} nests {} levels deep, over the limit of {} (raise max-depth)",
+            path.display(),
+            nested,
+            max_depth
+        ));
+    }
+    Ok(text)
+}
@@ -0,0 +1,344 @@
+use crate::config::Config;
+use crate::locales::LocaleSet;
+use crate::{layout, pretty, report, rules};
+use clap::ArgMatches;
+use serde_json::{Value, json};
+use std::ffi::OsString;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+// Deprecated or commonly misused codes and their canonical replacement.
+const ALIASES: &[(&str, &str)] = &[
+    ("iw", "he"),
+    ("in", "id"),
+    ("ji", "yi"),
+    ("jw", "jv"),
+    ("jp", "ja"),
+    ("zhcn", "zh-CN"),
+    ("zhtw", "zh-TW"),
+    ("zhhk", "zh-HK"),
+];
+
+const SETUP_EXTENSIONS: &[&str] = &["ts", "tsx", "js", "jsx", "mjs", "cjs", "json"];
+
+/// Returns the canonical BCP-47 spelling of a locale code: `-` separators,
+/// lowercase language, title-case script and uppercase region.
+pub fn canonical_locale(code: &str) -> String {
+    let lower = code.to_ascii_lowercase();
+    if let Some((_, to)) = ALIASES.iter().find(|(from, _)| *from == lower) {
+        return to.to_string();
+    }
+    let mut out: Vec<String> = Vec::new();
+    for (i, part) in code.split(['_', '-']).filter(|p| !p.is_empty()).enumerate() {
+        let p = if i == 0 {
+            let lang = part.to_ascii_lowercase();
+            ALIASES
+                .iter()
+                .find(|(from, _)| *from == lang)
+                .map(|(_, to)| to.to_string())
+                .unwrap_or(lang)
+        } else if part.len() == 4 && part.chars().all(|c| c.is_ascii_alphabetic()) {
+            let mut s = part[..1].to_ascii_uppercase();
+            s.push_str(&part[1..].to_ascii_lowercase());
+            s
+        } else if (part.len() == 2 && part.chars().all(|c| c.is_ascii_alphabetic()))
+            || (part.len() == 3 && part.chars().all(|c| c.is_ascii_digit()))
+        {
+            part.to_ascii_uppercase()
+        } else {
+            part.to_ascii_lowercase()
+        };
+        out.push(p);
+    }
+    out.join("-")
+}
+
+/// Locale files in `files` whose stem is not already canonical, paired with
+/// the path they should be renamed to.
+pub fn suggest_renames(files: &[PathBuf]) -> Vec<(PathBuf, PathBuf)> {
+    let mut out = Vec::new();
+    for p in files {
+        let Some(stem) = p.file_stem().and_then(|s| s.to_str()) else {
+            continue;
+        };
+        let canonical = canonical_locale(stem);
+        if canonical != stem {
+            out.push((p.clone(), p.with_file_name(format!("{}.json", canonical))));
+        }
+    }
+    out
+}
+
+fn list_setup_files(dir: &Path, out: &mut Vec<PathBuf>) {
+    let Ok(read) = fs::read_dir(dir) else {
+        return;
+    };
+    for p in read.flatten().map(|e| e.path()) {
+        let name = p.file_name().and_then(|s| s.to_str()).unwrap_or("");
+        if p.is_dir() {
+            if name != "node_modules" && !name.starts_with('.') {
+                list_setup_files(&p, out);
+            }
+        } else if name.to_ascii_lowercase().contains("i18n")
+            && p.extension()
+                .and_then(|e| e.to_str())
+                .is_some_and(|e| SETUP_EXTENSIONS.contains(&e))
+        {
+            out.push(p);
+        }
+    }
+}
+
+/// Frontend i18n setup files (any source file with `i18n` in its name) below
+/// `src_dir`.
+pub fn find_setup_files(src_dir: &Path) -> Vec<PathBuf> {
+    let mut out = Vec::new();
+    list_setup_files(src_dir, &mut out);
+    out.sort();
+    out
+}
+
+/// Where the frontend's sources are looked for: the parent of the locales
+/// directory, or `.` for a relative `locales` whose parent is empty.
+fn source_dir(dir: &Path) -> &Path {
+    match dir.parent() {
+        Some(p) if !p.as_os_str().is_empty() => p,
+        _ => Path::new("."),
+    }
+}
+
+/// Replaces quoted occurrences of `from` (and `from.json`) with `to` in `text`.
+pub fn replace_references(text: &str, from: &str, to: &str) -> String {
+    let mut s = text.to_string();
+    for q in ['"', '\'', '`'] {
+        s = s.replace(&format!("{q}{from}{q}"), &format!("{q}{to}{q}"));
+    }
+    s.replace(&format!("/{from}.json"), &format!("/{to}.json"))
+}
+
+/// `rename-locales`: prints (or with `--apply` performs) canonical renames and
+/// rewrites matching references in the frontend's i18n setup files.
+pub fn run(dir: &Path, m: &ArgMatches) -> i32 {
+    if !dir.exists() {
+        eprintln!("Directory does not exist: {}", dir.display());
+        return 2;
+    }
+    if crate::layout::namespaced() {
+        eprintln!("rename-locales does not support namespaced layouts (locales/<locale>/*.json)");
+        return 2;
+    }
+    let renames = suggest_renames(&crate::list_json_files(dir));
+    if renames.is_empty() {
+        crate::report::info("All locale file names are canonical");
+        return 0;
+    }
+    let apply = m.get_flag("apply");
+    let src_dir = match m.get_one::<OsString>("src") {
+        Some(s) => PathBuf::from(s),
+        None => source_dir(dir).to_path_buf(),
+    };
+    let setup_files = find_setup_files(&src_dir);
+    let mut any_errors = false;
+    let mut renames = renames;
+    renames.retain(|(from, to)| {
+        if to.exists() {
+            eprintln!(
+                "{}: ERROR: cannot rename, {} already exists",
+                from.display(),
+                to.display()
+            );
+            any_errors = true;
+        }
+        !to.exists()
+    });
+    for (from, to) in &renames {
+        println!("{} -> {}", from.display(), to.display());
+        if apply && let Err(e) = crate::write::rename(from, to) {
+            eprintln!("Failed to rename {}: {}", from.display(), e);
+            any_errors = true;
+        }
+    }
+    for f in &setup_files {
+        let Ok(text) = fs::read_to_string(f) else {
+            continue;
+        };
+        let mut updated = text.clone();
+        for (from, to) in &renames {
+            let stem = |p: &Path| p.file_stem().unwrap().to_string_lossy().into_owned();
+            updated = replace_references(&updated, &stem(from), &stem(to));
+        }
+        if updated == text {
+            continue;
+        }
+        println!("  references in {}", f.display());
+        if apply && let Err(e) = crate::write::write_file(f, &updated) {
+            eprintln!("Failed to write {}: {}", f.display(), e);
+            any_errors = true;
+        }
+    }
+    if any_errors {
+        2
+    } else if apply {
+        // Under --dry-run the renames above are the plan; nothing is left to do.
+        0
+    } else {
+        println!("Run with --apply to rename");
+        1
+    }
+}
+
+/// `v` with every string replaced by `marker`, keeping keys and their order.
+fn blanked(v: &Value, marker: &str) -> Value {
+    match v {
+        Value::String(_) => Value::String(marker.to_string()),
+        Value::Object(map) => Value::Object(
+            map.iter()
+                .map(|(k, child)| (k.clone(), blanked(child, marker)))
+                .collect(),
+        ),
+        Value::Array(items) => Value::Array(items.iter().map(|i| blanked(i, marker)).collect()),
+        other => other.clone(),
+    }
+}
+
+/// Adds `locale` to the `exclude-locales` of the config file at `path`,
+/// creating the file if needed.
+fn exclude_in_config(path: &Path, locale: &str) -> Result<(), String> {
+    let mut doc: Value = match fs::read_to_string(path) {
+        Ok(text) => {
+            serde_json::from_str(&text).map_err(|e| format!("parse {}: {}", path.display(), e))?
+        }
+        Err(_) => json!({}),
+    };
+    let Some(root) = doc.as_object_mut() else {
+        return Err(format!("{}: root is not an object", path.display()));
+    };
+    let list = root
+        .entry("exclude-locales")
+        .or_insert_with(|| json!([]))
+        .as_array_mut()
+        .ok_or_else(|| format!("{}: exclude-locales is not an array", path.display()))?;
+    if !list.iter().any(|l| l == locale) {
+        list.push(json!(locale));
+    }
+    let text = serde_json::to_string_pretty(&doc).map_err(|e| e.to_string())? + "\n";
+    crate::write::write_file(path, &text)
+}
+
+/// `new-locale`: creates the files of a new locale from the base with every
+/// string blank (or `--marker`), optionally excludes it from checks in the
+/// config until it is ready, and prints what to do next.
+pub fn run_new(
+    dir: &Path,
+    base: &Path,
+    m: &ArgMatches,
+    config: &Config,
+    config_path: &Path,
+    locale_set: &mut LocaleSet,
+) -> i32 {
+    let requested = m.get_one::<String>("locale").unwrap();
+    let locale = canonical_locale(requested);
+    if locale != *requested {
+        report::info(format!(
+            "Using the canonical code {} for {}",
+            locale, requested
+        ));
+    }
+    let marker = m.get_one::<String>("marker").map_or("", String::as_str);
+    // (base file, new file)
+    let pairs: Vec<(PathBuf, PathBuf)> = if layout::namespaced() {
+        let mut names: Vec<PathBuf> = layout::files(dir)
+            .into_iter()
+            .filter(|p| layout::is_base(base, p))
+            .collect();
+        names.sort();
+        names
+            .into_iter()
+            .map(|p| {
+                let target = dir.join(&locale).join(p.file_name().unwrap());
+                (p, target)
+            })
+            .collect()
+    } else {
+        vec![(base.to_path_buf(), dir.join(format!("{}.json", locale)))]
+    };
+    if let Some((_, existing)) = pairs.iter().find(|(_, t)| t.exists()) {
+        eprintln!("{} already exists", existing.display());
+        return 2;
+    }
+    if layout::namespaced()
+        && !crate::write::dry_run()
+        && let Err(e) = fs::create_dir_all(dir.join(&locale))
+    {
+        eprintln!("Failed to create {}: {}", dir.join(&locale).display(), e);
+        return 2;
+    }
+    let mut strings = 0;
+    for (bp, target) in &pairs {
+        let base_v = match locale_set.value(bp) {
+            Ok(v) => v,
+            Err(e) => {
+                eprintln!("Failed to read {}: {}", bp.display(), e);
+                return 2;
+            }
+        };
+        strings += rules::string_entries(&base_v).len();
+        let text = pretty::canonical(&blanked(&base_v, marker), Some(&base_v), &config.fmt);
+        if let Err(e) = crate::write::write_file(target, &text) {
+            eprintln!("Failed to write {}: {}", target.display(), e);
+            return 2;
+        }
+        report::info(format!(
+            "{} {}",
+            crate::write::verb("Created", "create"),
+            target.display()
+        ));
+    }
+    let exclude = m.get_flag("exclude_from_checks");
+    if exclude && let Err(e) = exclude_in_config(config_path, &locale) {
+        eprintln!("Failed to update {}: {}", config_path.display(), e);
+        return 2;
+    }
+    let src_dir = source_dir(dir);
+    report::info("\nNext steps:");
+    report::info(format!(
+        "  1. Translate the {} strings of {} (`cvr-i18n stats` shows progress)",
+        strings, locale
+    ));
+    let setup = find_setup_files(src_dir);
+    if setup.is_empty() {
+        report::info(format!("  2. Register {} in the app's i18n setup", locale));
+    } else {
+        let names: Vec<String> = setup.iter().map(|p| p.display().to_string()).collect();
+        report::info(format!(
+            "  2. Register {} in the app's i18n setup: {}",
+            locale,
+            names.join(", ")
+        ));
+    }
+    if exclude {
+        report::info(format!(
+            "  3. Remove {} from exclude-locales in {} once it is ready to be checked",
+            locale,
+            config_path.display()
+        ));
+    } else {
+        report::info(format!(
+            "  3. Until it is complete, `cvr-i18n -m` reports its missing strings; pass --exclude-locale {} to skip it",
+            locale
+        ));
+    }
+    0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sources_of_a_relative_locales_dir_are_the_working_dir() {
+        assert_eq!(source_dir(Path::new("locales")), Path::new("."));
+        assert_eq!(source_dir(Path::new("src/locales")), Path::new("src"));
+        assert_eq!(source_dir(Path::new("/app/locales")), Path::new("/app"));
+    }
+}
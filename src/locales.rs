@@ -0,0 +1,44 @@
+//! The locale files of one run, each read and parsed at most once however
+//! many checks look at it.
+
+use crate::limits;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+
+#[derive(Default)]
+pub struct LocaleSet {
+    texts: HashMap<PathBuf, Result<Rc<str>, String>>,
+    values: HashMap<PathBuf, Result<Rc<Value>, String>>,
+}
+
+impl LocaleSet {
+    /// The raw text of `path`, within the size and depth limits.
+    pub fn text(&mut self, path: &Path) -> Result<Rc<str>, String> {
+        self.texts
+            .entry(path.to_path_buf())
+            .or_insert_with(|| limits::read(path).map(Rc::from))
+            .clone()
+    }
+
+    /// `path` parsed as JSON.
+    pub fn value(&mut self, path: &Path) -> Result<Rc<Value>, String> {
+        if let Some(v) = self.values.get(path) {
+            return v.clone();
+        }
+        let v = self.text(path).and_then(|text| {
+            serde_json::from_str(&text)
+                .map(Rc::new)
+                .map_err(|e| format!("parse {}: {}", path.display(), e))
+        });
+        self.values.insert(path.to_path_buf(), v.clone());
+        v
+    }
+
+    /// Drops what is cached for `path`, after it has been rewritten.
+    pub fn forget(&mut self, path: &Path) {
+        self.texts.remove(path);
+        self.values.remove(path);
+    }
+}
@@ -1,74 +1,218 @@
+mod baseline;
+mod bundle;
+mod changelog;
+mod check;
+mod chrome;
+mod copied;
+mod daemon;
+mod diff;
+mod edit;
+mod export;
+mod filter;
+mod grep;
+mod interop;
+mod limits;
+mod locale;
+mod locales;
+mod meta;
+mod metrics;
+mod mt;
+mod nesting;
+mod notify;
+mod patch;
+mod php;
+mod plugins;
+mod pretty;
+mod qt;
+mod regional;
+mod replace;
+mod report;
+mod schema;
+mod source;
+mod stats;
+mod suggest;
+mod usage;
+mod vscode;
+
 use clap::{Arg, ArgAction, Command};
-use indexmap::IndexMap;
+use cvr_i18n::{
+    api, config, find_duplicates, find_lookalikes, json, keys_from_value, layout,
+    lookalike_message, order, rules, scan, scan_keys, write,
+};
 use serde_json::Value;
-use std::collections::{HashMap, HashSet};
+use std::collections::HashSet;
 use std::ffi::OsString;
-use std::fs;
 use std::path::{Path, PathBuf};
+use std::time::Instant;
 
 fn read_json(path: &Path) -> Result<Value, String> {
-    fs::read_to_string(path)
-        .map_err(|e| format!("read {}: {}", path.display(), e))
-        .and_then(|s| {
-            serde_json::from_str(&s).map_err(|e| format!("parse {}: {}", path.display(), e))
-        })
+    limits::read(path).and_then(|s| {
+        serde_json::from_str(&s).map_err(|e| format!("parse {}: {}", path.display(), e))
+    })
 }
 
-fn keys_from_value(v: &Value) -> Vec<String> {
-    if let Value::Object(map) = v {
-        map.keys().cloned().collect()
-    } else {
-        Vec::new()
-    }
+/// Ends the process with `code` as `--exit-zero` and
+/// `--detailed-exit-codes` have it.
+fn exit(code: i32) -> ! {
+    patch::finish();
+    std::process::exit(report::exit_code(code))
 }
 
 fn list_json_files(dir: &Path) -> Vec<PathBuf> {
-    match fs::read_dir(dir) {
-        Ok(read) => {
-            let mut entries: Vec<_> = read.flatten().map(|e| e.path()).collect();
-            entries.retain(|p| p.is_file() && p.extension() == Some("json".as_ref()));
-            entries.sort();
-            entries
-        }
-        Err(_) => Vec::new(),
-    }
+    let mut entries = layout::files(dir);
+    entries.retain(|p| filter::included(dir, p));
+    entries.sort();
+    entries
 }
 
-fn find_duplicates_in_file(path: &Path) -> Result<HashMap<String, usize>, String> {
-    let v = read_json(path)?;
-    if let Value::Object(map) = v {
-        let mut counts = HashMap::new();
-        for k in map.keys() {
-            *counts.entry(k.clone()).or_insert(0usize) += 1;
+fn write_json(path: &Path, v: &Value, style: &config::FmtStyle) -> Result<(), String> {
+    write::write_file(path, &json::to_string(v, style))
+}
+
+/// Reorders `path` after `base_keys`, then after the `order` patterns,
+/// returning the keys that moved and, with `--unknown-keys error`, the keys
+/// not in the base that kept the file from being written.
+fn write_sorted(
+    path: &Path,
+    base_keys: &[String],
+    order: &[String],
+    unknown: order::Unknown,
+    style: &config::FmtStyle,
+) -> Result<(Vec<String>, Vec<String>), String> {
+    let Value::Object(map) = read_json(path)? else {
+        return Err(format!("{}: root is not an object", path.display()));
+    };
+    match order::sort_object(map, base_keys, order, unknown) {
+        Ok((out, moved)) => {
+            write_json(path, &Value::Object(out), style)?;
+            Ok((moved, Vec::new()))
         }
-        Ok(counts.into_iter().filter(|(_, c)| *c > 1).collect())
+        Err(rejected) => Ok((Vec::new(), rejected)),
+    }
+}
+
+/// The base file, or the base locale directory in a namespaced layout.
+fn resolve_base(dir: &Path, base: Option<&String>) -> PathBuf {
+    let base_file = base.map(|s| s.as_str()).unwrap_or(layout::default_base());
+    if base_file.contains('/') || base_file.contains('\\') {
+        Path::new(base_file).to_path_buf()
     } else {
-        Err(format!("{}: root is not an object", path.display()))
+        dir.join(base_file)
     }
 }
 
-fn write_sorted(path: &Path, base_keys: &[String]) -> Result<(), String> {
-    let v = read_json(path)?;
-    if let Value::Object(mut map) = v {
-        let mut out: IndexMap<String, Value> = IndexMap::new();
-        let mut missing = Vec::new();
-        for k in base_keys {
-            if let Some(val) = map.remove(k) {
-                out.insert(k.clone(), val);
-            } else {
-                missing.push(k.clone());
+fn print_sorted(path: &Path, (moved, unknown): &(Vec<String>, Vec<String>)) {
+    if !unknown.is_empty() {
+        report::header(path, "UNKNOWN KEYS", unknown.len());
+        for k in unknown {
+            report::detail(format!("  {}", k));
+        }
+        return;
+    }
+    report::info(format!(
+        "{} {} ({} keys moved)",
+        write::verb("Sorted", "sort"),
+        path.display(),
+        moved.len()
+    ));
+    for k in moved {
+        report::info_detail(format!("  {}", k));
+    }
+}
+
+/// The `-f` file, or every locale file in `dir`.
+fn target_files(dir: &Path, file: Option<&String>) -> Vec<PathBuf> {
+    match file {
+        Some(f) => vec![PathBuf::from(f)],
+        None => {
+            if !dir.exists() {
+                eprintln!("Directory does not exist: {}", dir.display());
+                exit(2);
             }
+            list_json_files(dir)
         }
-        let mut remaining: Vec<_> = map.into_iter().collect();
-        remaining.sort_by(|a, b| a.0.cmp(&b.0));
-        for (k, v) in remaining {
-            out.insert(k, v);
+    }
+}
+
+/// Reports locale directories whose namespace files differ from the base
+/// locale's: missing files are errors, extra ones warnings.
+fn check_namespace_files(dir: &Path, base: &Path) -> Vec<rules::Finding> {
+    let mut findings = Vec::new();
+    for p in layout::parity(dir, base) {
+        let missing: Vec<&String> = p
+            .missing
+            .iter()
+            .filter(|n| filter::included(dir, &p.dir.join(n)))
+            .collect();
+        let extra: Vec<&String> = p
+            .extra
+            .iter()
+            .filter(|n| filter::included(dir, &p.dir.join(n)))
+            .collect();
+        if !missing.is_empty() {
+            report::header(&p.dir, "MISSING FILES", missing.len());
+        }
+        for n in missing {
+            report::detail(format!("  {}", n));
+            findings.push(rules::Finding::new(
+                &p.dir.join(n),
+                "missing-file",
+                n.clone(),
+                rules::Severity::Error,
+                format!("namespace file missing (present in {})", base.display()),
+            ));
+        }
+        if !extra.is_empty() {
+            report::header(&p.dir, "EXTRA FILES", extra.len());
+        }
+        for n in extra {
+            report::detail(format!("  {}", n));
+            findings.push(rules::Finding::new(
+                &p.dir.join(n),
+                "extra-file",
+                n.clone(),
+                rules::Severity::Warning,
+                format!("namespace file not in {}", base.display()),
+            ));
+        }
+    }
+    findings
+}
+
+fn print_missing_key(key: &str, base: &Value) {
+    match base.get(key) {
+        Some(Value::String(v)) if report::verbosity() >= 1 => {
+            report::detail(format!("  {}  = {:?}", key, v))
+        }
+        _ => report::detail(format!("  {}", key)),
+    }
+}
+
+fn print_findings(path: &Path, label: &str, findings: &[rules::Finding]) {
+    if findings.is_empty() {
+        report::ok(path);
+        return;
+    }
+    report::header(path, label, findings.len());
+    for f in findings {
+        // File-level findings have no key.
+        let key = if f.key.is_empty() {
+            String::new()
+        } else {
+            format!("{}: ", f.key)
+        };
+        report::detail(format!(
+            "  {} [{}] {}{}",
+            report::severity(f.severity),
+            f.rule,
+            key,
+            f.message
+        ));
+        if report::verbosity() >= 1
+            && let Some(v) = &f.value
+        {
+            report::detail(format!("      {:?}", v));
         }
-        let s = serde_json::to_string_pretty(&out).map_err(|e| e.to_string())?;
-        fs::write(path, s).map_err(|e| format!("write {}: {}", path.display(), e))?;
-        Ok(())
-    } else {
-        Err(format!("{}: root is not an object", path.display()))
     }
 }
 
@@ -79,6 +223,7 @@ fn main() {
             Arg::new("directory")
                 .short('d')
                 .long("directory")
+                .global(true)
                 .value_parser(clap::builder::ValueParser::os_string()),
         )
         .arg(
@@ -87,28 +232,675 @@ fn main() {
                 .long("duplicated-key")
                 .action(ArgAction::SetTrue),
         )
+        .arg(
+            Arg::new("output_style")
+                .long("output-style")
+                .value_name("STYLE")
+                .global(true)
+                .value_parser(["pretty", "compact"]),
+        )
+        .arg(
+            Arg::new("normalize_keys")
+                .long("normalize-keys")
+                .global(true)
+                .action(ArgAction::SetTrue),
+        )
         .arg(
             Arg::new("missing_key")
                 .short('m')
                 .long("missing-key")
                 .action(ArgAction::SetTrue),
         )
+        .arg(
+            Arg::new("suggest")
+                .long("suggest")
+                .requires("missing_key")
+                .action(ArgAction::SetTrue),
+        )
         .arg(
             Arg::new("export")
                 .short('e')
                 .long("export")
                 .value_name("DIR"),
         )
+        .arg(
+            Arg::new("locations")
+                .long("locations")
+                .requires("export")
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("src")
+                .long("src")
+                .value_name("DIR")
+                .requires("locations")
+                .value_parser(clap::builder::ValueParser::os_string()),
+        )
         .arg(
             Arg::new("sort")
                 .short('s')
                 .long("sort")
                 .action(ArgAction::SetTrue),
         )
+        .arg(
+            Arg::new("lint")
+                .short('l')
+                .long("lint")
+                .value_name("RULE")
+                .value_delimiter(',')
+                .action(ArgAction::Append),
+        )
+        .arg(Arg::new("schema").long("schema").value_name("FILE"))
+        .arg(
+            Arg::new("regional")
+                .long("regional")
+                .action(ArgAction::SetTrue),
+        )
+        .arg(Arg::new("meta").long("meta").action(ArgAction::SetTrue))
+        .arg(Arg::new("copied").long("copied").action(ArgAction::SetTrue))
+        .arg(
+            Arg::new("order")
+                .long("order")
+                .value_name("FILE")
+                .requires("sort"),
+        )
+        .arg(
+            Arg::new("unknown_keys")
+                .long("unknown-keys")
+                .value_name("PLACEMENT")
+                .value_parser(["top", "bottom", "alpha", "error"])
+                .default_value("alpha")
+                .requires("sort"),
+        )
+        .arg(Arg::new("fix").long("fix").action(ArgAction::SetTrue))
+        .arg(
+            Arg::new("format")
+                .long("format")
+                .value_name("FORMAT")
+                .global(true)
+                .value_parser(["text", "codeclimate", "csv"])
+                .default_value("text"),
+        )
+        .arg(
+            Arg::new("exclude")
+                .long("exclude")
+                .value_name("GLOB")
+                .global(true)
+                .action(ArgAction::Append),
+        )
+        .arg(
+            Arg::new("exclude_locale")
+                .long("exclude-locale")
+                .value_name("LOCALE")
+                .global(true)
+                .value_delimiter(',')
+                .action(ArgAction::Append),
+        )
+        .arg(
+            Arg::new("only")
+                .long("only")
+                .value_name("LOCALES")
+                .global(true)
+                .value_delimiter(',')
+                .action(ArgAction::Append),
+        )
+        .arg(
+            Arg::new("dry_run")
+                .long("dry-run")
+                .global(true)
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("diff")
+                .long("diff")
+                .global(true)
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("emit_patch")
+                .long("emit-patch")
+                .value_name("FILE")
+                .global(true)
+                .conflicts_with("diff"),
+        )
+        .arg(
+            Arg::new("patch_format")
+                .long("patch-format")
+                .value_name("FORMAT")
+                .global(true)
+                .value_parser(["unified", "json"])
+                .default_value("unified"),
+        )
+        .arg(
+            Arg::new("backup")
+                .long("backup")
+                .value_name("SUFFIX")
+                .global(true)
+                .num_args(0..=1)
+                .require_equals(true)
+                .default_missing_value(".bak"),
+        )
+        .arg(
+            Arg::new("webhook")
+                .long("webhook")
+                .value_name("URL")
+                .global(true),
+        )
+        .arg(
+            Arg::new("webhook_payload")
+                .long("webhook-payload")
+                .value_name("PAYLOAD")
+                .global(true)
+                .value_parser(["report", "summary", "slack", "discord"])
+                .default_value("report")
+                .requires("webhook"),
+        )
+        .arg(
+            Arg::new("telegram")
+                .long("telegram")
+                .global(true)
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("summary")
+                .long("summary")
+                .global(true)
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("quiet")
+                .short('q')
+                .long("quiet")
+                .global(true)
+                .action(ArgAction::SetTrue)
+                .conflicts_with("verbose"),
+        )
+        .arg(
+            Arg::new("verbose")
+                .short('v')
+                .long("verbose")
+                .global(true)
+                .action(ArgAction::Count),
+        )
+        .arg(
+            Arg::new("no_color")
+                .long("no-color")
+                .global(true)
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("deny")
+                .long("deny")
+                .value_name("LEVEL")
+                .global(true)
+                .value_parser(["warnings"])
+                .action(ArgAction::Append),
+        )
+        .arg(
+            Arg::new("exit_zero")
+                .long("exit-zero")
+                .global(true)
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("detailed_exit_codes")
+                .long("detailed-exit-codes")
+                .global(true)
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("max_errors")
+                .long("max-errors")
+                .value_name("N")
+                .global(true)
+                .value_parser(clap::value_parser!(usize)),
+        )
+        .arg(
+            Arg::new("max_warnings")
+                .long("max-warnings")
+                .value_name("N")
+                .global(true)
+                .value_parser(clap::value_parser!(usize)),
+        )
+        .arg(
+            Arg::new("config")
+                .short('c')
+                .long("config")
+                .global(true)
+                .value_name("FILE")
+                .value_parser(clap::builder::ValueParser::os_string()),
+        )
         .arg(Arg::new("base").short('b').long("base").value_name("FILE"))
-        .arg(Arg::new("file").short('f').long("file").value_name("FILE"));
+        .arg(Arg::new("file").short('f').long("file").value_name("FILE"))
+        .subcommand(
+            Command::new("rename-locales")
+                .about("Rename locale files to canonical BCP-47 codes")
+                .arg(Arg::new("apply").long("apply").action(ArgAction::SetTrue))
+                .arg(
+                    Arg::new("src")
+                        .long("src")
+                        .value_name("DIR")
+                        .value_parser(clap::builder::ValueParser::os_string()),
+                ),
+        )
+        .subcommand(
+            Command::new("bundle")
+                .about("Package a locale's untranslated strings for a translator")
+                .args_conflicts_with_subcommands(true)
+                .subcommand_negates_reqs(true)
+                .arg(
+                    Arg::new("locale")
+                        .long("locale")
+                        .value_name("LOCALE")
+                        .required(true),
+                )
+                .arg(
+                    Arg::new("output")
+                        .short('o')
+                        .long("output")
+                        .value_name("FILE"),
+                )
+                .subcommand(
+                    Command::new("import")
+                        .about("Merge the translations of a returned bundle")
+                        .arg(Arg::new("archive").value_name("FILE").required(true)),
+                ),
+        )
+        .subcommand(
+            Command::new("chrome")
+                .about("Convert to and from WebExtension _locales/<locale>/messages.json files")
+                .subcommand_required(true)
+                .subcommand(
+                    Command::new("export")
+                        .about("Write every locale as <DIR>/<locale>/messages.json")
+                        .arg(Arg::new("dir").value_name("DIR").required(true)),
+                )
+                .subcommand(
+                    Command::new("import")
+                        .about(
+                            "Merge the messages of <DIR>/<locale>/messages.json into the locales",
+                        )
+                        .arg(Arg::new("dir").value_name("DIR").required(true)),
+                ),
+        )
+        .subcommand(
+            Command::new("php")
+                .about("Convert to and from Laravel-style PHP language files")
+                .subcommand_required(true)
+                .subcommand(
+                    Command::new("export")
+                        .about("Write every locale as <DIR>/<locale>/<FILE>.php")
+                        .arg(Arg::new("dir").value_name("DIR").required(true))
+                        .arg(
+                            Arg::new("file")
+                                .long("file")
+                                .value_name("FILE")
+                                .default_value("messages"),
+                        ),
+                )
+                .subcommand(
+                    Command::new("import")
+                        .about("Merge the strings of PHP language files into the locales")
+                        .arg(
+                            Arg::new("files")
+                                .value_name("FILE")
+                                .num_args(1..)
+                                .required(true),
+                        ),
+                ),
+        )
+        .subcommand(
+            Command::new("qt")
+                .about("Convert to and from Qt Linguist .ts files")
+                .subcommand_required(true)
+                .subcommand(
+                    Command::new("export")
+                        .about("Write every locale but the base as <DIR>/<locale>.ts")
+                        .arg(Arg::new("dir").value_name("DIR").required(true)),
+                )
+                .subcommand(
+                    Command::new("import")
+                        .about("Merge the finished translations of .ts files into the locales")
+                        .arg(
+                            Arg::new("files")
+                                .value_name("FILE")
+                                .num_args(1..)
+                                .required(true),
+                        ),
+                ),
+        )
+        .subcommand(
+            Command::new("vscode")
+                .about("Convert to VS Code package.nls.json files")
+                .subcommand_required(true)
+                .subcommand(
+                    Command::new("export")
+                        .about("Write every locale as <DIR>/package.nls.<locale>.json")
+                        .arg(Arg::new("dir").value_name("DIR").required(true))
+                        .arg(Arg::new("prefix").long("prefix").value_name("PREFIX"))
+                        .arg(Arg::new("check").long("check").action(ArgAction::SetTrue)),
+                ),
+        )
+        .subcommand(
+            Command::new("clean")
+                .about("Remove missing-key exports and bundles from a handoff directory")
+                .arg(Arg::new("export_dir").value_name("DIR").required(true))
+                .arg(Arg::new("stale").long("stale").action(ArgAction::SetTrue)),
+        )
+        .subcommand(
+            Command::new("apply")
+                .about("Apply a JSON patch written by --emit-patch to the locale files")
+                .arg(Arg::new("patch").value_name("FILE").required(true)),
+        )
+        .subcommand(
+            Command::new("verify-export")
+                .about("Check a handoff directory's exports and bundles against the locales")
+                .arg(Arg::new("export_dir").value_name("DIR").required(true)),
+        )
+        .subcommand(
+            Command::new("changelog")
+                .about("List base keys added, removed or changed between two git refs")
+                .arg(
+                    Arg::new("from")
+                        .long("from")
+                        .value_name("REF")
+                        .required(true),
+                )
+                .arg(
+                    Arg::new("to")
+                        .long("to")
+                        .value_name("REF")
+                        .default_value("HEAD"),
+                ),
+        )
+        .subcommand(
+            Command::new("release-notes")
+                .about("Write per-locale Markdown notes of what to translate for a release")
+                .arg(
+                    Arg::new("from")
+                        .long("from")
+                        .value_name("REF")
+                        .required(true),
+                )
+                .arg(
+                    Arg::new("to")
+                        .long("to")
+                        .value_name("REF")
+                        .default_value("HEAD"),
+                )
+                .arg(
+                    Arg::new("locale")
+                        .long("locale")
+                        .value_name("LOCALE")
+                        .default_value("all"),
+                )
+                .arg(Arg::new("release").long("release").value_name("NAME"))
+                .arg(
+                    Arg::new("output")
+                        .short('o')
+                        .long("output")
+                        .value_name("DIR"),
+                ),
+        )
+        .subcommand(
+            Command::new("stats")
+                .about("Show the translation progress of every locale")
+                .arg(Arg::new("record").long("record").action(ArgAction::SetTrue))
+                .arg(Arg::new("trend").long("trend").action(ArgAction::SetTrue))
+                .arg(
+                    Arg::new("history")
+                        .long("history")
+                        .value_name("FILE")
+                        .default_value(stats::DEFAULT_HISTORY),
+                )
+                .arg(
+                    Arg::new("since")
+                        .long("since")
+                        .value_name("DATE")
+                        .requires("trend"),
+                )
+                .arg(
+                    Arg::new("until")
+                        .long("until")
+                        .value_name("DATE")
+                        .requires("trend"),
+                ),
+        )
+        .subcommand(
+            Command::new("usage")
+                .about("Count the source call sites of each key, most used first")
+                .arg(
+                    Arg::new("src")
+                        .long("src")
+                        .value_name("DIR")
+                        .value_parser(clap::builder::ValueParser::os_string()),
+                ),
+        )
+        .subcommand(
+            Command::new("grep")
+                .about("Search keys and values of every locale")
+                .arg(Arg::new("pattern").value_name("PATTERN").required(true))
+                .arg(Arg::new("keys").long("keys").action(ArgAction::SetTrue))
+                .arg(Arg::new("values").long("values").action(ArgAction::SetTrue))
+                .arg(
+                    Arg::new("locale")
+                        .long("locale")
+                        .value_name("LOCALE")
+                        .value_delimiter(',')
+                        .action(ArgAction::Append),
+                )
+                .arg(
+                    Arg::new("ignore_case")
+                        .short('i')
+                        .long("ignore-case")
+                        .action(ArgAction::SetTrue),
+                )
+                .arg(
+                    Arg::new("fixed_strings")
+                        .short('F')
+                        .long("fixed-strings")
+                        .action(ArgAction::SetTrue),
+                ),
+        )
+        .subcommand(
+            Command::new("show")
+                .about("Print one key's value in every locale")
+                .arg(Arg::new("key").value_name("KEY").required(true)),
+        )
+        .subcommand(
+            Command::new("set")
+                .about("Set one key's value in a locale")
+                .arg(Arg::new("locale").value_name("LOCALE").required(true))
+                .arg(Arg::new("key").value_name("KEY").required(true))
+                .arg(Arg::new("value").value_name("VALUE").required(true))
+                .arg(Arg::new("create").long("create").action(ArgAction::SetTrue)),
+        )
+        .subcommand(
+            Command::new("remove")
+                .about("Remove one key from a locale")
+                .arg(Arg::new("locale").value_name("LOCALE").required(true))
+                .arg(Arg::new("key").value_name("KEY").required(true)),
+        )
+        .subcommand(
+            Command::new("replace")
+                .about("Find and replace text in the values of every locale")
+                .arg(
+                    Arg::new("pattern")
+                        .long("pattern")
+                        .value_name("PATTERN")
+                        .required(true),
+                )
+                .arg(
+                    Arg::new("with")
+                        .long("with")
+                        .value_name("TEXT")
+                        .required(true),
+                )
+                .arg(
+                    Arg::new("locale")
+                        .long("locale")
+                        .value_name("LOCALE")
+                        .value_delimiter(',')
+                        .action(ArgAction::Append),
+                )
+                .arg(
+                    Arg::new("keys")
+                        .long("keys")
+                        .value_name("GLOB")
+                        .action(ArgAction::Append),
+                )
+                .arg(
+                    Arg::new("ignore_case")
+                        .short('i')
+                        .long("ignore-case")
+                        .action(ArgAction::SetTrue),
+                )
+                .arg(
+                    Arg::new("fixed_strings")
+                        .short('F')
+                        .long("fixed-strings")
+                        .action(ArgAction::SetTrue),
+                ),
+        )
+        .subcommand(
+            Command::new("describe")
+                .about("Fill the base's .meta.json from i18n comments in the source")
+                .arg(
+                    Arg::new("src")
+                        .long("src")
+                        .value_name("DIR")
+                        .value_parser(clap::builder::ValueParser::os_string()),
+                ),
+        )
+        .subcommand(
+            Command::new("flatten")
+                .about("Convert nested keys into flat separator-joined keys")
+                .arg(
+                    Arg::new("separator")
+                        .long("separator")
+                        .value_name("SEP")
+                        .default_value("."),
+                ),
+        )
+        .subcommand(
+            Command::new("unflatten")
+                .about("Group separator-joined keys into nested objects")
+                .arg(
+                    Arg::new("separator")
+                        .long("separator")
+                        .value_name("SEP")
+                        .default_value("."),
+                ),
+        )
+        .subcommand(
+            Command::new("check")
+                .about("Run every configured check in one pass")
+                .arg(
+                    Arg::new("min_coverage")
+                        .long("min-coverage")
+                        .value_name("PERCENT")
+                        .value_parser(clap::value_parser!(f64)),
+                )
+                .arg(Arg::new("baseline").long("baseline").value_name("FILE"))
+                .arg(
+                    Arg::new("update_baseline")
+                        .long("update-baseline")
+                        .action(ArgAction::SetTrue),
+                )
+                .arg(Arg::new("fix").long("fix").action(ArgAction::SetTrue)),
+        )
+        .subcommand(
+            Command::new("machine-translations")
+                .about("List machine-translated strings awaiting review")
+                .visible_alias("mt")
+                .arg(
+                    Arg::new("record")
+                        .long("record")
+                        .value_name("FILE")
+                        .default_value(mt::DEFAULT_RECORD),
+                )
+                .subcommand(
+                    Command::new("mark")
+                        .about("Record strings as machine translated")
+                        .arg(Arg::new("locale").value_name("LOCALE").required(true))
+                        .arg(Arg::new("keys").value_name("KEY").num_args(0..))
+                        .arg(
+                            Arg::new("all")
+                                .long("all")
+                                .conflicts_with("keys")
+                                .action(ArgAction::SetTrue),
+                        ),
+                )
+                .subcommand(
+                    Command::new("approve")
+                        .about("Record machine-translated strings as reviewed")
+                        .arg(Arg::new("locale").value_name("LOCALE").required(true))
+                        .arg(Arg::new("keys").value_name("KEY").num_args(0..))
+                        .arg(
+                            Arg::new("all")
+                                .long("all")
+                                .conflicts_with("keys")
+                                .action(ArgAction::SetTrue),
+                        ),
+                ),
+        )
+        .subcommand(
+            Command::new("new-locale")
+                .about("Create the files of a new locale from the base")
+                .arg(Arg::new("locale").value_name("LOCALE").required(true))
+                .arg(Arg::new("marker").long("marker").value_name("TEXT"))
+                .arg(
+                    Arg::new("exclude_from_checks")
+                        .long("exclude-from-checks")
+                        .action(ArgAction::SetTrue),
+                ),
+        )
+        .subcommand(
+            Command::new("daemon")
+                .about("Run check periodically and notify when the results change")
+                .arg(
+                    Arg::new("interval")
+                        .long("interval")
+                        .value_name("INTERVAL")
+                        .default_value("1h"),
+                )
+                .arg(Arg::new("pull").long("pull").action(ArgAction::SetTrue))
+                .arg(Arg::new("metrics").long("metrics").value_name("ADDR")),
+        )
+        .subcommand(Command::new("rules").about("List every check and lint rule"))
+        .subcommand(
+            Command::new("fmt")
+                .about("Rewrite locale files into the configured canonical form")
+                .arg(Arg::new("check").long("check").action(ArgAction::SetTrue))
+                .arg(
+                    Arg::new("verify_roundtrip")
+                        .long("verify-roundtrip")
+                        .action(ArgAction::SetTrue)
+                        .conflicts_with("check"),
+                ),
+        )
+        .subcommand(
+            Command::new("migrate-separator")
+                .about("Replace a key separator in every locale file (and source)")
+                .arg(
+                    Arg::new("from")
+                        .long("from")
+                        .value_name("SEP")
+                        .required(true),
+                )
+                .arg(Arg::new("to").long("to").value_name("SEP").required(true))
+                .arg(
+                    Arg::new("src")
+                        .long("src")
+                        .value_name("DIR")
+                        .value_parser(clap::builder::ValueParser::os_string()),
+                ),
+        );
 
     let matches = cmd.clone().get_matches();
+    if let Some(("rules", _)) = matches.subcommand() {
+        exit(check::list());
+    }
 
     let dir: PathBuf = if let Some(d) = matches.get_one::<OsString>("directory") {
         d.clone().into()
@@ -120,223 +912,680 @@ fn main() {
         eprintln!(
             "No default directory found (checked ./locales and ./src/locales). Please specify with -d"
         );
-        std::process::exit(2);
+        exit(2);
     };
 
     let dir = dir.as_path();
+    layout::init(dir);
+    report::init(&matches);
+    write::init(&matches);
+    if patch::init(&matches, dir) {
+        write::set_preview(patch::record);
+    } else if matches.get_flag("diff") || report::verbosity() >= 1 {
+        write::set_preview(diff::show);
+    }
+    let thresholds = report::Thresholds::from_matches(&matches);
 
-    if matches.get_flag("duplicated_key") {
-        if let Some(f) = matches.get_one::<String>("file") {
-            let p = Path::new(f);
-            match find_duplicates_in_file(p) {
-                Ok(d) if d.is_empty() => println!("{}: OK", p.display()),
-                Ok(d) => {
-                    println!("{}: DUPLICATES:", p.display());
-                    for (k, c) in d {
-                        println!("  {}  ({} times)", k, c);
-                    }
-                    std::process::exit(1);
+    let config = match matches.get_one::<OsString>("config") {
+        Some(c) => config::load(Path::new(c)),
+        None if Path::new(config::DEFAULT_CONFIG).exists() => {
+            config::load(Path::new(config::DEFAULT_CONFIG))
+        }
+        None => Ok(config::Config::default()),
+    };
+    let mut config = config.unwrap_or_else(|e| {
+        eprintln!("Failed to load config: {}", e);
+        exit(2);
+    });
+    if matches.get_flag("normalize_keys") {
+        config.normalize_keys = true;
+    }
+    match matches
+        .get_one::<String>("output_style")
+        .map(String::as_str)
+    {
+        Some("compact") => config.fmt.output_style = config::OutputStyle::Compact,
+        Some("pretty") => config.fmt.output_style = config::OutputStyle::Pretty,
+        _ => {}
+    }
+    limits::init(&config);
+    rules::init(&config);
+    if let Err(e) = filter::init(&matches, &config) {
+        eprintln!("Invalid pattern: {}", e);
+        exit(2);
+    }
+    notify::init(
+        &matches,
+        dir,
+        &resolve_base(dir, matches.get_one::<String>("base")),
+        &config.notify,
+    );
+    let mut locale_set = locales::LocaleSet::default();
+
+    match matches.subcommand() {
+        Some(("rename-locales", sub)) => exit(locale::run(dir, sub)),
+        Some(("flatten", sub)) => exit(nesting::run_flatten(dir, sub, &config.fmt)),
+        Some(("unflatten", sub)) => exit(nesting::run_unflatten(dir, sub, &config.fmt)),
+        Some(("migrate-separator", sub)) => exit(nesting::run_migrate(dir, sub, &config.fmt)),
+        Some(("fmt", sub)) => {
+            let base = resolve_base(dir, matches.get_one::<String>("base"));
+            exit(pretty::run(dir, &base, sub, &config, &mut locale_set))
+        }
+        Some(("clean", sub)) => {
+            let base = resolve_base(dir, matches.get_one::<String>("base"));
+            exit(export::run_clean(dir, &base, sub, &mut locale_set))
+        }
+        Some(("apply", sub)) => exit(patch::run_apply(dir, sub, &config.fmt)),
+        Some(("verify-export", sub)) => {
+            let base = resolve_base(dir, matches.get_one::<String>("base"));
+            exit(export::run_verify(
+                dir,
+                &base,
+                sub,
+                &mut locale_set,
+                &thresholds,
+            ))
+        }
+        Some(("bundle", sub)) => {
+            let base = resolve_base(dir, matches.get_one::<String>("base"));
+            exit(match sub.subcommand() {
+                Some(("import", imp)) => bundle::import(dir, &base, imp, &config, &mut locale_set),
+                _ => bundle::run(dir, &base, sub, &config, &mut locale_set),
+            })
+        }
+        Some(("chrome", sub)) => {
+            let base = resolve_base(dir, matches.get_one::<String>("base"));
+            exit(match sub.subcommand() {
+                Some(("import", imp)) => {
+                    chrome::run_import(dir, &base, imp, &config, &mut locale_set)
                 }
-                Err(e) => {
-                    eprintln!("{}: ERROR: {}", p.display(), e);
-                    std::process::exit(2);
+                Some(("export", exp)) => {
+                    chrome::run_export(dir, &base, exp, &config, &mut locale_set)
                 }
-            }
-            return;
+                _ => 2,
+            })
         }
-        if !dir.exists() {
-            eprintln!("Directory does not exist: {}", dir.display());
-            std::process::exit(2);
+        Some(("php", sub)) => {
+            let base = resolve_base(dir, matches.get_one::<String>("base"));
+            exit(match sub.subcommand() {
+                Some(("import", imp)) => php::run_import(dir, &base, imp, &config, &mut locale_set),
+                Some(("export", exp)) => php::run_export(dir, &base, exp, &mut locale_set),
+                _ => 2,
+            })
         }
-        let mut any_errors = false;
-        let mut any_duplicates = false;
-        for p in list_json_files(dir) {
-            match find_duplicates_in_file(&p) {
-                Ok(d) if d.is_empty() => println!("{}: OK", p.display()),
-                Ok(d) => {
-                    any_duplicates = true;
-                    println!("{}: DUPLICATES:", p.display());
+        Some(("qt", sub)) => {
+            let base = resolve_base(dir, matches.get_one::<String>("base"));
+            exit(match sub.subcommand() {
+                Some(("import", imp)) => qt::run_import(dir, &base, imp, &config, &mut locale_set),
+                Some(("export", exp)) => qt::run_export(dir, &base, exp, &mut locale_set),
+                _ => 2,
+            })
+        }
+        Some(("vscode", sub)) => {
+            let base = resolve_base(dir, matches.get_one::<String>("base"));
+            exit(match sub.subcommand() {
+                Some(("export", exp)) => {
+                    vscode::run_export(dir, &base, exp, &config, &mut locale_set)
+                }
+                _ => 2,
+            })
+        }
+        Some(("changelog", sub)) => {
+            let base = resolve_base(dir, matches.get_one::<String>("base"));
+            exit(changelog::run(&base, sub))
+        }
+        Some(("release-notes", sub)) => {
+            let base = resolve_base(dir, matches.get_one::<String>("base"));
+            exit(changelog::run_notes(dir, &base, sub, &mut locale_set))
+        }
+        Some(("stats", sub)) => {
+            let base = resolve_base(dir, matches.get_one::<String>("base"));
+            exit(stats::run(dir, &base, sub, &mut locale_set))
+        }
+        Some(("usage", sub)) => {
+            let base = resolve_base(dir, matches.get_one::<String>("base"));
+            exit(usage::run(dir, &base, sub, &mut locale_set))
+        }
+        Some(("grep", sub)) => {
+            let base = resolve_base(dir, matches.get_one::<String>("base"));
+            exit(grep::run(dir, &base, sub, &mut locale_set))
+        }
+        Some(("show", sub)) => {
+            let base = resolve_base(dir, matches.get_one::<String>("base"));
+            exit(edit::run_show(dir, &base, sub, &mut locale_set))
+        }
+        Some(("set", sub)) => {
+            let base = resolve_base(dir, matches.get_one::<String>("base"));
+            exit(edit::run_set(dir, &base, sub, &config, &mut locale_set))
+        }
+        Some(("remove", sub)) => exit(edit::run_remove(dir, sub, &mut locale_set)),
+        Some(("replace", sub)) => exit(replace::run(dir, sub, &config, &mut locale_set)),
+        Some(("describe", sub)) => {
+            let base = resolve_base(dir, matches.get_one::<String>("base"));
+            exit(meta::run_describe(dir, &base, sub, &mut locale_set))
+        }
+        Some(("machine-translations", sub)) => {
+            let base = resolve_base(dir, matches.get_one::<String>("base"));
+            exit(mt::run(dir, &base, sub, &mut locale_set))
+        }
+        Some(("new-locale", sub)) => {
+            let base = resolve_base(dir, matches.get_one::<String>("base"));
+            let config_path = matches
+                .get_one::<OsString>("config")
+                .map_or(Path::new(config::DEFAULT_CONFIG), Path::new);
+            exit(locale::run_new(
+                dir,
+                &base,
+                sub,
+                &config,
+                config_path,
+                &mut locale_set,
+            ))
+        }
+        Some(("daemon", sub)) => {
+            let base = resolve_base(dir, matches.get_one::<String>("base"));
+            exit(daemon::run(dir, &base, sub, &config, &thresholds))
+        }
+        Some(("check", sub)) => {
+            let base = resolve_base(dir, matches.get_one::<String>("base"));
+            let mut config = config;
+            if let Some(min) = sub.get_one::<f64>("min_coverage") {
+                config.min_coverage = Some(*min);
+            }
+            if let Some(path) = sub.get_one::<String>("baseline") {
+                config.coverage_baseline = Some(PathBuf::from(path));
+            }
+            let update_baseline = sub.get_flag("update_baseline");
+            if update_baseline && config.coverage_baseline.is_none() {
+                eprintln!("--update-baseline needs --baseline or coverage-baseline in the config");
+                exit(2);
+            }
+            exit(check::run(
+                dir,
+                &base,
+                &config,
+                &mut locale_set,
+                &thresholds,
+                update_baseline,
+                sub.get_flag("fix"),
+            ))
+        }
+        _ => {}
+    }
+
+    if let Some(schema) = matches.get_one::<String>("schema") {
+        let files = target_files(dir, matches.get_one::<String>("file"));
+        exit(schema::run(
+            files,
+            Path::new(schema),
+            &mut locale_set,
+            &thresholds,
+        ));
+    }
+
+    if matches.get_flag("regional") {
+        let base = resolve_base(dir, matches.get_one::<String>("base"));
+        exit(regional::run(dir, &base, &mut locale_set, &thresholds));
+    }
+
+    if matches.get_flag("copied") {
+        let base = resolve_base(dir, matches.get_one::<String>("base"));
+        exit(copied::run(
+            dir,
+            &base,
+            &config,
+            &mut locale_set,
+            &thresholds,
+        ));
+    }
+
+    if matches.get_flag("meta") {
+        let base = resolve_base(dir, matches.get_one::<String>("base"));
+        exit(meta::run(dir, &base, &mut locale_set, &thresholds));
+    }
+
+    if matches.get_flag("duplicated_key") {
+        let files = target_files(dir, matches.get_one::<String>("file"));
+        let mut unreadable = 0;
+        let mut findings = Vec::new();
+        let checked = files.len();
+        report::progress_start(files.len());
+        for p in files {
+            let start = Instant::now();
+            report::progress_file(&p);
+            let found = locale_set.text(&p).map(|text| {
+                let lookalikes = if config.normalize_keys {
+                    find_lookalikes(&text)
+                } else {
+                    Vec::new()
+                };
+                (find_duplicates(&p, &text), lookalikes)
+            });
+            match found {
+                Ok(((d, None), l)) if d.is_empty() && l.is_empty() => report::ok(&p),
+                Ok(((d, syntax), l)) => {
+                    if !d.is_empty() || !l.is_empty() {
+                        report::header(&p, "DUPLICATES", d.len() + l.len());
+                    }
                     for (k, c) in d {
-                        println!("  {}  ({} times)", k, c);
+                        report::detail(format!("  {}  ({} times)", k, c));
+                        findings.push(rules::Finding::new(
+                            &p,
+                            "duplicate-key",
+                            k,
+                            rules::Severity::Error,
+                            format!("key appears {} times", c),
+                        ));
+                    }
+                    for (k, like) in l {
+                        report::detail(format!("  {}  ({:?} looks like {:?})", k, k, like));
+                        findings.push(rules::Finding::new(
+                            &p,
+                            "duplicate-key",
+                            k.clone(),
+                            rules::Severity::Error,
+                            lookalike_message(&k, &like),
+                        ));
+                    }
+                    if let Some(e) = syntax {
+                        unreadable += 1;
+                        report::error(&p, e);
                     }
                 }
                 Err(e) => {
-                    any_errors = true;
-                    eprintln!("{}: ERROR: {}", p.display(), e);
+                    unreadable += 1;
+                    report::error(&p, e);
                 }
             }
+            report::timing(&p, start);
+            report::progress_inc();
         }
-        if any_errors {
-            std::process::exit(2);
-        }
-        if any_duplicates {
-            std::process::exit(1);
+        report::progress_finish();
+        report::emit(&findings);
+        report::headline(
+            checked,
+            &format!(
+                "{} files with duplicates, {} duplicate keys",
+                report::files_with(&findings),
+                findings.len()
+            ),
+            unreadable,
+        );
+        if unreadable > 0 {
+            exit(2);
         }
-        return;
+        exit(thresholds.exit_code(findings.len(), 0));
     }
 
     if matches.get_flag("missing_key") {
-        let base_file = matches
-            .get_one::<String>("base")
-            .map(|s| s.as_str())
-            .unwrap_or("en.json");
-        let base_path = if base_file.contains('/') || base_file.contains('\\') {
-            Path::new(base_file).to_path_buf()
-        } else {
-            dir.join(base_file)
-        };
+        let base_path = resolve_base(dir, matches.get_one::<String>("base"));
         if !base_path.exists() {
             eprintln!("Base file {} not found", base_path.display());
-            std::process::exit(2);
+            exit(2);
+        }
+        if !layout::namespaced()
+            && let Err(e) = locale_set.value(&base_path)
+        {
+            eprintln!("Failed to read {}: {}", base_path.display(), e);
+            exit(2);
         }
-        let base_v = match read_json(&base_path) {
-            Ok(v) => v,
-            Err(e) => {
-                eprintln!("Failed to read {}: {}", base_path.display(), e);
-                std::process::exit(2);
-            }
-        };
-        let base_keys = keys_from_value(&base_v);
         let export_dir = matches.get_one::<String>("export");
-        if let Some(f) = matches.get_one::<String>("file") {
-            let p = Path::new(f);
-            match read_json(p) {
-                Ok(v) => {
-                    let keys: HashSet<String> = keys_from_value(&v).into_iter().collect();
+        let locations = matches
+            .get_flag("locations")
+            .then(|| usage::scan(&usage::src_dir(dir, &matches)).0);
+        let mut files = target_files(dir, matches.get_one::<String>("file"));
+        let mut unreadable = 0;
+        let mut findings = Vec::new();
+        if layout::namespaced() && matches.get_one::<String>("file").is_none() {
+            findings.extend(check_namespace_files(dir, &base_path));
+            // Extra namespace files were just reported; there is no base to
+            // compare their keys against.
+            files.retain(|p| layout::base_for(&base_path, p).exists());
+        }
+        let checked = files
+            .iter()
+            .filter(|p| !layout::is_base(&base_path, p))
+            .count();
+        report::progress_start(files.len());
+        for p in files {
+            if layout::is_base(&base_path, &p) {
+                report::progress_inc();
+                continue;
+            }
+            let start = Instant::now();
+            report::progress_file(&p);
+            let bp = layout::base_for(&base_path, &p);
+            let keys = locale_set.text(&p).and_then(|text| scan_keys(&p, &text));
+            match keys.and_then(|keys| locale_set.value(&bp).map(|b| (keys, b))) {
+                Ok((keys, base_v)) => {
+                    let base_keys = keys_from_value(&base_v);
                     let missing: Vec<String> = base_keys
                         .iter()
                         .filter(|k| !keys.contains(*k))
                         .cloned()
                         .collect();
                     if missing.is_empty() {
-                        println!("{}: OK", p.display());
+                        report::ok(&p);
                     } else {
-                        println!("{}: MISSING:", p.display());
+                        report::header(&p, "MISSING", missing.len());
+                        let own = matches
+                            .get_flag("suggest")
+                            .then(|| locale_set.value(&p).ok())
+                            .flatten();
                         for k in &missing {
-                            println!("  {}", k);
+                            print_missing_key(k, &base_v);
+                            let mut message = format!("missing (present in {})", bp.display());
+                            if let Some(s) = own.as_ref().and_then(|v| suggest::best(k, &base_v, v))
+                            {
+                                report::detail(format!(
+                                    "    suggest {:?} (translation of {}, {:.0}% similar)",
+                                    s.translation,
+                                    s.key,
+                                    s.similarity * 100.0
+                                ));
+                                message.push_str(&format!(
+                                    "; suggest {:?} (translation of {})",
+                                    s.translation, s.key
+                                ));
+                            }
+                            findings.push(rules::Finding::new(
+                                &p,
+                                "missing-key",
+                                k.clone(),
+                                rules::Severity::Error,
+                                message,
+                            ));
                         }
                         if let Some(ed) = export_dir {
-                            let file_name = format!(
-                                "{}_missing.json",
-                                p.file_stem().unwrap().to_str().unwrap()
-                            );
-                            let export_path = Path::new(ed).join(file_name);
-                            let json = serde_json::to_string_pretty(&missing).unwrap();
-                            if let Err(e) = fs::write(&export_path, json) {
+                            let export_path = Path::new(ed).join(export::file_name(&p));
+                            let json = match &locations {
+                                Some(locs) => serde_json::to_string_pretty(&usage::annotate(
+                                    &missing,
+                                    &base_v,
+                                    layout::namespace_of(&p).as_deref(),
+                                    locs,
+                                    // A broken sidecar is reported by --meta.
+                                    &meta::load(&bp, &mut locale_set).unwrap_or_default(),
+                                )),
+                                None => serde_json::to_string_pretty(&missing),
+                            }
+                            .unwrap();
+                            if let Err(e) = write::write_file(&export_path, &json) {
                                 eprintln!("Failed to write {}: {}", export_path.display(), e);
                             } else {
-                                println!("Exported missing keys to {}", export_path.display());
+                                report::info(format!(
+                                    "{} missing keys to {}",
+                                    write::verb("Exported", "export"),
+                                    export_path.display()
+                                ));
                             }
-                            std::process::exit(1);
                         }
                     }
                 }
                 Err(e) => {
-                    eprintln!("{}: ERROR: {}", p.display(), e);
-                    std::process::exit(2);
+                    unreadable += 1;
+                    report::error(&p, e);
                 }
             }
-            return;
+            report::timing(&p, start);
+            report::progress_inc();
         }
-        if !dir.exists() {
-            eprintln!("Directory does not exist: {}", dir.display());
-            std::process::exit(2);
+        report::progress_finish();
+        report::emit(&findings);
+        let (keys, namespace_files): (Vec<_>, Vec<_>) =
+            findings.iter().partition(|f| f.rule == "missing-key");
+        let missing_files = namespace_files
+            .iter()
+            .filter(|f| f.rule == "missing-file")
+            .count();
+        let mut problems = format!(
+            "{} missing keys across {} locales",
+            keys.len(),
+            keys.iter()
+                .map(|f| f.locale.as_str())
+                .collect::<HashSet<_>>()
+                .len()
+        );
+        if layout::namespaced() {
+            problems.push_str(&format!(
+                ", {} missing and {} extra namespace files",
+                missing_files,
+                namespace_files.len() - missing_files
+            ));
         }
-        let mut any_missing = false;
-        for p in list_json_files(dir) {
-            if p == base_path {
-                continue;
+        report::headline(checked, &problems, unreadable);
+        if unreadable > 0 {
+            exit(2);
+        }
+        let errors = findings
+            .iter()
+            .filter(|f| f.severity == rules::Severity::Error)
+            .count();
+        exit(thresholds.exit_code(errors, findings.len() - errors));
+    }
+
+    if let Some(ids) = matches.get_many::<String>("lint") {
+        let mut selected = Vec::new();
+        for id in ids {
+            if id == "all" {
+                selected.extend(rules::RULES.iter());
+            } else if let Some(r) = rules::find_rule(id) {
+                selected.push(r);
+            } else {
+                eprintln!("Unknown rule: {}", id);
+                exit(2);
             }
-            match read_json(&p) {
-                Ok(v) => {
-                    let keys: HashSet<String> = keys_from_value(&v).into_iter().collect();
-                    let missing: Vec<String> = base_keys
-                        .iter()
-                        .filter(|k| !keys.contains(*k))
-                        .cloned()
-                        .collect();
-                    if missing.is_empty() {
-                        println!("{}: OK", p.display());
-                    } else {
-                        any_missing = true;
-                        println!("{}: MISSING:", p.display());
-                        for k in &missing {
-                            println!("  {}", k);
-                        }
-                        if let Some(ed) = export_dir {
-                            let file_name = format!(
-                                "{}_missing.json",
-                                p.file_stem().unwrap().to_str().unwrap()
-                            );
-                            let export_path = Path::new(ed).join(file_name);
-                            let json = serde_json::to_string_pretty(&missing).unwrap();
-                            if let Err(e) = fs::write(&export_path, json) {
-                                eprintln!("Failed to write {}: {}", export_path.display(), e);
-                            } else {
-                                println!("Exported missing keys to {}", export_path.display());
-                            }
-                        }
-                    }
-                }
-                Err(e) => {
-                    eprintln!("{}: ERROR: {}", p.display(), e);
+        }
+        let base_path = resolve_base(dir, matches.get_one::<String>("base"));
+        let files = target_files(dir, matches.get_one::<String>("file"));
+        let mut unreadable = 0;
+        let mut all = Vec::new();
+        let checked = files.len();
+        report::progress_start(files.len());
+        for p in files {
+            let start = Instant::now();
+            report::progress_file(&p);
+            let base = if layout::is_base(&base_path, &p) {
+                None
+            } else {
+                locale_set.value(&layout::base_for(&base_path, &p)).ok()
+            };
+            let (findings, fixed, err) = match locale_set.text(&p) {
+                Ok(text) => rules::lint_file(
+                    &selected,
+                    &p,
+                    &text,
+                    base.as_deref(),
+                    &config,
+                    matches.get_flag("fix"),
+                ),
+                Err(e) => (Vec::new(), Vec::new(), Some(e)),
+            };
+            if !fixed.is_empty() {
+                locale_set.forget(&p);
+                report::info(format!(
+                    "{} {} fix(es) to {}",
+                    write::verb("Applied", "apply"),
+                    fixed.len(),
+                    p.display()
+                ));
+                for k in &fixed {
+                    report::info_detail(format!("  {}", k));
                 }
             }
+            if err.is_none() || !findings.is_empty() {
+                print_findings(&p, "LINT", &findings);
+            }
+            if let Some(e) = err {
+                unreadable += 1;
+                report::error(&p, e);
+            }
+            all.extend(findings);
+            report::timing(&p, start);
+            report::progress_inc();
         }
-        if any_missing {
-            std::process::exit(1);
-        } else {
-            std::process::exit(0);
+        report::progress_finish();
+        report::emit(&all);
+        let errors = all
+            .iter()
+            .filter(|f| f.severity == rules::Severity::Error)
+            .count();
+        report::headline(
+            checked,
+            &format!(
+                "{} errors and {} warnings in {} files",
+                errors,
+                all.len() - errors,
+                report::files_with(&all)
+            ),
+            unreadable,
+        );
+        if unreadable > 0 {
+            exit(2);
         }
+        exit(thresholds.exit_code(errors, all.len() - errors));
     }
 
     if matches.get_flag("sort") {
-        let base_file = matches
-            .get_one::<String>("base")
-            .map(|s| s.as_str())
-            .unwrap_or("en.json");
-        let base_path = if base_file.contains('/') || base_file.contains('\\') {
-            Path::new(base_file).to_path_buf()
-        } else {
-            dir.join(base_file)
-        };
+        let base_path = resolve_base(dir, matches.get_one::<String>("base"));
         if !base_path.exists() {
             eprintln!("Base file {} not found", base_path.display());
-            std::process::exit(2);
+            exit(2);
         }
-        let base_indexmap: IndexMap<String, Value> =
-            serde_json::from_str(&fs::read_to_string(&base_path).unwrap()).unwrap_or_else(|e| {
-                eprintln!("Failed to parse {} as IndexMap: {}", base_path.display(), e);
-                std::process::exit(2);
-            });
-        let keys: Vec<String> = base_indexmap.keys().cloned().collect();
+        let order = match matches.get_one::<String>("order") {
+            Some(o) => order::load(Path::new(o)).unwrap_or_else(|e| {
+                eprintln!("Failed to read order file: {}", e);
+                exit(2);
+            }),
+            None => Vec::new(),
+        };
+        let unknown = order::Unknown::parse(matches.get_one::<String>("unknown_keys").unwrap());
+        let mut base_keys = |p: &Path| -> Result<Vec<String>, String> {
+            let bp = layout::base_for(&base_path, p);
+            locale_set.value(&bp).map(|v| keys_from_value(&v))
+        };
         if let Some(f) = matches.get_one::<String>("file") {
             let p = Path::new(f);
-            match write_sorted(p, &keys) {
-                Ok(_) => println!("Sorted {}", p.display()),
+            match base_keys(p).and_then(|keys| write_sorted(p, &keys, &order, unknown, &config.fmt))
+            {
+                Ok(sorted) => {
+                    print_sorted(p, &sorted);
+                    exit(if sorted.1.is_empty() { 0 } else { 1 });
+                }
                 Err(e) => {
                     eprintln!("Failed to sort {}: {}", p.display(), e);
-                    std::process::exit(2);
+                    exit(2);
                 }
             }
-            return;
         }
         if !dir.exists() {
             eprintln!("Directory does not exist: {}", dir.display());
-            std::process::exit(2);
+            exit(2);
         }
+        if !layout::namespaced()
+            && let Err(e) = base_keys(&base_path)
+        {
+            eprintln!("Failed to read {}: {}", base_path.display(), e);
+            exit(2);
+        }
+        let mut rejected = 0;
         for p in list_json_files(dir) {
-            if p == base_path {
+            // The base only moves when an order file says how.
+            if layout::is_base(&base_path, &p) && order.is_empty() {
                 continue;
             }
-            match write_sorted(&p, &keys) {
-                Ok(_) => println!("Sorted {}", p.display()),
+            match base_keys(&p)
+                .and_then(|keys| write_sorted(&p, &keys, &order, unknown, &config.fmt))
+            {
+                Ok(sorted) => {
+                    if !sorted.1.is_empty() {
+                        rejected += 1;
+                    }
+                    print_sorted(&p, &sorted);
+                }
                 Err(e) => eprintln!("Failed to sort {}: {}", p.display(), e),
             }
         }
-        std::process::exit(0);
+        exit(if rejected > 0 { 1 } else { 0 });
     }
 
     println!("{}", cmd.render_help());
-    std::process::exit(0);
+    exit(0);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use config::FmtStyle;
+    use std::fs;
+
+    fn temp_file(name: &str, contents: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("cvr-i18n-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join(name);
+        fs::write(&path, contents).unwrap();
+        path
+    }
+
+    fn keys(list: &[&str]) -> Vec<String> {
+        list.iter().map(|k| k.to_string()).collect()
+    }
+
+    #[test]
+    fn sort_twice_is_byte_identical() {
+        let path = temp_file(
+            "twice.json",
+            r#"{"zeta":"z","c":"3","nested":{"y":"1","x":"2"},"alpha":"a","b":"2","a":"1"}"#,
+        );
+        let base = keys(&["a", "b", "c", "nested"]);
+        for unknown in ["top", "bottom", "alpha"] {
+            let unknown = order::Unknown::parse(unknown);
+            write_sorted(&path, &base, &[], unknown, &FmtStyle::default()).unwrap();
+            let first = fs::read(&path).unwrap();
+            let (moved, _) =
+                write_sorted(&path, &base, &[], unknown, &FmtStyle::default()).unwrap();
+            assert_eq!(fs::read(&path).unwrap(), first);
+            assert!(moved.is_empty());
+        }
+    }
+
+    #[test]
+    fn unknown_keys_sort_the_same_whatever_the_input_order() {
+        let base = keys(&["a"]);
+        let one = temp_file("one.json", r#"{"q":"1","a":"2","m":"3","b":"4"}"#);
+        let two = temp_file("two.json", r#"{"b":"4","m":"3","a":"2","q":"1"}"#);
+        write_sorted(
+            &one,
+            &base,
+            &[],
+            order::Unknown::Alpha,
+            &FmtStyle::default(),
+        )
+        .unwrap();
+        write_sorted(
+            &two,
+            &base,
+            &[],
+            order::Unknown::Alpha,
+            &FmtStyle::default(),
+        )
+        .unwrap();
+        let sorted = fs::read_to_string(&one).unwrap();
+        assert_eq!(sorted, fs::read_to_string(&two).unwrap());
+        let v = read_json(&one).unwrap();
+        assert_eq!(keys_from_value(&v), keys(&["a", "b", "m", "q"]));
+    }
+
+    #[test]
+    fn read_and_write_keep_key_order() {
+        let text = "{\n  \"z\": \"1\",\n  \"a\": {\n    \"y\": \"2\",\n    \"b\": \"3\"\n  },\n  \"m\": \"4\"\n}";
+        let path = temp_file("roundtrip.json", text);
+        let v = read_json(&path).unwrap();
+        let style = FmtStyle {
+            final_newline: false,
+            ..FmtStyle::default()
+        };
+        write_json(&path, &v, &style).unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), text);
+    }
 }
@@ -0,0 +1,307 @@
+//! `--meta`: the metadata sidecar of a base file (`en.meta.json`), which
+//! describes each key for translators, kept in sync with the base: no
+//! descriptions of keys the base dropped, and a warning for base keys
+//! without one. The sidecar maps dotted keys, or nested objects like the
+//! locale files, to a description, or to an object with the `description`
+//! and a `context` list of URLs (design screenshots, docs) for the key.
+//!
+//! `describe` fills the sidecar from `// i18n:` comments and JSDoc blocks
+//! next to the `t()` calls in the source, so developers document a string
+//! where they use it.
+
+use crate::layout;
+use crate::locales::LocaleSet;
+use crate::report::{self, Thresholds};
+use crate::rules::{self, Finding, Severity};
+use crate::{source, usage, write};
+use clap::ArgMatches;
+use serde_json::{Map, Value};
+use std::collections::{BTreeMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// What the sidecar says about one key.
+#[derive(Default)]
+pub struct KeyMeta {
+    pub description: String,
+    /// URLs showing where the string appears.
+    pub context: Vec<String>,
+}
+
+/// Metadata of each dotted key of one base file.
+pub type Metadata = BTreeMap<String, KeyMeta>;
+
+/// Collects the entries of the sidecar object `map` under `prefix`. An
+/// object with a `context` list is an entry; other objects nest keys.
+fn collect(map: &Map<String, Value>, prefix: &str, out: &mut Metadata) {
+    for (k, v) in map {
+        let key = if prefix.is_empty() {
+            k.clone()
+        } else {
+            format!("{}.{}", prefix, k)
+        };
+        match v {
+            Value::String(s) => {
+                out.insert(
+                    key,
+                    KeyMeta {
+                        description: s.clone(),
+                        context: Vec::new(),
+                    },
+                );
+            }
+            Value::Object(entry) if entry.get("context").is_some_and(Value::is_array) => {
+                let description = entry.get("description").and_then(Value::as_str);
+                let context = entry["context"].as_array().unwrap();
+                out.insert(
+                    key,
+                    KeyMeta {
+                        description: description.unwrap_or_default().to_string(),
+                        context: context
+                            .iter()
+                            .filter_map(|u| u.as_str().map(str::to_string))
+                            .collect(),
+                    },
+                );
+            }
+            Value::Object(nested) => collect(nested, &key, out),
+            _ => {}
+        }
+    }
+}
+
+/// The metadata of the base file `base_file`, empty when it has no
+/// sidecar.
+pub fn load(base_file: &Path, locale_set: &mut LocaleSet) -> Result<Metadata, String> {
+    let path = layout::meta_for(base_file);
+    if !path.exists() {
+        return Ok(Metadata::new());
+    }
+    let mut out = Metadata::new();
+    if let Value::Object(map) = &*locale_set.value(&path)? {
+        collect(map, "", &mut out);
+    }
+    Ok(out)
+}
+
+/// The base file, or the namespace files of the base locale directory.
+fn base_files(dir: &Path, base: &Path) -> Vec<PathBuf> {
+    if !layout::namespaced() {
+        return vec![base.to_path_buf()];
+    }
+    let mut files: Vec<PathBuf> = layout::files(dir)
+        .into_iter()
+        .filter(|p| layout::is_base(base, p))
+        .collect();
+    files.sort();
+    files
+}
+
+/// Orphan and missing descriptions of every base file that has a sidecar,
+/// reported against the sidecar, and how many files could not be read.
+pub fn findings(dir: &Path, base: &Path, locale_set: &mut LocaleSet) -> (Vec<Finding>, usize) {
+    let mut out = Vec::new();
+    let mut unreadable = 0;
+    for b in base_files(dir, base) {
+        let path = layout::meta_for(&b);
+        if !path.exists() {
+            continue;
+        }
+        let read = load(&b, locale_set).and_then(|d| locale_set.value(&b).map(|v| (d, v)));
+        let (descriptions, base_v) = match read {
+            Ok(r) => r,
+            Err(e) => {
+                unreadable += 1;
+                report::error(&path, e);
+                continue;
+            }
+        };
+        let keys: Vec<String> = rules::string_entries(&base_v)
+            .into_iter()
+            .map(|(k, _)| k)
+            .collect();
+        let known: HashSet<&String> = keys.iter().collect();
+        for k in descriptions.keys().filter(|k| !known.contains(k)) {
+            out.push(Finding::new(
+                &path,
+                "meta-orphan",
+                k.clone(),
+                Severity::Error,
+                format!("describes a key {} does not have", b.display()),
+            ));
+        }
+        for k in &keys {
+            if descriptions
+                .get(k)
+                .is_none_or(|d| d.description.trim().is_empty())
+            {
+                out.push(Finding::new(
+                    &path,
+                    "meta-undocumented",
+                    k.clone(),
+                    Severity::Warning,
+                    "no description".to_string(),
+                ));
+            }
+        }
+    }
+    (out, unreadable)
+}
+
+pub fn run(dir: &Path, base: &Path, locale_set: &mut LocaleSet, thresholds: &Thresholds) -> i32 {
+    let (found, unreadable) = findings(dir, base, locale_set);
+    // Findings come sidecar by sidecar.
+    for group in found.chunk_by(|a, b| a.file == b.file) {
+        crate::print_findings(&group[0].file, "PROBLEMS", group);
+    }
+    report::emit(&found);
+    let errors = found
+        .iter()
+        .filter(|f| f.severity == Severity::Error)
+        .count();
+    report::headline(
+        base_files(dir, base).len(),
+        &format!(
+            "{} orphan and {} missing descriptions",
+            errors,
+            found.len() - errors
+        ),
+        unreadable,
+    );
+    if unreadable > 0 {
+        return 2;
+    }
+    thresholds.exit_code(errors, found.len() - errors)
+}
+
+/// Where a description came from in the source.
+struct Found {
+    description: String,
+    at: String,
+}
+
+/// The descriptions in the source below `src` by key, the first found
+/// winning, and the `file:line`s of descriptions contradicting it.
+fn from_source(src: &Path) -> (BTreeMap<String, Found>, Vec<(String, String)>) {
+    let mut found: BTreeMap<String, Found> = BTreeMap::new();
+    let mut conflicts = Vec::new();
+    for f in source::files(src) {
+        let Ok(text) = fs::read_to_string(&f) else {
+            continue;
+        };
+        for (key, line, description) in source::described_calls(&text) {
+            let at = format!("{}:{}", f.display(), line);
+            match found.get(&key) {
+                Some(first) if first.description != description => {
+                    conflicts.push((key, format!("{} (kept {})", at, first.at)));
+                }
+                Some(_) => {}
+                None => {
+                    found.insert(key, Found { description, at });
+                }
+            }
+        }
+    }
+    (found, conflicts)
+}
+
+/// `descriptions` as a sidecar: base keys in base order, then the rest.
+fn sidecar_text(descriptions: &Metadata, keys: &[String]) -> String {
+    let mut out = Map::new();
+    for k in keys.iter().chain(descriptions.keys()) {
+        let Some(d) = descriptions.get(k) else {
+            continue;
+        };
+        if out.contains_key(k) {
+            continue;
+        }
+        let v = if d.context.is_empty() {
+            Value::String(d.description.clone())
+        } else {
+            let mut entry = Map::new();
+            if !d.description.is_empty() {
+                entry.insert("description".into(), d.description.clone().into());
+            }
+            entry.insert("context".into(), d.context.clone().into());
+            Value::Object(entry)
+        };
+        out.insert(k.clone(), v);
+    }
+    serde_json::to_string_pretty(&Value::Object(out)).unwrap() + "\n"
+}
+
+pub fn run_describe(dir: &Path, base: &Path, m: &ArgMatches, locale_set: &mut LocaleSet) -> i32 {
+    let (found, conflicts) = from_source(&usage::src_dir(dir, m));
+    // Base file -> its keys described in the source.
+    let mut by_file: BTreeMap<PathBuf, Vec<(String, &Found)>> = BTreeMap::new();
+    for (key, f) in &found {
+        let (file, k) = match key.split_once(':') {
+            Some((ns, k)) if layout::namespaced() => (base.join(format!("{}.json", ns)), k),
+            _ => (base.to_path_buf(), key.as_str()),
+        };
+        by_file.entry(file).or_default().push((k.to_string(), f));
+    }
+    let mut unknown = Vec::new();
+    let mut failed = false;
+    for (file, described) in by_file {
+        let read = locale_set
+            .value(&file)
+            .and_then(|v| load(&file, locale_set).map(|d| (v, d)));
+        let (base_v, mut descriptions) = match read {
+            Ok(r) => r,
+            Err(e) => {
+                report::error(&file, e);
+                failed = true;
+                continue;
+            }
+        };
+        let keys: Vec<String> = rules::string_entries(&base_v)
+            .into_iter()
+            .map(|(k, _)| k)
+            .collect();
+        let (mut added, mut updated) = (0, 0);
+        for (k, f) in described {
+            if !keys.contains(&k) {
+                unknown.push((k, f.at.clone()));
+                continue;
+            }
+            let entry = descriptions.entry(k).or_default();
+            if entry.description.is_empty() {
+                added += 1;
+            } else if entry.description != f.description {
+                updated += 1;
+            }
+            entry.description = f.description.clone();
+        }
+        if added + updated == 0 {
+            continue;
+        }
+        let path = layout::meta_for(&file);
+        if let Err(e) = write::write_file(&path, &sidecar_text(&descriptions, &keys)) {
+            eprintln!("Failed to write {}: {}", path.display(), e);
+            failed = true;
+            continue;
+        }
+        report::info(format!(
+            "{} {} ({} added, {} updated)",
+            write::verb("Wrote", "write"),
+            path.display(),
+            added,
+            updated
+        ));
+    }
+    if !conflicts.is_empty() {
+        report::line(format!("CONFLICTING DESCRIPTIONS ({}):", conflicts.len()));
+        for (k, at) in &conflicts {
+            report::detail(format!("  {}: {}", k, at));
+        }
+    }
+    if !unknown.is_empty() {
+        report::line(format!("NOT IN THE BASE ({}):", unknown.len()));
+        for (k, at) in &unknown {
+            report::detail(format!("  {}: {}", k, at));
+        }
+    }
+    report::info(format!("{} keys described in the source", found.len()));
+    if failed { 2 } else { 0 }
+}
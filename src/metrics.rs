@@ -0,0 +1,166 @@
+//! `daemon --metrics ADDR`: a Prometheus `/metrics` endpoint with the
+//! translation health of the last run, so a Grafana board can track it
+//! alongside other project metrics.
+
+use crate::api;
+use crate::layout;
+use crate::locales::LocaleSet;
+use crate::report;
+use crate::rules;
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+use std::io::{self, BufRead, BufReader, Write};
+use std::net::{TcpListener, TcpStream};
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+/// The metrics text the endpoint serves, replaced after every run.
+pub type Shared = Arc<Mutex<String>>;
+
+#[derive(Default)]
+struct Counts {
+    translated: usize,
+    missing: usize,
+    empty: usize,
+    extra: usize,
+}
+
+impl Counts {
+    fn coverage(&self) -> f64 {
+        let total = self.translated + self.missing + self.empty;
+        if total == 0 {
+            100.0
+        } else {
+            self.translated as f64 * 100.0 / total as f64
+        }
+    }
+}
+
+/// A per-locale gauge: name, help text and value.
+type Gauge = (&'static str, &'static str, fn(&Counts) -> f64);
+
+const GAUGES: &[Gauge] = &[
+    ("i18n_missing_keys", "Base strings the locale lacks.", |c| {
+        c.missing as f64
+    }),
+    (
+        "i18n_empty_keys",
+        "Base strings the locale leaves empty.",
+        |c| c.empty as f64,
+    ),
+    (
+        "i18n_translated_keys",
+        "Base strings the locale translates.",
+        |c| c.translated as f64,
+    ),
+    (
+        "i18n_extra_keys",
+        "Strings the locale has that the base does not.",
+        |c| c.extra as f64,
+    ),
+    (
+        "i18n_coverage_percent",
+        "Translated base strings as a percentage.",
+        Counts::coverage,
+    ),
+];
+
+/// `value` as a label value: backslashes, quotes and newlines escaped.
+fn label(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+/// The metrics of every locale but the base, summed over its namespace
+/// files, with the exit code and time of the run.
+pub fn render(
+    dir: &Path,
+    base: &Path,
+    exit_code: i32,
+    now: u64,
+    locale_set: &mut LocaleSet,
+) -> String {
+    let mut locales: BTreeMap<String, Counts> = BTreeMap::new();
+    for p in crate::target_files(dir, None) {
+        if layout::is_base(base, &p) {
+            continue;
+        }
+        let bp = layout::base_for(base, &p);
+        let (doc, base_v) = match (locale_set.value(&p), locale_set.value(&bp)) {
+            (Ok(d), Ok(b)) => (d, b),
+            (Err(e), _) | (_, Err(e)) => {
+                report::error(&p, e);
+                continue;
+            }
+        };
+        let s = api::stats_of(&doc, Some(&base_v));
+        let c = locales.entry(rules::locale_of(&p)).or_default();
+        c.translated += s.translated;
+        c.missing += s.missing;
+        c.empty += s.empty;
+        c.extra += s.extra;
+    }
+    let mut out = String::new();
+    for (name, help, value) in GAUGES {
+        let _ = writeln!(out, "# HELP {} {}\n# TYPE {} gauge", name, help, name);
+        for (locale, c) in &locales {
+            let _ = writeln!(out, "{}{{locale=\"{}\"}} {}", name, label(locale), value(c));
+        }
+    }
+    let _ = writeln!(
+        out,
+        "# HELP i18n_check_exit_code Exit code of the last check run.\n\
+         # TYPE i18n_check_exit_code gauge\n\
+         i18n_check_exit_code {}",
+        exit_code
+    );
+    let _ = writeln!(
+        out,
+        "# HELP i18n_last_run_timestamp_seconds When the last check ran.\n\
+         # TYPE i18n_last_run_timestamp_seconds gauge\n\
+         i18n_last_run_timestamp_seconds {}",
+        now
+    );
+    out
+}
+
+/// Answers one request: the metrics on `/metrics`, 404 elsewhere.
+fn respond(mut stream: TcpStream, metrics: &Shared) -> io::Result<()> {
+    stream.set_read_timeout(Some(Duration::from_secs(5)))?;
+    let mut reader = BufReader::new(&stream);
+    let mut request = String::new();
+    reader.read_line(&mut request)?;
+    // The headers are read and ignored.
+    let mut header = String::new();
+    while reader.read_line(&mut header)? > 2 {
+        header.clear();
+    }
+    let path = request.split_whitespace().nth(1).unwrap_or("");
+    let (status, body) = if path.split('?').next() == Some("/metrics") {
+        ("200 OK", metrics.lock().unwrap().clone())
+    } else {
+        ("404 Not Found", "not found\n".to_string())
+    };
+    write!(
+        stream,
+        "HTTP/1.1 {}\r\nContent-Type: text/plain; version=0.0.4\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        body.len(),
+        body
+    )
+}
+
+/// Serves `metrics` on `addr` from a background thread.
+pub fn serve(addr: &str, metrics: Shared) -> Result<(), String> {
+    let listener = TcpListener::bind(addr).map_err(|e| format!("listen on {}: {}", addr, e))?;
+    thread::spawn(move || {
+        for stream in listener.incoming().flatten() {
+            let _ = respond(stream, &metrics);
+        }
+    });
+    Ok(())
+}
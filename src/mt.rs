@@ -0,0 +1,167 @@
+//! `machine-translations`: which strings were machine translated and are
+//! still awaiting human review. The record lives in a sidecar file rather
+//! than in the locale files, which the app loads as they are: for every
+//! locale, each machine-translated key and the value written for it. A
+//! string counts as reviewed once it is approved or its value changes.
+
+use crate::changelog::{self, Strings};
+use crate::locales::LocaleSet;
+use crate::report::{self, Format};
+use crate::write;
+use clap::ArgMatches;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+
+pub const DEFAULT_RECORD: &str = "cvr-i18n.mt.json";
+
+/// Locale -> key -> the machine translation written for it.
+type Record = BTreeMap<String, Strings>;
+
+fn load(path: &Path) -> Result<Record, String> {
+    match fs::read_to_string(path) {
+        Ok(text) => {
+            serde_json::from_str(&text).map_err(|e| format!("parse {}: {}", path.display(), e))
+        }
+        Err(_) => Ok(Record::new()),
+    }
+}
+
+fn save(path: &Path, record: &Record) -> Result<(), String> {
+    let text = serde_json::to_string_pretty(record).map_err(|e| e.to_string())? + "\n";
+    write::write_file(path, &text)
+}
+
+/// The keys of `m`'s `KEY` arguments, or with `--all` every key of `own`
+/// that `all` picks, failing on keys the locale does not have.
+fn keys_arg(
+    m: &ArgMatches,
+    own: &Strings,
+    all: impl Fn(&str) -> bool,
+) -> Result<Vec<String>, String> {
+    if m.get_flag("all") {
+        return Ok(own.keys().filter(|k| all(k)).cloned().collect());
+    }
+    let keys: Vec<String> = m
+        .get_many::<String>("keys")
+        .map(|k| k.cloned().collect())
+        .unwrap_or_default();
+    if keys.is_empty() {
+        return Err("give the keys, or --all".to_string());
+    }
+    match keys.iter().find(|k| !own.contains_key(*k)) {
+        Some(k) => Err(format!("no string {}", k)),
+        None => Ok(keys),
+    }
+}
+
+/// Machine-translated strings of every locale whose value is still the one
+/// written, as locale -> keys.
+fn pending(record: &Record, locales: &BTreeMap<String, Strings>) -> BTreeMap<String, Vec<String>> {
+    let mut out = BTreeMap::new();
+    for (locale, written) in record {
+        let Some(own) = locales.get(locale) else {
+            continue;
+        };
+        let keys: Vec<String> = written
+            .iter()
+            .filter(|(k, v)| own.get(*k) == Some(*v))
+            .map(|(k, _)| k.clone())
+            .collect();
+        if !keys.is_empty() {
+            out.insert(locale.clone(), keys);
+        }
+    }
+    out
+}
+
+pub fn run(dir: &Path, base: &Path, m: &ArgMatches, locale_set: &mut LocaleSet) -> i32 {
+    let path = Path::new(m.get_one::<String>("record").unwrap());
+    let mut record = match load(path) {
+        Ok(r) => r,
+        Err(e) => {
+            eprintln!("Failed to read the record: {}", e);
+            return 2;
+        }
+    };
+    let locales = match changelog::locales(dir, base, locale_set) {
+        Ok((_, l)) => l,
+        Err(e) => {
+            eprintln!("Failed to read {}", e);
+            return 2;
+        }
+    };
+    if let Some((action, sub)) = m.subcommand() {
+        let locale = sub.get_one::<String>("locale").unwrap();
+        let Some(own) = locales.get(locale) else {
+            eprintln!("No locale {} in {}", locale, dir.display());
+            return 2;
+        };
+        let written = record.entry(locale.clone()).or_default();
+        let keys = if action == "mark" {
+            keys_arg(sub, own, |k| !own[k].trim().is_empty())
+        } else {
+            keys_arg(sub, written, |_| true)
+        };
+        let keys = match keys {
+            Ok(k) => k,
+            Err(e) => {
+                eprintln!("{}", e);
+                return 2;
+            }
+        };
+        for k in &keys {
+            if action == "mark" {
+                written.insert(k.clone(), own[k].clone());
+            } else {
+                written.remove(k);
+            }
+        }
+        if written.is_empty() {
+            record.remove(locale);
+        }
+        if let Err(e) = save(path, &record) {
+            eprintln!("Failed to write {}: {}", path.display(), e);
+            return 2;
+        }
+        let done = if action == "mark" {
+            write::verb("Marked", "mark")
+        } else {
+            write::verb("Approved", "approve")
+        };
+        report::info(format!("{} {} strings of {}", done, keys.len(), locale));
+        return 0;
+    }
+    let pending = pending(&record, &locales);
+    if report::style().format == Format::Csv {
+        println!("locale,key,value");
+        for (locale, keys) in &pending {
+            for k in keys {
+                println!(
+                    "{},{},{}",
+                    report::csv_field(locale),
+                    report::csv_field(k),
+                    report::csv_field(&locales[locale][k])
+                );
+            }
+        }
+    } else {
+        for (locale, keys) in &pending {
+            report::header(Path::new(locale), "AWAITING REVIEW", keys.len());
+            for k in keys {
+                report::detail(format!(
+                    "  {}: {}",
+                    k,
+                    changelog::quoted(&locales[locale][k])
+                ));
+            }
+        }
+        let total: usize = pending.values().map(Vec::len).sum();
+        report::info(format!(
+            "{} machine-translated strings awaiting review in {} locales",
+            total,
+            pending.len()
+        ));
+    }
+    if pending.is_empty() { 0 } else { 1 }
+}
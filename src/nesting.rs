@@ -0,0 +1,302 @@
+//! `flatten`, `unflatten` and `migrate-separator`: reshaping the keys of
+//! every locale file at once.
+
+use crate::config::FmtStyle;
+use clap::ArgMatches;
+use serde_json::{Map, Value};
+use std::collections::HashMap;
+use std::ffi::OsString;
+use std::fs;
+use std::path::Path;
+
+fn flatten_into(
+    prefix: &str,
+    v: &Value,
+    sep: &str,
+    out: &mut Map<String, Value>,
+    clashes: &mut Vec<String>,
+) {
+    match v {
+        Value::Object(map) if !map.is_empty() => {
+            for (k, child) in map {
+                let key = if prefix.is_empty() {
+                    k.clone()
+                } else {
+                    format!("{}{}{}", prefix, sep, k)
+                };
+                flatten_into(&key, child, sep, out, clashes);
+            }
+        }
+        _ => {
+            if out.insert(prefix.to_string(), v.clone()).is_some() {
+                clashes.push(prefix.to_string());
+            }
+        }
+    }
+}
+
+/// Flattens `map`'s nested objects into `sep`-joined keys, keeping order.
+/// Empty objects and arrays stay leaves. Fails with the keys that two
+/// different paths flatten to (`"a.b"` next to `{"a": {"b": …}}`).
+pub fn flatten(map: &Map<String, Value>, sep: &str) -> Result<Map<String, Value>, Vec<String>> {
+    let mut out = Map::new();
+    let mut clashes = Vec::new();
+    for (k, v) in map {
+        flatten_into(k, v, sep, &mut out, &mut clashes);
+    }
+    if clashes.is_empty() {
+        Ok(out)
+    } else {
+        Err(clashes)
+    }
+}
+
+fn insert_path(out: &mut Map<String, Value>, key: &str, sep: &str, v: Value) -> Result<(), ()> {
+    let mut parts: Vec<&str> = key.split(sep).collect();
+    let last = parts.pop().unwrap_or_default();
+    let mut cur = out;
+    for part in parts {
+        let next = cur
+            .entry(part.to_string())
+            .or_insert_with(|| Value::Object(Map::new()));
+        match next {
+            Value::Object(map) => cur = map,
+            _ => return Err(()),
+        }
+    }
+    match cur.get(last) {
+        None => {
+            cur.insert(last.to_string(), v);
+            Ok(())
+        }
+        // An empty object leaf under keys that now live inside it.
+        Some(Value::Object(_)) if v.as_object().is_some_and(|m| m.is_empty()) => Ok(()),
+        Some(_) => Err(()),
+    }
+}
+
+/// Groups `sep`-joined keys of `map` into nested objects, keeping the order
+/// in which each group first appears. Fails with the keys that are both a
+/// value and a prefix of other keys (`"a"` next to `"a.b"`).
+pub fn unflatten(map: &Map<String, Value>, sep: &str) -> Result<Map<String, Value>, Vec<String>> {
+    let flat = flatten(map, sep)?;
+    let mut out = Map::new();
+    let mut conflicts = Vec::new();
+    for (k, v) in &flat {
+        if insert_path(&mut out, k, sep, v.clone()).is_err() {
+            conflicts.push(k.clone());
+        }
+    }
+    // The key inserted first may not report itself; name both sides.
+    for k in conflicts.clone() {
+        for other in flat.keys() {
+            let prefix = format!("{}{}", other, sep);
+            if k.starts_with(&prefix) && !conflicts.contains(other) {
+                conflicts.push(other.clone());
+            }
+        }
+    }
+    if conflicts.is_empty() {
+        Ok(out)
+    } else {
+        Err(conflicts)
+    }
+}
+
+type Convert = fn(&Map<String, Value>, &str) -> Result<Map<String, Value>, Vec<String>>;
+
+/// Rewrites every locale file in `dir` with `convert`. Nothing is written
+/// unless every file converts cleanly, so the locales never end up half
+/// migrated.
+fn convert_all(
+    dir: &Path,
+    sep: &str,
+    convert: Convert,
+    done: &str,
+    would: &str,
+    style: &FmtStyle,
+) -> i32 {
+    let mut failed = 0;
+    let mut pending = Vec::new();
+    for p in crate::target_files(dir, None) {
+        let map = match crate::read_json(&p) {
+            Ok(Value::Object(map)) => map,
+            Ok(_) => {
+                crate::report::error(&p, "root is not an object");
+                return 2;
+            }
+            Err(e) => {
+                crate::report::error(&p, e);
+                return 2;
+            }
+        };
+        match convert(&map, sep) {
+            Ok(converted) if converted == map => crate::report::ok(&p),
+            Ok(converted) => pending.push((p, converted)),
+            Err(conflicts) => {
+                crate::report::header(&p, "CONFLICTS", conflicts.len());
+                for k in conflicts {
+                    crate::report::detail(format!("  {}", k));
+                }
+                failed += 1;
+            }
+        }
+    }
+    if failed > 0 {
+        eprintln!(
+            "{} file(s) have conflicting keys; nothing was written",
+            failed
+        );
+        return 1;
+    }
+    for (p, converted) in pending {
+        let written = crate::write::write_file(
+            &p,
+            &crate::json::to_string(&Value::Object(converted), style),
+        );
+        match written {
+            Ok(()) => crate::report::info(format!(
+                "{} {}",
+                crate::write::verb(done, would),
+                p.display()
+            )),
+            Err(e) => {
+                crate::report::error(&p, e);
+                return 2;
+            }
+        }
+    }
+    0
+}
+
+/// `flatten`: rewrites every locale file in `dir` with flat keys.
+pub fn run_flatten(dir: &Path, m: &ArgMatches, style: &FmtStyle) -> i32 {
+    let sep = m.get_one::<String>("separator").unwrap();
+    convert_all(dir, sep, flatten, "Flattened", "flatten", style)
+}
+
+/// `unflatten`: rewrites every locale file in `dir` with nested objects.
+pub fn run_unflatten(dir: &Path, m: &ArgMatches, style: &FmtStyle) -> i32 {
+    let sep = m.get_one::<String>("separator").unwrap();
+    convert_all(dir, sep, unflatten, "Unflattened", "unflatten", style)
+}
+
+fn migrate_keys(
+    old_prefix: &str,
+    new_prefix: &str,
+    map: &Map<String, Value>,
+    from: &str,
+    to: &str,
+    renamed: &mut Vec<(String, String)>,
+    conflicts: &mut Vec<String>,
+) -> Map<String, Value> {
+    let mut out = Map::new();
+    let mut origin: HashMap<String, &str> = HashMap::new();
+    for (k, v) in map {
+        let new_key = k.replace(from, to);
+        let old_path = join(old_prefix, k);
+        let new_path = join(new_prefix, &new_key);
+        let v = match v {
+            Value::Object(child) => Value::Object(migrate_keys(
+                &old_path, &new_path, child, from, to, renamed, conflicts,
+            )),
+            _ => {
+                renamed.push((old_path.clone(), new_path.clone()));
+                v.clone()
+            }
+        };
+        if let Some(other) = origin.insert(new_key.clone(), k) {
+            conflicts.push(format!(
+                "{} (both {} and {})",
+                new_path,
+                join(old_prefix, other),
+                old_path
+            ));
+        }
+        out.insert(new_key, v);
+    }
+    out
+}
+
+fn join(prefix: &str, key: &str) -> String {
+    if prefix.is_empty() {
+        key.to_string()
+    } else {
+        format!("{}.{}", prefix, key)
+    }
+}
+
+/// `migrate-separator`: replaces `--from` with `--to` in every key of every
+/// locale file and, with `--src`, in source string literals naming those
+/// keys. Nothing is written if any two keys collide.
+pub fn run_migrate(dir: &Path, m: &ArgMatches, style: &FmtStyle) -> i32 {
+    let from = m.get_one::<String>("from").unwrap();
+    let to = m.get_one::<String>("to").unwrap();
+    let mut failed = 0;
+    let mut pending = Vec::new();
+    let mut renamed = Vec::new();
+    for p in crate::target_files(dir, None) {
+        let map = match crate::read_json(&p) {
+            Ok(Value::Object(map)) => map,
+            Ok(_) => {
+                crate::report::error(&p, "root is not an object");
+                return 2;
+            }
+            Err(e) => {
+                crate::report::error(&p, e);
+                return 2;
+            }
+        };
+        let mut conflicts = Vec::new();
+        let migrated = migrate_keys("", "", &map, from, to, &mut renamed, &mut conflicts);
+        if !conflicts.is_empty() {
+            crate::report::header(&p, "CONFLICTS", conflicts.len());
+            for c in conflicts {
+                crate::report::detail(format!("  {}", c));
+            }
+            failed += 1;
+        } else if migrated == map {
+            crate::report::ok(&p);
+        } else {
+            pending.push((p, crate::json::to_string(&Value::Object(migrated), style)));
+        }
+    }
+    if failed > 0 {
+        eprintln!(
+            "{} file(s) have conflicting keys; nothing was written",
+            failed
+        );
+        return 1;
+    }
+    renamed.retain(|(old, new)| old != new);
+    renamed.sort();
+    renamed.dedup();
+    if let Some(src) = m.get_one::<OsString>("src") {
+        for f in crate::source::files(Path::new(src)) {
+            let Ok(text) = fs::read_to_string(&f) else {
+                continue;
+            };
+            let mut updated = text.clone();
+            for (old, new) in &renamed {
+                updated = crate::source::replace_literal(&updated, old, new);
+            }
+            if updated != text {
+                pending.push((f, updated));
+            }
+        }
+    }
+    for (p, text) in pending {
+        match crate::write::write_file(&p, &text) {
+            Ok(()) => crate::report::info(format!(
+                "{} {}",
+                crate::write::verb("Migrated", "migrate"),
+                p.display()
+            )),
+            Err(e) => {
+                crate::report::error(&p, e);
+                return 2;
+            }
+        }
+    }
+    0
+}
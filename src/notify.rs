@@ -0,0 +1,364 @@
+//! `--webhook` and `--telegram`: posting the outcome of a run, either as
+//! JSON for a bot that opens an issue when a locale falls behind, or as a
+//! compact per-locale status message for a Slack, Discord or Telegram chat.
+
+use crate::changelog;
+use crate::config::{NotifyConfig, TelegramConfig};
+use crate::locales::LocaleSet;
+use crate::rules::{Finding, Severity};
+use crate::write;
+use clap::ArgMatches;
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Mutex, OnceLock};
+
+/// What to post to the webhook: every finding, only the counts, or a chat
+/// message.
+#[derive(Clone, Copy, PartialEq)]
+pub enum Payload {
+    Report,
+    Summary,
+    Slack,
+    Discord,
+}
+
+struct Notifier {
+    webhook: Option<(String, Payload)>,
+    telegram: bool,
+    dir: PathBuf,
+    base: PathBuf,
+    settings: NotifyConfig,
+}
+
+static NOTIFIER: OnceLock<Option<Notifier>> = OnceLock::new();
+/// Findings of the run, kept from [`record`] until [`send`].
+static FINDINGS: Mutex<Vec<Value>> = Mutex::new(Vec::new());
+/// Whether to skip notifying about results identical to the last ones.
+static ONLY_ON_CHANGE: AtomicBool = AtomicBool::new(false);
+/// The results last notified about, with [`only_on_change`].
+static LAST: Mutex<Option<String>> = Mutex::new(None);
+
+pub fn init(m: &ArgMatches, dir: &Path, base: &Path, settings: &NotifyConfig) {
+    let webhook = m.get_one::<String>("webhook").map(|url| {
+        let payload = match m.get_one::<String>("webhook_payload").map(String::as_str) {
+            Some("summary") => Payload::Summary,
+            Some("slack") => Payload::Slack,
+            Some("discord") => Payload::Discord,
+            _ => Payload::Report,
+        };
+        (url.clone(), payload)
+    });
+    let telegram = m.get_flag("telegram");
+    let notifier = (webhook.is_some() || telegram).then(|| Notifier {
+        webhook,
+        telegram,
+        dir: dir.to_path_buf(),
+        base: base.to_path_buf(),
+        settings: settings.clone(),
+    });
+    let _ = NOTIFIER.set(notifier);
+}
+
+/// From now on, notify only when a run's results differ from the previous
+/// run's, for repeated runs in one process.
+pub fn only_on_change() {
+    ONLY_ON_CHANGE.store(true, Ordering::Relaxed);
+}
+
+fn notifier() -> Option<&'static Notifier> {
+    NOTIFIER.get().and_then(Option::as_ref)
+}
+
+/// Keeps the findings of the run for the webhook, if there is one.
+pub fn record(findings: &[Finding]) {
+    if notifier().is_none() {
+        return;
+    }
+    FINDINGS.lock().unwrap().extend(findings.iter().map(|f| {
+        json!({
+            "file": f.file.to_string_lossy(),
+            "locale": f.locale,
+            "rule": f.rule,
+            "key": f.key,
+            "severity": f.severity.as_str(),
+            "message": f.message,
+        })
+    }));
+}
+
+fn post(url: &str, body: &Value) -> Result<(), String> {
+    ureq::post(url)
+        .set("Content-Type", "application/json")
+        .send_string(&body.to_string())
+        .map(drop)
+        .map_err(|e| e.to_string())
+}
+
+/// Posts the run's summary, with the `report` payload also its findings, to
+/// the webhook, and the chat messages to Slack, Discord or Telegram. A
+/// failed post is reported but does not fail the run.
+pub fn send(checked: usize, problems: &str, unreadable: usize) {
+    let Some(n) = notifier() else {
+        return;
+    };
+    let findings = std::mem::take(&mut *FINDINGS.lock().unwrap());
+    if ONLY_ON_CHANGE.load(Ordering::Relaxed) {
+        let results = format!("{}\n{}", problems, Value::Array(findings.clone()));
+        let mut last = LAST.lock().unwrap();
+        if last.as_deref() == Some(results.as_str()) {
+            return;
+        }
+        *last = Some(results);
+    }
+    if let Some((url, payload @ (Payload::Report | Payload::Summary))) = &n.webhook {
+        let errors = findings
+            .iter()
+            .filter(|f| f["severity"] == Severity::Error.as_str())
+            .count();
+        let mut body = json!({
+            "summary": {
+                "checked": checked,
+                "problems": problems,
+                "unreadable": unreadable,
+                "errors": errors,
+                "warnings": findings.len() - errors,
+            },
+        });
+        if *payload == Payload::Report {
+            body["findings"] = Value::Array(findings);
+        }
+        if let Err(e) = post(url, &body) {
+            eprintln!("Failed to notify {}: {}", url, e);
+        }
+    }
+    let chat_hook = n
+        .webhook
+        .as_ref()
+        .filter(|(_, p)| matches!(p, Payload::Slack | Payload::Discord));
+    if chat_hook.is_some() || n.telegram {
+        let headline = format!("Checked {} files: {}", checked, problems);
+        if let Err(e) = chat(n, chat_hook, &headline) {
+            eprintln!("Failed to notify: {}", e);
+        }
+    }
+}
+
+/// Completion of one locale as last posted.
+#[derive(Serialize, Deserialize)]
+struct LocaleState {
+    coverage: f64,
+    missing: BTreeSet<String>,
+}
+
+type State = BTreeMap<String, LocaleState>;
+
+/// Every locale's completion now, read afresh from the locale files.
+fn status(dir: &Path, base: &Path) -> Result<State, String> {
+    let (base_strings, locales) = changelog::locales(dir, base, &mut LocaleSet::default())?;
+    Ok(locales
+        .into_iter()
+        .map(|(locale, own)| {
+            let missing: BTreeSet<String> = base_strings
+                .keys()
+                .filter(|k| !changelog::translated(&own, k))
+                .cloned()
+                .collect();
+            let coverage = if base_strings.is_empty() {
+                100.0
+            } else {
+                (base_strings.len() - missing.len()) as f64 * 100.0 / base_strings.len() as f64
+            };
+            (locale, LocaleState { coverage, missing })
+        })
+        .collect())
+}
+
+/// Keys missing now that were not missing when last posted.
+fn new_missing<'a>(now: &'a LocaleState, before: Option<&LocaleState>) -> Vec<&'a String> {
+    now.missing
+        .iter()
+        .filter(|k| before.is_none_or(|b| !b.missing.contains(*k)))
+        .collect()
+}
+
+/// Whether the change since the last post reaches one of the thresholds.
+fn worth_posting(settings: &NotifyConfig, now: &State, before: &State) -> bool {
+    if settings.min_change.is_none() && settings.min_new_missing.is_none() {
+        return true;
+    }
+    now.iter().any(|(locale, s)| {
+        let prev = before.get(locale);
+        let change = (s.coverage - prev.map_or(0.0, |p| p.coverage)).abs();
+        settings.min_change.is_some_and(|min| change >= min)
+            || settings
+                .min_new_missing
+                .is_some_and(|min| new_missing(s, prev).len() >= min)
+    })
+}
+
+/// How a chat marks up bold text and code.
+#[derive(Clone, Copy, PartialEq)]
+enum Markup {
+    Slack,
+    Discord,
+    /// Telegram's HTML parse mode.
+    Html,
+}
+
+impl Markup {
+    fn bold(self, s: &str) -> String {
+        match self {
+            Markup::Slack => format!("*{}*", escape(s)),
+            Markup::Discord => format!("**{}**", s),
+            Markup::Html => format!("<b>{}</b>", escape(s)),
+        }
+    }
+
+    fn code(self, s: &str) -> String {
+        match self {
+            Markup::Html => format!("<code>{}</code>", escape(s)),
+            Markup::Slack => format!("`{}`", escape(s)),
+            Markup::Discord => format!("`{}`", s),
+        }
+    }
+
+    fn text(self, s: &str) -> String {
+        match self {
+            Markup::Slack | Markup::Html => escape(s),
+            Markup::Discord => s.to_string(),
+        }
+    }
+}
+
+/// `s` with `&`, `<` and `>` escaped, which Telegram's HTML and Slack both
+/// read as markup: unescaped, a key like `<!channel>` would ping a Slack
+/// channel.
+fn escape(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+/// `• de: 96.5%, 2 new missing: `a`, `b``.
+fn locale_line(markup: Markup, locale: &str, now: &LocaleState, before: &State) -> String {
+    const SHOWN: usize = 5;
+    let mut out = format!("• {}: {:.1}%", markup.text(locale), now.coverage);
+    let added = new_missing(now, before.get(locale));
+    if added.is_empty() {
+        return out;
+    }
+    let keys: Vec<String> = added.iter().take(SHOWN).map(|k| markup.code(k)).collect();
+    out.push_str(&format!(
+        ", {} new missing: {}",
+        added.len(),
+        keys.join(", ")
+    ));
+    if added.len() > SHOWN {
+        out.push_str(&format!(" and {} more", added.len() - SHOWN));
+    }
+    out
+}
+
+/// The status message: the run's headline, then one line per locale.
+fn message(markup: Markup, headline: &str, now: &State, before: &State) -> String {
+    let mut out = format!("{}: {}", markup.bold("cvr-i18n"), markup.text(headline));
+    for (locale, s) in now {
+        out.push('\n');
+        out.push_str(&locale_line(markup, locale, s, before));
+    }
+    out
+}
+
+/// Sends `text` to the Telegram chat `chat`.
+fn telegram(settings: &TelegramConfig, token: &str, chat: &str, text: &str) -> Result<(), String> {
+    let url = format!(
+        "{}/bot{}/sendMessage",
+        settings.api_url.trim_end_matches('/'),
+        token
+    );
+    let body = json!({ "chat_id": chat, "text": text, "parse_mode": "HTML" });
+    // The URL holds the token, so errors name the chat instead.
+    post(&url, &body).map_err(|_| format!("Telegram chat {}: request failed", chat))
+}
+
+/// Posts the per-locale status to the Slack or Discord webhook and the
+/// Telegram chats when it moved enough since the last post, then remembers
+/// it. Telegram locale chats are only pinged about new missing keys.
+fn chat(n: &Notifier, hook: Option<&(String, Payload)>, headline: &str) -> Result<(), String> {
+    let now = status(&n.dir, &n.base)?;
+    let state_path = &n.settings.state;
+    let before: State = match fs::read_to_string(state_path) {
+        Ok(text) => serde_json::from_str(&text)
+            .map_err(|e| format!("parse {}: {}", state_path.display(), e))?,
+        Err(_) => State::new(),
+    };
+    if !worth_posting(&n.settings, &now, &before) {
+        return Ok(());
+    }
+    let mut errors = Vec::new();
+    if let Some((url, payload)) = hook {
+        let body = if *payload == Payload::Slack {
+            json!({ "text": message(Markup::Slack, headline, &now, &before) })
+        } else {
+            // Discord rejects messages over 2000 characters.
+            let text: String = message(Markup::Discord, headline, &now, &before)
+                .chars()
+                .take(2000)
+                .collect();
+            json!({ "content": text })
+        };
+        if let Err(e) = post(url, &body) {
+            errors.push(format!("{}: {}", url, e));
+        }
+    }
+    if n.telegram {
+        let tg = &n.settings.telegram;
+        match std::env::var(&tg.token_env) {
+            Ok(token) => {
+                if let Some(chat) = &tg.chat {
+                    let text = message(Markup::Html, headline, &now, &before);
+                    errors.extend(telegram(tg, &token, chat, &text).err());
+                }
+                for (locale, chat) in &tg.locales {
+                    let Some(s) = now.get(locale) else {
+                        continue;
+                    };
+                    if new_missing(s, before.get(locale)).is_empty() {
+                        continue;
+                    }
+                    let text = format!(
+                        "{}\n{}",
+                        Markup::Html.bold("cvr-i18n"),
+                        locale_line(Markup::Html, locale, s, &before)
+                    );
+                    errors.extend(telegram(tg, &token, chat, &text).err());
+                }
+            }
+            Err(_) => errors.push(format!("{} is not set", tg.token_env)),
+        }
+    }
+    if !errors.is_empty() {
+        return Err(errors.join("; "));
+    }
+    let text = serde_json::to_string_pretty(&now).map_err(|e| e.to_string())?;
+    write::write_file(state_path, &text)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn slack_messages_escape_control_sequences() {
+        assert_eq!(
+            Markup::Slack.text("<!channel> & co"),
+            "&lt;!channel&gt; &amp; co"
+        );
+        assert_eq!(Markup::Slack.code("<@U1>"), "`&lt;@U1&gt;`");
+        assert_eq!(Markup::Slack.bold("<b>"), "*&lt;b&gt;*");
+    }
+}
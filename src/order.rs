@@ -0,0 +1,121 @@
+//! Key order files for `--sort --order`: one key per line, `prefix*` for
+//! every key starting with `prefix`, `#` comments and blank lines ignored.
+
+use serde_json::{Map, Value};
+use std::fs;
+use std::path::Path;
+
+pub fn load(path: &Path) -> Result<Vec<String>, String> {
+    let text = fs::read_to_string(path).map_err(|e| format!("read {}: {}", path.display(), e))?;
+    Ok(parse(&text))
+}
+
+/// The patterns of an order file's text.
+pub fn parse(text: &str) -> Vec<String> {
+    text.lines()
+        .map(str::trim)
+        .filter(|l| !l.is_empty() && !l.starts_with('#'))
+        .map(String::from)
+        .collect()
+}
+
+fn matches(pattern: &str, key: &str) -> bool {
+    match pattern.strip_suffix('*') {
+        Some(prefix) => key.starts_with(prefix),
+        None => pattern == key,
+    }
+}
+
+/// Reorders `keys` so that keys matched by `patterns` come first, in
+/// pattern order; keys a wildcard matches keep their relative order, as do
+/// the unmatched keys after them.
+pub fn apply(patterns: &[String], keys: &[String]) -> Vec<String> {
+    let mut out: Vec<String> = Vec::with_capacity(keys.len());
+    let mut taken = vec![false; keys.len()];
+    for pattern in patterns {
+        for (i, k) in keys.iter().enumerate() {
+            if !taken[i] && matches(pattern, k) {
+                taken[i] = true;
+                out.push(k.clone());
+            }
+        }
+    }
+    out.extend(
+        keys.iter()
+            .zip(&taken)
+            .filter(|(_, t)| !**t)
+            .map(|(k, _)| k.clone()),
+    );
+    out
+}
+
+/// Where `--sort` puts keys the base does not have.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Unknown {
+    /// Before the base keys, in their current order.
+    Top,
+    /// After the base keys, in their current order.
+    Bottom,
+    /// After the base keys, alphabetically.
+    Alpha,
+    /// Refuse to sort the file.
+    Error,
+}
+
+impl Unknown {
+    pub fn parse(s: &str) -> Self {
+        match s {
+            "top" => Unknown::Top,
+            "bottom" => Unknown::Bottom,
+            "error" => Unknown::Error,
+            _ => Unknown::Alpha,
+        }
+    }
+}
+
+/// Keys not in the base, which [`Unknown::Error`] refuses to sort.
+pub type Unknowns = Vec<String>;
+
+/// `map` reordered after `base_keys`, then after the `patterns`, with the
+/// keys that moved; with [`Unknown::Error`], the keys not in the base as the
+/// error instead.
+pub fn sort_object(
+    mut map: Map<String, Value>,
+    base_keys: &[String],
+    patterns: &[String],
+    unknown: Unknown,
+) -> Result<(Map<String, Value>, Vec<String>), Unknowns> {
+    let before: Vec<String> = map.keys().cloned().collect();
+    let known: Vec<String> = base_keys
+        .iter()
+        .filter(|k| map.contains_key(*k))
+        .cloned()
+        .collect();
+    let mut remaining: Vec<String> = before
+        .iter()
+        .filter(|k| !base_keys.contains(k))
+        .cloned()
+        .collect();
+    let keys = match unknown {
+        Unknown::Error if !remaining.is_empty() => return Err(remaining),
+        Unknown::Top => [remaining, known].concat(),
+        Unknown::Alpha => {
+            remaining.sort();
+            [known, remaining].concat()
+        }
+        _ => [known, remaining].concat(),
+    };
+    let mut out = Map::new();
+    for k in apply(patterns, &keys) {
+        if let Some(val) = map.remove(&k) {
+            out.insert(k, val);
+        }
+    }
+    let moved = out
+        .keys()
+        .zip(&before)
+        .filter(|(a, b)| a != b)
+        .map(|(a, _)| a.clone())
+        .collect();
+    Ok((out, moved))
+}
@@ -0,0 +1,582 @@
+//! `--emit-patch`: collects what a command would write and saves it as one
+//! patch file instead of touching the locales, so machine edits can be
+//! reviewed, attached to a pull request or applied selectively. Unified
+//! diffs apply with `git apply`; JSON patches (RFC 6902, one operation list
+//! per file, keyed by the file's path within the locales directory) with
+//! `apply`.
+
+use crate::config::FmtStyle;
+use crate::{json, report, write};
+use clap::ArgMatches;
+use serde_json::{Map, Value, json};
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+use std::sync::{Mutex, OnceLock};
+
+#[derive(Clone, Copy, PartialEq)]
+enum Format {
+    Unified,
+    Json,
+}
+
+/// The patch file, its format and the locales directory.
+static TARGET: OnceLock<(PathBuf, Format, PathBuf)> = OnceLock::new();
+/// Each file's contents before the run and what would be written, in the
+/// order first written. A file written twice keeps its original contents.
+static EDITS: Mutex<Vec<(PathBuf, Option<String>, String)>> = Mutex::new(Vec::new());
+
+/// Lines of unchanged context around each hunk.
+const CONTEXT: usize = 3;
+/// Beyond this many line pairs a changed block is replaced whole rather than
+/// diffed line by line.
+const DIFF_LIMIT: usize = 4_000_000;
+
+/// Returns whether `--emit-patch` was given.
+pub fn init(m: &ArgMatches, dir: &Path) -> bool {
+    let Some(file) = m.get_one::<String>("emit_patch") else {
+        return false;
+    };
+    let format = match m.get_one::<String>("patch_format").map(String::as_str) {
+        Some("json") => Format::Json,
+        _ => Format::Unified,
+    };
+    let _ = TARGET.set((PathBuf::from(file), format, dir.to_path_buf()));
+    true
+}
+
+/// The write preview: records `new` for `path` instead of showing it.
+pub fn record(path: &Path, new: &str) {
+    let mut edits = EDITS.lock().unwrap();
+    if let Some(edit) = edits.iter_mut().find(|(p, ..)| p == path) {
+        edit.2 = new.to_string();
+        return;
+    }
+    let old = fs::read_to_string(path).ok();
+    edits.push((path.to_path_buf(), old, new.to_string()));
+}
+
+/// Writes the collected patch, if `--emit-patch` was given.
+pub fn finish() {
+    let Some((target, format, dir)) = TARGET.get() else {
+        return;
+    };
+    let edits = EDITS.lock().unwrap();
+    let changed: Vec<_> = edits
+        .iter()
+        .filter(|(_, old, new)| old.as_deref() != Some(new.as_str()))
+        .collect();
+    let text = match format {
+        Format::Unified => changed
+            .iter()
+            .map(|(p, old, new)| unified(p, old.as_deref(), new))
+            .collect(),
+        Format::Json => {
+            let mut files = Map::new();
+            for (p, old, new) in &changed {
+                let Ok(name) = p.strip_prefix(dir) else {
+                    report::error(
+                        p,
+                        "not in the locales directory, left out of the JSON patch",
+                    );
+                    continue;
+                };
+                files.insert(display(name), Value::Array(json_patch(old.as_deref(), new)));
+            }
+            let mut text = serde_json::to_string_pretty(&Value::Object(files)).unwrap();
+            text.push('\n');
+            text
+        }
+    };
+    if let Err(e) = fs::write(target, text) {
+        report::error(target, e);
+        std::process::exit(2);
+    }
+    report::info(format!(
+        "Wrote patch for {} files to {}",
+        changed.len(),
+        target.display()
+    ));
+}
+
+/// `path` as written in patch headers, without a leading `./`.
+fn display(path: &Path) -> String {
+    let s = path.to_string_lossy().replace('\\', "/");
+    s.strip_prefix("./").unwrap_or(&s).to_string()
+}
+
+enum Line {
+    Same(usize),
+    Del(usize),
+    Add(usize),
+}
+
+/// A line-level edit script turning `a` into `b`: common prefix and suffix
+/// are kept, and the rest is diffed by longest common subsequence.
+fn edit_script(a: &[&str], b: &[&str]) -> Vec<Line> {
+    let prefix = a.iter().zip(b).take_while(|(x, y)| x == y).count();
+    let suffix = a[prefix..]
+        .iter()
+        .rev()
+        .zip(b[prefix..].iter().rev())
+        .take_while(|(x, y)| x == y)
+        .count();
+    let (am, bm) = (&a[prefix..a.len() - suffix], &b[prefix..b.len() - suffix]);
+    let mut script: Vec<Line> = (0..prefix).map(Line::Same).collect();
+    let (n, m) = (am.len(), bm.len());
+    if n.saturating_mul(m) > DIFF_LIMIT {
+        script.extend((0..n).map(|i| Line::Del(prefix + i)));
+        script.extend((0..m).map(|j| Line::Add(prefix + j)));
+    } else {
+        // lcs[i * (m + 1) + j]: longest common subsequence of am[i..] and bm[j..].
+        let mut lcs = vec![0u32; (n + 1) * (m + 1)];
+        for i in (0..n).rev() {
+            for j in (0..m).rev() {
+                lcs[i * (m + 1) + j] = if am[i] == bm[j] {
+                    lcs[(i + 1) * (m + 1) + j + 1] + 1
+                } else {
+                    lcs[(i + 1) * (m + 1) + j].max(lcs[i * (m + 1) + j + 1])
+                };
+            }
+        }
+        let (mut i, mut j) = (0, 0);
+        while i < n || j < m {
+            if i < n && j < m && am[i] == bm[j] {
+                script.push(Line::Same(prefix + i));
+                i += 1;
+                j += 1;
+            } else if j == m || (i < n && lcs[(i + 1) * (m + 1) + j] >= lcs[i * (m + 1) + j + 1]) {
+                script.push(Line::Del(prefix + i));
+                i += 1;
+            } else {
+                script.push(Line::Add(prefix + j));
+                j += 1;
+            }
+        }
+    }
+    script.extend((a.len() - suffix..a.len()).map(Line::Same));
+    script
+}
+
+/// One file's unified diff, `/dev/null` standing in for a new file.
+fn unified(path: &Path, old: Option<&str>, new: &str) -> String {
+    let name = display(path);
+    let a: Vec<&str> = old.unwrap_or("").split_inclusive('\n').collect();
+    let b: Vec<&str> = new.split_inclusive('\n').collect();
+    let script = edit_script(&a, &b);
+    let mut out = match old {
+        Some(_) => format!("--- a/{}\n+++ b/{}\n", name, name),
+        None => format!("--- /dev/null\n+++ b/{}\n", name),
+    };
+
+    // Position in `a` and `b` before each script entry.
+    let mut at = Vec::with_capacity(script.len() + 1);
+    let (mut i, mut j) = (0, 0);
+    for line in &script {
+        at.push((i, j));
+        match line {
+            Line::Same(_) => (i, j) = (i + 1, j + 1),
+            Line::Del(_) => i += 1,
+            Line::Add(_) => j += 1,
+        }
+    }
+    at.push((i, j));
+
+    let changes: Vec<usize> = (0..script.len())
+        .filter(|&k| !matches!(script[k], Line::Same(_)))
+        .collect();
+    let mut k = 0;
+    while k < changes.len() {
+        let start = changes[k].saturating_sub(CONTEXT);
+        let mut last = changes[k];
+        while k + 1 < changes.len() && changes[k + 1] - last <= 2 * CONTEXT + 1 {
+            k += 1;
+            last = changes[k];
+        }
+        k += 1;
+        let end = (last + CONTEXT + 1).min(script.len());
+        let ((a0, b0), (a1, b1)) = (at[start], at[end]);
+        let range = |from: usize, to: usize| {
+            let len = to - from;
+            format!("{},{}", if len == 0 { from } else { from + 1 }, len)
+        };
+        out.push_str(&format!("@@ -{} +{} @@\n", range(a0, a1), range(b0, b1)));
+        for line in &script[start..end] {
+            let (mark, text) = match *line {
+                Line::Same(i) => (' ', a[i]),
+                Line::Del(i) => ('-', a[i]),
+                Line::Add(j) => ('+', b[j]),
+            };
+            out.push(mark);
+            out.push_str(text);
+            if !text.ends_with('\n') {
+                out.push_str("\n\\ No newline at end of file\n");
+            }
+        }
+    }
+    out
+}
+
+/// `key` escaped as a JSON pointer segment.
+fn pointer(parent: &str, key: &str) -> String {
+    format!("{}/{}", parent, key.replace('~', "~0").replace('/', "~1"))
+}
+
+/// RFC 6902 operations turning `old` into `new`. Every replaced or removed
+/// value is `test`ed first so a patch made against other contents refuses
+/// to apply. Key order is not expressible and is left out.
+fn json_patch(old: Option<&str>, new: &str) -> Vec<Value> {
+    let parse = |s: &str| serde_json::from_str::<Value>(s.trim_start_matches('\u{feff}')).ok();
+    let new = parse(new).unwrap_or(Value::Null);
+    let mut ops = Vec::new();
+    match old.map(parse) {
+        None => ops.push(json!({"op": "add", "path": "", "value": new})),
+        Some(None) => ops.push(json!({"op": "replace", "path": "", "value": new})),
+        Some(Some(old)) => diff_values(&old, &new, "", &mut ops),
+    }
+    ops
+}
+
+fn diff_values(old: &Value, new: &Value, path: &str, ops: &mut Vec<Value>) {
+    match (old, new) {
+        (Value::Object(a), Value::Object(b)) => {
+            for (k, va) in a {
+                let p = pointer(path, k);
+                match b.get(k) {
+                    Some(vb) => diff_values(va, vb, &p, ops),
+                    None => {
+                        ops.push(json!({"op": "test", "path": p, "value": va}));
+                        ops.push(json!({"op": "remove", "path": p}));
+                    }
+                }
+            }
+            for (k, vb) in b.iter().filter(|(k, _)| !a.contains_key(*k)) {
+                ops.push(json!({"op": "add", "path": pointer(path, k), "value": vb}));
+            }
+        }
+        _ if old == new => {}
+        _ => {
+            ops.push(json!({"op": "test", "path": path, "value": old}));
+            ops.push(json!({"op": "replace", "path": path, "value": new}));
+        }
+    }
+}
+
+/// A JSON pointer's segments, unescaped. Fails unless the pointer is empty
+/// or starts with `/`.
+fn segments(path: &str) -> Result<Vec<String>, String> {
+    if path.is_empty() {
+        return Ok(Vec::new());
+    }
+    let rest = path
+        .strip_prefix('/')
+        .ok_or_else(|| "invalid pointer (must start with /)".to_string())?;
+    Ok(rest
+        .split('/')
+        .map(|s| s.replace("~1", "/").replace("~0", "~"))
+        .collect())
+}
+
+fn lookup<'a>(v: &'a Value, segs: &[String]) -> Option<&'a Value> {
+    segs.iter().try_fold(v, |v, s| match v {
+        Value::Object(map) => map.get(s),
+        Value::Array(items) => s.parse::<usize>().ok().and_then(|i| items.get(i)),
+        _ => None,
+    })
+}
+
+fn lookup_mut<'a>(v: &'a mut Value, segs: &[String]) -> Option<&'a mut Value> {
+    segs.iter().try_fold(v, |v, s| match v {
+        Value::Object(map) => map.get_mut(s),
+        Value::Array(items) => s.parse::<usize>().ok().and_then(|i| items.get_mut(i)),
+        _ => None,
+    })
+}
+
+/// Sets, inserts (`add`) or removes (`value` of `None`) the value at `segs`.
+fn put(doc: &mut Value, segs: &[String], value: Option<Value>, add: bool) -> Result<(), String> {
+    let Some((last, parent)) = segs.split_last() else {
+        *doc = value.unwrap_or(Value::Null);
+        return Ok(());
+    };
+    match lookup_mut(doc, parent) {
+        Some(Value::Object(map)) => {
+            match value {
+                Some(v) => {
+                    map.insert(last.clone(), v);
+                }
+                None => {
+                    map.shift_remove(last);
+                }
+            }
+            Ok(())
+        }
+        Some(Value::Array(items)) => {
+            let at = match last.as_str() {
+                "-" if add => items.len(),
+                s => s
+                    .parse::<usize>()
+                    .ok()
+                    .filter(|&i| i < items.len() || (add && i == items.len()))
+                    .ok_or_else(|| format!("no array index {}", s))?,
+            };
+            match value {
+                Some(v) if add => items.insert(at, v),
+                Some(v) => items[at] = v,
+                None => {
+                    items.remove(at);
+                }
+            }
+            Ok(())
+        }
+        _ => Err("parent no longer exists".to_string()),
+    }
+}
+
+/// Applies one file's operations to `doc` (`None` for a file that does not
+/// exist yet), checking each key three ways: a key still holding the value
+/// the patch was made against is changed, one already holding the patched
+/// value is left alone, and anything else is a conflict. Returns how many
+/// keys changed, or the conflicts.
+fn apply_ops(doc: &mut Option<Value>, ops: &[Value]) -> Result<usize, Vec<String>> {
+    let (mut applied, mut conflicts) = (0, Vec::new());
+    let mut expected: Option<(&str, &Value)> = None;
+    for op in ops {
+        let path = op.get("path").and_then(Value::as_str).unwrap_or("");
+        let kind = op.get("op").and_then(Value::as_str).unwrap_or("");
+        let value = op.get("value");
+        if kind == "test" {
+            expected = value.map(|v| (path, v));
+            continue;
+        }
+        let old = expected.take().filter(|(p, _)| *p == path).map(|(_, v)| v);
+        let shown = if path.is_empty() { "/" } else { path };
+        let segs = match segments(path) {
+            Ok(segs) => segs,
+            Err(e) => {
+                conflicts.push(format!("{}: {}", shown, e));
+                continue;
+            }
+        };
+        let current = doc.as_ref().and_then(|d| lookup(d, &segs));
+        let target = match kind {
+            "add" | "replace" => match value {
+                Some(v) => Some(v.clone()),
+                None => {
+                    conflicts.push(format!("{}: {} without a value", shown, kind));
+                    continue;
+                }
+            },
+            "remove" => None,
+            other => {
+                conflicts.push(format!("{}: unsupported operation {:?}", shown, other));
+                continue;
+            }
+        };
+        if current == target.as_ref() {
+            continue;
+        }
+        let agrees = match kind {
+            "add" => current.is_none(),
+            _ => current.is_some() && old.is_none_or(|o| current == Some(o)),
+        };
+        if !agrees {
+            conflicts.push(match current {
+                Some(c) => format!("{}: now {}", shown, c),
+                None => format!("{}: no longer exists", shown),
+            });
+            continue;
+        }
+        let result = match doc {
+            Some(d) => put(d, &segs, target, kind == "add"),
+            None if segs.is_empty() => {
+                *doc = target;
+                Ok(())
+            }
+            None => Err("file does not exist".to_string()),
+        };
+        match result {
+            Ok(()) => applied += 1,
+            Err(e) => conflicts.push(format!("{}: {}", shown, e)),
+        }
+    }
+    if conflicts.is_empty() {
+        Ok(applied)
+    } else {
+        Err(conflicts)
+    }
+}
+
+/// The file a JSON patch names: `name` within `dir`. Fails for names that
+/// could point elsewhere, absolute or with `.` or `..` components.
+fn resolve(dir: &Path, name: &str) -> Result<PathBuf, String> {
+    let rel = Path::new(name);
+    let plain = rel.components().next().is_some()
+        && rel.components().all(|c| matches!(c, Component::Normal(_)));
+    if plain {
+        Ok(dir.join(rel))
+    } else {
+        Err(format!("{:?} is not a file in the locales directory", name))
+    }
+}
+
+/// `apply`: writes a JSON patch from `--emit-patch --patch-format json` to
+/// the files it names. A file whose keys have diverged from what the patch
+/// was made against is listed and left untouched.
+pub fn run_apply(dir: &Path, m: &ArgMatches, style: &FmtStyle) -> i32 {
+    let patch = Path::new(m.get_one::<String>("patch").unwrap());
+    let files = match fs::read_to_string(patch) {
+        Ok(text) => match serde_json::from_str::<Value>(&text) {
+            Ok(Value::Object(files)) if files.values().all(Value::is_array) => files,
+            _ => {
+                report::error(
+                    patch,
+                    "not a JSON patch (apply unified diffs with git apply)",
+                );
+                return 2;
+            }
+        },
+        Err(e) => {
+            report::error(patch, e);
+            return 2;
+        }
+    };
+    let (mut changed, mut diverged, mut failed) = (0, 0, 0);
+    for (name, ops) in &files {
+        let path = match resolve(dir, name) {
+            Ok(path) => path,
+            Err(e) => {
+                report::error(patch, e);
+                failed += 1;
+                continue;
+            }
+        };
+        let p = path.as_path();
+        let mut doc = match fs::read_to_string(p) {
+            Ok(text) => match serde_json::from_str::<Value>(text.trim_start_matches('\u{feff}')) {
+                Ok(v) => Some(v),
+                Err(e) => {
+                    report::error(p, e);
+                    failed += 1;
+                    continue;
+                }
+            },
+            Err(_) if !p.exists() => None,
+            Err(e) => {
+                report::error(p, e);
+                failed += 1;
+                continue;
+            }
+        };
+        match apply_ops(&mut doc, ops.as_array().unwrap()) {
+            Err(conflicts) => {
+                diverged += 1;
+                report::header(p, "DIVERGED", conflicts.len());
+                for c in &conflicts {
+                    report::detail(format!("  {}", c));
+                }
+            }
+            Ok(0) => report::detail(format!("{}: already applied", p.display())),
+            Ok(n) => {
+                let doc = doc.unwrap_or(Value::Null);
+                if let Err(e) = write::write_file(p, &json::to_string(&doc, style)) {
+                    report::error(p, e);
+                    failed += 1;
+                    continue;
+                }
+                changed += 1;
+                report::info(format!(
+                    "{} {} change(s) to {}",
+                    write::verb("Applied", "apply"),
+                    n,
+                    p.display()
+                ));
+            }
+        }
+    }
+    report::info(format!(
+        "{} of {} files changed, {} diverged",
+        changed,
+        files.len(),
+        diverged
+    ));
+    if failed > 0 {
+        2
+    } else if diverged > 0 {
+        1
+    } else {
+        0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ops(old: &str, new: &str) -> Vec<Value> {
+        json_patch(Some(old), new)
+    }
+
+    #[test]
+    fn apply_ops_applies_cleanly() {
+        let patch = ops(r#"{"a":"1","b":"2"}"#, r#"{"a":"one","c":"3"}"#);
+        let mut doc = Some(json!({"a": "1", "b": "2"}));
+        assert_eq!(apply_ops(&mut doc, &patch), Ok(3));
+        assert_eq!(doc, Some(json!({"a": "one", "c": "3"})));
+    }
+
+    #[test]
+    fn apply_ops_skips_what_is_already_applied() {
+        let patch = ops(r#"{"a":"1","b":"2"}"#, r#"{"a":"one","c":"3"}"#);
+        let mut doc = Some(json!({"a": "one", "c": "3"}));
+        assert_eq!(apply_ops(&mut doc, &patch), Ok(0));
+        assert_eq!(doc, Some(json!({"a": "one", "c": "3"})));
+    }
+
+    #[test]
+    fn apply_ops_reports_conflicts() {
+        let patch = ops(r#"{"a":"1","b":"2"}"#, r#"{"a":"one"}"#);
+        let mut doc = Some(json!({"a": "uno", "b": "2"}));
+        let conflicts = apply_ops(&mut doc, &patch).unwrap_err();
+        assert_eq!(conflicts, vec![r#"/a: now "uno""#.to_string()]);
+    }
+
+    #[test]
+    fn apply_ops_rejects_pointers_without_a_slash() {
+        for path in ["a", "中文"] {
+            let patch = vec![json!({"op": "add", "path": path, "value": "x"})];
+            let mut doc = Some(json!({}));
+            let conflicts = apply_ops(&mut doc, &patch).unwrap_err();
+            assert_eq!(
+                conflicts,
+                vec![format!("{}: invalid pointer (must start with /)", path)]
+            );
+        }
+    }
+
+    #[test]
+    fn resolve_keeps_names_inside_the_locales_directory() {
+        let dir = Path::new("locales");
+        assert_eq!(
+            resolve(dir, "zh/common.json"),
+            Ok(dir.join("zh/common.json"))
+        );
+        for name in [
+            "",
+            "/etc/passwd",
+            "../x.json",
+            "zh/../../x.json",
+            "./zh.json",
+        ] {
+            assert!(resolve(dir, name).is_err(), "{:?} resolved", name);
+        }
+    }
+
+    #[test]
+    fn segments_unescape() {
+        assert_eq!(segments(""), Ok(vec![]));
+        assert_eq!(
+            segments("/a~1b/c~0d"),
+            Ok(vec!["a/b".to_string(), "c~d".to_string()])
+        );
+    }
+}
@@ -0,0 +1,413 @@
+//! `php`: Laravel-style PHP language files, `<?php return [...];`, for the
+//! web dashboard whose backend reads its strings from them.
+//!
+//! Every locale becomes `<DIR>/<locale>/<FILE>.php` (`zh_CN/messages.php`),
+//! nested objects becoming nested arrays in base order, so `app.title` is
+//! `__('messages.app.title')`. Strings a locale lacks or has empty are left
+//! out for Laravel's fallback locale to fill, and `{{name}}` placeholders
+//! become Laravel's `:name`. `php import` reads such files back, with the
+//! subset of PHP they use: arrays (`[]` or `array()`), quoted strings,
+//! numbers, `true`, `false`, `null` and comments.
+
+use crate::config::Config;
+use crate::interop::{self, placeholder_names};
+use crate::locales::LocaleSet;
+use crate::rules::{self, placeholders};
+use crate::{layout, report, write};
+use clap::ArgMatches;
+use serde_json::{Map, Value};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+fn is_ident(c: char) -> bool {
+    c.is_ascii_alphanumeric() || c == '_'
+}
+
+/// `s` with `{{name}}` placeholders written `:name`.
+fn to_laravel(s: &str) -> String {
+    let mut out = String::new();
+    let mut from = 0;
+    for (start, end) in placeholders(s) {
+        let name = s[start + 2..end - 2].trim();
+        out.push_str(&s[from..start]);
+        if !name.is_empty() && name.chars().all(is_ident) {
+            out.push(':');
+            out.push_str(name);
+        } else {
+            out.push_str(&s[start..end]);
+        }
+        from = end;
+    }
+    out.push_str(&s[from..]);
+    out
+}
+
+/// The inverse of [`to_laravel`] for the placeholders `base` has.
+fn from_laravel(s: &str, base: &str) -> String {
+    let names = placeholder_names(base);
+    let mut out = String::new();
+    let mut rest = s;
+    while let Some(i) = rest.find(':') {
+        out.push_str(&rest[..i]);
+        rest = &rest[i + 1..];
+        let len = rest.find(|c: char| !is_ident(c)).unwrap_or(rest.len());
+        if len > 0 && names.contains(&&rest[..len]) {
+            out.push_str(&format!("{{{{{}}}}}", &rest[..len]));
+            rest = &rest[len..];
+        } else {
+            out.push(':');
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+fn quote(s: &str) -> String {
+    format!("'{}'", s.replace('\\', "\\\\").replace('\'', "\\'"))
+}
+
+/// The strings of `v` that `base` also has, non-empty, nested as in `v` and
+/// in `base`'s order.
+fn translated(v: &Value, base: &Value) -> Option<Value> {
+    match (v, base) {
+        (Value::String(s), Value::String(_)) if !s.is_empty() => Some(v.clone()),
+        (Value::Object(map), Value::Object(base_map)) => {
+            let out: Map<String, Value> = base_map
+                .iter()
+                .filter_map(|(k, b)| Some((k.clone(), translated(map.get(k)?, b)?)))
+                .collect();
+            (!out.is_empty()).then_some(Value::Object(out))
+        }
+        _ => None,
+    }
+}
+
+fn write_array(map: &Map<String, Value>, depth: usize, out: &mut String) {
+    out.push_str("[\n");
+    for (k, v) in map {
+        out.push_str(&"    ".repeat(depth + 1));
+        out.push_str(&quote(k));
+        out.push_str(" => ");
+        match v {
+            Value::Object(inner) => write_array(inner, depth + 1, out),
+            Value::String(s) => out.push_str(&quote(&to_laravel(s))),
+            _ => {}
+        }
+        out.push_str(",\n");
+    }
+    out.push_str(&"    ".repeat(depth));
+    out.push(']');
+}
+
+/// `php export <DIR>`: writes `<DIR>/<locale>/<FILE>.php` for every locale.
+pub fn run_export(dir: &Path, base: &Path, m: &ArgMatches, locale_set: &mut LocaleSet) -> i32 {
+    if let Err(e) = layout::require_flat("php") {
+        eprintln!("{}", e);
+        return 2;
+    }
+    let out_dir = Path::new(m.get_one::<String>("dir").unwrap());
+    let file = format!("{}.php", m.get_one::<String>("file").unwrap());
+    let base_v = match locale_set.value(base) {
+        Ok(v) => v,
+        Err(e) => {
+            eprintln!("Failed to read {}: {}", base.display(), e);
+            return 2;
+        }
+    };
+    let mut failed = 0;
+    for p in crate::target_files(dir, None) {
+        let v = match locale_set.value(&p) {
+            Ok(v) => v,
+            Err(e) => {
+                report::error(&p, e);
+                failed += 1;
+                continue;
+            }
+        };
+        let strings = match translated(&v, &base_v) {
+            Some(Value::Object(map)) => map,
+            _ => Map::new(),
+        };
+        let mut text = "<?php\n\nreturn ".to_string();
+        write_array(&strings, 0, &mut text);
+        text.push_str(";\n");
+        let target = out_dir
+            .join(layout::locale_of(&p).replace('-', "_"))
+            .join(&file);
+        if !interop::export(&target, &text, "") {
+            failed += 1;
+        }
+    }
+    if failed > 0 { 2 } else { 0 }
+}
+
+/// A reader for the PHP a language file is made of.
+struct Parser<'a> {
+    src: &'a str,
+    at: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn rest(&self) -> &'a str {
+        &self.src[self.at..]
+    }
+
+    fn error(&self, what: &str) -> String {
+        let line = self.src[..self.at].matches('\n').count() + 1;
+        format!("line {}: {}", line, what)
+    }
+
+    /// Skips whitespace and comments.
+    fn skip(&mut self) {
+        loop {
+            let rest = self.rest();
+            let trimmed = rest.trim_start();
+            self.at += rest.len() - trimmed.len();
+            let rest = self.rest();
+            if rest.starts_with("//") || rest.starts_with('#') {
+                self.at += rest.find('\n').unwrap_or(rest.len());
+            } else if rest.starts_with("/*") {
+                self.at += rest.find("*/").map(|i| i + 2).unwrap_or(rest.len());
+            } else {
+                return;
+            }
+        }
+    }
+
+    fn eat(&mut self, token: &str) -> bool {
+        self.skip();
+        let matches = self
+            .rest()
+            .get(..token.len())
+            .is_some_and(|s| s.eq_ignore_ascii_case(token));
+        if matches {
+            self.at += token.len();
+        }
+        matches
+    }
+
+    fn expect(&mut self, token: &str) -> Result<(), String> {
+        if self.eat(token) {
+            Ok(())
+        } else {
+            Err(self.error(&format!("expected {}", token)))
+        }
+    }
+
+    fn string(&mut self) -> Result<String, String> {
+        let quote = self.rest().chars().next().unwrap_or_default();
+        let mut out = String::new();
+        let mut chars = self.rest()[1..].char_indices();
+        while let Some((i, c)) = chars.next() {
+            match c {
+                c if c == quote => {
+                    self.at += i + 2;
+                    return Ok(out);
+                }
+                '\\' => {
+                    let Some((_, next)) = chars.next() else {
+                        break;
+                    };
+                    match (quote, next) {
+                        (_, '\\') => out.push('\\'),
+                        (_, c) if c == quote => out.push(c),
+                        ('"', 'n') => out.push('\n'),
+                        ('"', 't') => out.push('\t'),
+                        ('"', 'r') => out.push('\r'),
+                        ('"', '$') => out.push('$'),
+                        (_, c) => {
+                            out.push('\\');
+                            out.push(c);
+                        }
+                    }
+                }
+                c => out.push(c),
+            }
+        }
+        Err(self.error("unterminated string"))
+    }
+
+    fn value(&mut self) -> Result<Value, String> {
+        self.skip();
+        if self.eat("[") {
+            return self.array("]");
+        }
+        if self.eat("array") {
+            self.expect("(")?;
+            return self.array(")");
+        }
+        match self.rest().chars().next() {
+            Some('\'' | '"') => return self.string().map(Value::String),
+            None => return Err(self.error("unexpected end of file")),
+            _ => {}
+        }
+        let len = self
+            .rest()
+            .find(|c: char| !(is_ident(c) || c == '.' || c == '-'))
+            .unwrap_or(self.rest().len());
+        let word = &self.rest()[..len];
+        let v = match word.to_ascii_lowercase().as_str() {
+            "true" => Value::Bool(true),
+            "false" => Value::Bool(false),
+            "null" => Value::Null,
+            _ => serde_json::from_str::<serde_json::Number>(word)
+                .map(Value::Number)
+                .map_err(|_| self.error(&format!("unsupported value {:?}", word)))?,
+        };
+        self.at += len;
+        Ok(v)
+    }
+
+    /// The elements of an array up to `close`: an object when any has a
+    /// key, a list otherwise.
+    fn array(&mut self, close: &str) -> Result<Value, String> {
+        let mut items: Vec<(Option<String>, Value)> = Vec::new();
+        while !self.eat(close) {
+            let first = self.value()?;
+            let item = if self.eat("=>") {
+                let key = match first {
+                    Value::String(s) => s,
+                    Value::Number(n) => n.to_string(),
+                    _ => return Err(self.error("unsupported array key")),
+                };
+                (Some(key), self.value()?)
+            } else {
+                (None, first)
+            };
+            items.push(item);
+            if !self.eat(",") {
+                self.expect(close)?;
+                break;
+            }
+        }
+        if items.iter().all(|(k, _)| k.is_none()) {
+            return Ok(Value::Array(items.into_iter().map(|(_, v)| v).collect()));
+        }
+        let mut next = 0;
+        let mut map = Map::new();
+        for (k, v) in items {
+            let k = k.unwrap_or_else(|| next.to_string());
+            if let Ok(i) = k.parse::<usize>() {
+                next = next.max(i + 1);
+            }
+            map.insert(k, v);
+        }
+        Ok(Value::Object(map))
+    }
+}
+
+/// The array a PHP language file returns.
+fn parse(src: &str) -> Result<Value, String> {
+    let mut p = Parser { src, at: 0 };
+    p.expect("<?php")?;
+    p.expect("return")?;
+    let v = p.value()?;
+    p.expect(";")?;
+    Ok(v)
+}
+
+/// `php import <FILE>...`: merges the strings of language files into the
+/// locale file named by each file's directory (`zh_CN/messages.php`).
+/// The locale file an imported PHP file goes to, named by its directory.
+fn target(dir: &Path, src: &Path) -> Result<PathBuf, String> {
+    // The locale is the directory's name, which `./messages.php` lacks.
+    let code = src
+        .parent()
+        .and_then(|d| d.file_name())
+        .map(|n| n.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    layout::file_for_code(dir, &code).map_err(|e| format!("{} (expected <locale>/<file>.php)", e))
+}
+
+pub fn run_import(
+    dir: &Path,
+    base: &Path,
+    m: &ArgMatches,
+    config: &Config,
+    locale_set: &mut LocaleSet,
+) -> i32 {
+    if let Err(e) = layout::require_flat("php") {
+        eprintln!("{}", e);
+        return 2;
+    }
+    let base_v = match locale_set.value(base) {
+        Ok(v) => v,
+        Err(e) => {
+            eprintln!("Failed to read {}: {}", base.display(), e);
+            return 2;
+        }
+    };
+    let base_strings: HashMap<String, &str> = rules::string_entries(&base_v).into_iter().collect();
+    let (mut failed, mut unknown_total) = (0, 0);
+    for f in m.get_many::<String>("files").unwrap() {
+        let src = PathBuf::from(f);
+        let parsed = match fs::read_to_string(&src)
+            .map_err(|e| e.to_string())
+            .and_then(|t| parse(&t))
+        {
+            Ok(v) => v,
+            Err(e) => {
+                report::error(&src, e);
+                failed += 1;
+                continue;
+            }
+        };
+        let path = match target(dir, &src) {
+            Ok(path) => path,
+            Err(e) => {
+                report::error(&src, e);
+                failed += 1;
+                continue;
+            }
+        };
+        if layout::is_base(base, &path) {
+            report::detail(format!("Skipped {} (the base locale)", src.display()));
+            continue;
+        }
+        let (mut values, mut unknown) = (Vec::new(), Vec::new());
+        for (k, s) in rules::string_entries(&parsed) {
+            let Some(b) = base_strings.get(&k) else {
+                unknown.push(k);
+                continue;
+            };
+            let value = from_laravel(s, b);
+            if !value.is_empty() {
+                values.push((k, value));
+            }
+        }
+        unknown_total += unknown.len();
+        interop::report_unknown(&src, "UNKNOWN KEYS", &unknown);
+        match interop::merge(&path, &base_v, values, config, locale_set) {
+            Ok(merged) => report::info(format!(
+                "{} {} strings into {}",
+                write::verb("Merged", "merge"),
+                merged,
+                path.display()
+            )),
+            Err(e) => {
+                report::error(&path, e);
+                failed += 1;
+            }
+        }
+    }
+    interop::exit_code(failed, unknown_total)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn files_outside_a_locale_directory_are_refused() {
+        let dir = Path::new("locales");
+        for src in ["messages.php", "./messages.php", "/messages.php"] {
+            let e = target(dir, Path::new(src)).unwrap_err();
+            assert!(e.ends_with("(expected <locale>/<file>.php)"), "{}", e);
+        }
+        assert_eq!(
+            target(dir, Path::new("lang/zh_CN/messages.php")),
+            layout::file_for_code(dir, "zh_CN")
+        );
+    }
+}
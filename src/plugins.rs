@@ -0,0 +1,228 @@
+//! WebAssembly plugins: custom per-key checks, one `.wasm` module per file in
+//! the config's `plugins` directory, whose findings `check` merges into its
+//! report.
+//!
+//! A plugin exports `memory`, `alloc(len: i32) -> i32` and
+//! `check(ptr: i32, len: i32) -> i64`. `check` is called once per string with
+//! `{"locale", "key", "value", "base"}` as JSON at a buffer from `alloc`, and
+//! returns the address (high 32 bits) and length (low 32 bits) of a JSON
+//! array of `{"severity": "error" | "warning", "message"}`. Each call may
+//! run a fixed amount of fuel, roughly one unit per instruction; a plugin
+//! that runs out, such as one looping forever, fails the check.
+
+use crate::rules::{self, Finding, Severity};
+use serde::Deserialize;
+use serde_json::{Value, json};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+pub struct Plugin {
+    /// `plugin:` followed by the module's file stem.
+    pub id: &'static str,
+    #[cfg(feature = "plugins")]
+    runtime: runtime::Runtime,
+}
+
+#[derive(Deserialize)]
+struct Reported {
+    severity: String,
+    message: String,
+}
+
+/// The `.wasm` files in `dir`, sorted.
+fn modules(dir: &Path) -> Result<Vec<PathBuf>, String> {
+    let entries = fs::read_dir(dir).map_err(|e| format!("read {}: {}", dir.display(), e))?;
+    let mut out: Vec<PathBuf> = entries
+        .filter_map(|e| e.ok().map(|e| e.path()))
+        .filter(|p| p.extension().is_some_and(|e| e == "wasm"))
+        .collect();
+    out.sort();
+    Ok(out)
+}
+
+#[cfg(feature = "plugins")]
+fn plugin_id(path: &Path) -> &'static str {
+    let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+    Box::leak(format!("plugin:{}", stem).into_boxed_str())
+}
+
+#[cfg(feature = "plugins")]
+pub fn load(dir: &Path) -> Result<Vec<Plugin>, String> {
+    modules(dir)?
+        .into_iter()
+        .map(|p| {
+            let bytes = fs::read(&p).map_err(|e| format!("read {}: {}", p.display(), e))?;
+            let runtime = runtime::Runtime::new(&bytes)
+                .map_err(|e| format!("load plugin {}: {}", p.display(), e))?;
+            Ok(Plugin {
+                id: plugin_id(&p),
+                runtime,
+            })
+        })
+        .collect()
+}
+
+#[cfg(not(feature = "plugins"))]
+pub fn load(dir: &Path) -> Result<Vec<Plugin>, String> {
+    let found = modules(dir)?;
+    if found.is_empty() {
+        return Ok(Vec::new());
+    }
+    Err(format!(
+        "{} has {} plugins but this build has no plugin support (build with --features plugins)",
+        dir.display(),
+        found.len()
+    ))
+}
+
+impl Plugin {
+    #[cfg(feature = "plugins")]
+    fn call(&mut self, input: &str) -> Result<String, String> {
+        self.runtime.call(input)
+    }
+
+    #[cfg(not(feature = "plugins"))]
+    fn call(&mut self, _input: &str) -> Result<String, String> {
+        unreachable!("plugins are only loaded with the plugins feature")
+    }
+}
+
+/// Every plugin's findings for the strings of `doc`.
+pub fn run(
+    plugins: &mut [Plugin],
+    file: &Path,
+    doc: &Value,
+    base: Option<&Value>,
+) -> Result<Vec<Finding>, String> {
+    let locale = rules::locale_of(file);
+    let base_entries: HashMap<String, &str> = base
+        .map(rules::string_entries)
+        .unwrap_or_default()
+        .into_iter()
+        .collect();
+    let mut out = Vec::new();
+    for (key, value) in rules::string_entries(doc) {
+        let input = json!({
+            "locale": locale,
+            "key": key,
+            "value": value,
+            "base": base_entries.get(&key),
+        })
+        .to_string();
+        for plugin in plugins.iter_mut() {
+            let output = plugin
+                .call(&input)
+                .map_err(|e| format!("{} on {}: {}", plugin.id, key, e))?;
+            let reported: Vec<Reported> = serde_json::from_str(&output)
+                .map_err(|e| format!("{} on {}: bad output: {}", plugin.id, key, e))?;
+            for r in reported {
+                let severity = match r.severity.as_str() {
+                    "error" => Severity::Error,
+                    _ => Severity::Warning,
+                };
+                let mut f = Finding::new(file, plugin.id, key.clone(), severity, r.message);
+                f.value = Some(value.to_string());
+                out.push(f);
+            }
+        }
+    }
+    Ok(out)
+}
+
+#[cfg(feature = "plugins")]
+mod runtime {
+    use wasmi::core::TrapCode;
+    use wasmi::{Config, Engine, Linker, Memory, Module, Store, TypedFunc};
+
+    /// Fuel for one string: `alloc` and `check` together.
+    const FUEL: u64 = 100_000_000;
+
+    fn trap(e: wasmi::Error) -> String {
+        if e.as_trap_code() == Some(TrapCode::OutOfFuel) {
+            format!("ran out of fuel ({} units per string)", FUEL)
+        } else {
+            e.to_string()
+        }
+    }
+
+    pub struct Runtime {
+        store: Store<()>,
+        memory: Memory,
+        alloc: TypedFunc<i32, i32>,
+        check: TypedFunc<(i32, i32), i64>,
+    }
+
+    impl Runtime {
+        pub fn new(bytes: &[u8]) -> Result<Self, String> {
+            let engine = Engine::new(Config::default().consume_fuel(true));
+            let module = Module::new(&engine, bytes).map_err(|e| e.to_string())?;
+            let mut store = Store::new(&engine, ());
+            store.set_fuel(FUEL).map_err(|e| e.to_string())?;
+            // Plugins get no imports: no file system, network or clock.
+            let instance = Linker::<()>::new(&engine)
+                .instantiate(&mut store, &module)
+                .and_then(|pre| pre.start(&mut store))
+                .map_err(trap)?;
+            let memory = instance
+                .get_memory(&store, "memory")
+                .ok_or("no exported memory")?;
+            let alloc = instance
+                .get_typed_func(&store, "alloc")
+                .map_err(|e| format!("alloc: {}", e))?;
+            let check = instance
+                .get_typed_func(&store, "check")
+                .map_err(|e| format!("check: {}", e))?;
+            Ok(Runtime {
+                store,
+                memory,
+                alloc,
+                check,
+            })
+        }
+
+        pub fn call(&mut self, input: &str) -> Result<String, String> {
+            let len = input.len() as i32;
+            self.store.set_fuel(FUEL).map_err(|e| e.to_string())?;
+            let ptr = self.alloc.call(&mut self.store, len).map_err(trap)?;
+            self.memory
+                .write(&mut self.store, ptr as u32 as usize, input.as_bytes())
+                .map_err(|e| e.to_string())?;
+            let packed = self.check.call(&mut self.store, (ptr, len)).map_err(trap)? as u64;
+            let mut out = vec![0; (packed & 0xffff_ffff) as usize];
+            self.memory
+                .read(&self.store, (packed >> 32) as usize, &mut out)
+                .map_err(|e| e.to_string())?;
+            String::from_utf8(out).map_err(|e| e.to_string())
+        }
+    }
+}
+
+#[cfg(all(test, feature = "plugins"))]
+mod tests {
+    use super::runtime::Runtime;
+
+    /// A plugin whose `check` loops forever: `alloc` returns 0 and `check`
+    /// is `loop br 0 end unreachable`.
+    const LOOPING: &[u8] = &[
+        0x00, 0x61, 0x73, 0x6d, 0x01, 0x00, 0x00, 0x00, // header
+        0x01, 0x0c, 0x02, 0x60, 0x01, 0x7f, 0x01, 0x7f, 0x60, 0x02, 0x7f, 0x7f, 0x01,
+        0x7e, // types
+        0x03, 0x03, 0x02, 0x00, 0x01, // functions
+        0x05, 0x03, 0x01, 0x00, 0x01, // memory
+        0x07, 0x1a, 0x03, // exports
+        0x06, b'm', b'e', b'm', b'o', b'r', b'y', 0x02, 0x00, //
+        0x05, b'a', b'l', b'l', b'o', b'c', 0x00, 0x00, //
+        0x05, b'c', b'h', b'e', b'c', b'k', 0x00, 0x01, //
+        0x0a, 0x0f, 0x02, // code
+        0x04, 0x00, 0x41, 0x00, 0x0b, //
+        0x08, 0x00, 0x03, 0x40, 0x0c, 0x00, 0x0b, 0x00, 0x0b,
+    ];
+
+    #[test]
+    fn a_looping_plugin_runs_out_of_fuel() {
+        let mut runtime = Runtime::new(LOOPING).unwrap();
+        let e = runtime.call("{}").unwrap_err();
+        assert!(e.starts_with("ran out of fuel"), "{}", e);
+    }
+}
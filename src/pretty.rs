@@ -0,0 +1,213 @@
+//! `fmt`: rewriting locale files into the project's canonical form.
+
+use crate::config::{Config, FmtStyle, KeyOrder};
+use crate::locales::LocaleSet;
+use clap::ArgMatches;
+use serde_json::{Map, Value};
+use std::collections::HashMap;
+use std::path::Path;
+
+pub use crate::json::to_string;
+
+/// `v` with its object keys in `order`; `Base` follows `base` at every level
+/// where it has an object, then puts other keys alphabetically.
+pub fn reorder(v: &Value, order: KeyOrder, base: Option<&Value>) -> Value {
+    let Value::Object(map) = v else {
+        return v.clone();
+    };
+    let mut keys: Vec<&String> = map.keys().collect();
+    match order {
+        KeyOrder::Keep => {}
+        KeyOrder::Alpha => keys.sort(),
+        KeyOrder::Base => {
+            let rank: HashMap<&String, usize> = base
+                .and_then(Value::as_object)
+                .map(|b| b.keys().enumerate().map(|(i, k)| (k, i)).collect())
+                .unwrap_or_default();
+            keys.sort_by(|a, b| match (rank.get(a), rank.get(b)) {
+                (Some(x), Some(y)) => x.cmp(y),
+                (Some(_), None) => std::cmp::Ordering::Less,
+                (None, Some(_)) => std::cmp::Ordering::Greater,
+                (None, None) => a.cmp(b),
+            });
+        }
+    }
+    let mut out = Map::new();
+    for k in keys {
+        let child_base = base.and_then(|b| b.get(k));
+        out.insert(k.clone(), reorder(&map[k], order, child_base));
+    }
+    Value::Object(out)
+}
+
+/// `v` in the canonical form of `style`, ordering keys after `base` when the
+/// style asks for it.
+pub fn canonical(v: &Value, base: Option<&Value>, style: &FmtStyle) -> String {
+    to_string(&reorder(v, style.key_order, base), style)
+}
+
+/// The string literals of `text` (keys included) by path, sorted, to tell
+/// whether two texts hold the same strings, repeats counted.
+fn strings(text: &str) -> Vec<(String, bool, String)> {
+    let mut out: Vec<_> = crate::scan::literals(text)
+        .map(|l| (l.path, l.is_key, crate::scan::unescape(l.raw)))
+        .collect();
+    out.sort();
+    out
+}
+
+/// What goes wrong when `text` (parsed as `v`) is re-serialized into
+/// `formatted`: strings lost or altered (repeated keys, escapes), values
+/// that do not read back the same, and output that formatting changes
+/// again.
+fn roundtrip_problems(
+    text: &str,
+    v: &Value,
+    formatted: &str,
+    base: Option<&Value>,
+    style: &FmtStyle,
+) -> Vec<String> {
+    let mut out = Vec::new();
+    let Ok(reparsed) = serde_json::from_str::<Value>(formatted) else {
+        out.push("formatted output does not parse".to_string());
+        return out;
+    };
+    if reparsed != *v {
+        out.push("formatted output reads back as different values".to_string());
+    }
+    let (before, after) = (strings(text), strings(formatted));
+    if before != after {
+        let lost = before.iter().filter(|s| !after.contains(s)).count();
+        out.push(format!(
+            "{} strings lost or altered (repeated keys or escapes)",
+            lost.max(before.len().abs_diff(after.len()))
+        ));
+    }
+    if canonical(&reparsed, base, style) != formatted {
+        out.push("formatting the output again changes it".to_string());
+    }
+    out
+}
+
+/// `fmt --verify-roundtrip`: formats every file in memory only, and reports
+/// files formatting would change, with anything it would lose.
+fn verify(dir: &Path, base: &Path, config: &Config, locale_set: &mut LocaleSet) -> i32 {
+    let style = &config.fmt;
+    let (mut changed, mut lossy, mut unreadable) = (0, 0, 0);
+    let files = crate::target_files(dir, None);
+    for p in &files {
+        let loaded = locale_set
+            .text(p)
+            .and_then(|text| locale_set.value(p).map(|v| (text, v)));
+        let (text, v) = match loaded {
+            Ok(loaded) => loaded,
+            Err(e) => {
+                crate::report::error(p, e);
+                unreadable += 1;
+                continue;
+            }
+        };
+        let base_v = locale_set.value(&crate::layout::base_for(base, p)).ok();
+        let formatted = canonical(&v, base_v.as_deref(), style);
+        let problems = roundtrip_problems(&text, &v, &formatted, base_v.as_deref(), style);
+        if !problems.is_empty() {
+            lossy += 1;
+            crate::report::header(p, "NOT ROUND-TRIP SAFE", problems.len());
+            for problem in &problems {
+                crate::report::detail(format!("  {}", problem));
+            }
+        } else if formatted != *text {
+            changed += 1;
+            crate::report::flag(p, "WOULD CHANGE");
+        } else {
+            crate::report::ok(p);
+        }
+    }
+    crate::report::headline(
+        files.len(),
+        &format!(
+            "{} files would change, {} not round-trip safe",
+            changed + lossy,
+            lossy
+        ),
+        unreadable,
+    );
+    if unreadable > 0 {
+        2
+    } else if changed + lossy > 0 {
+        1
+    } else {
+        0
+    }
+}
+
+/// `fmt`: rewrites every locale file (or, with `--check`, only lists those)
+/// that is not in the configured canonical form.
+pub fn run(
+    dir: &Path,
+    base: &Path,
+    m: &ArgMatches,
+    config: &Config,
+    locale_set: &mut LocaleSet,
+) -> i32 {
+    if m.get_flag("verify_roundtrip") {
+        return verify(dir, base, config, locale_set);
+    }
+    let check = m.get_flag("check");
+    let style = &config.fmt;
+    let mut unformatted = 0;
+    let mut unreadable = 0;
+    let files = crate::target_files(dir, None);
+    for p in &files {
+        let loaded = locale_set
+            .text(p)
+            .and_then(|text| locale_set.value(p).map(|v| (text, v)));
+        let (text, v) = match loaded {
+            Ok(loaded) => loaded,
+            Err(e) => {
+                crate::report::error(p, e);
+                unreadable += 1;
+                continue;
+            }
+        };
+        let base_v = locale_set.value(&crate::layout::base_for(base, p)).ok();
+        let formatted = canonical(&v, base_v.as_deref(), style);
+        if formatted == *text {
+            crate::report::ok(p);
+            continue;
+        }
+        unformatted += 1;
+        if check {
+            crate::report::flag(p, "NOT FORMATTED");
+        } else if let Err(e) = crate::write::write_file(p, &formatted) {
+            crate::report::error(p, e);
+            unreadable += 1;
+        } else {
+            crate::report::info(format!(
+                "{} {}",
+                crate::write::verb("Formatted", "format"),
+                p.display()
+            ));
+        }
+    }
+    crate::report::headline(
+        files.len(),
+        &format!(
+            "{} files {}",
+            unformatted,
+            if check {
+                "not formatted"
+            } else {
+                "reformatted"
+            }
+        ),
+        unreadable,
+    );
+    if unreadable > 0 {
+        2
+    } else if check && unformatted > 0 {
+        1
+    } else {
+        0
+    }
+}
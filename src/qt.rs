@@ -0,0 +1,491 @@
+//! `qt`: Qt Linguist `.ts` files, so the desktop components translated with
+//! Qt's tooling share the locales here.
+//!
+//! Every locale but the base becomes `<DIR>/<locale>.ts` (`zh_CN.ts`). A
+//! key's context is its dotted prefix (`settings.proxy` for
+//! `settings.proxy.title`, empty for top-level keys), and each message
+//! carries the key as its `id` with the base string as `source`, so the
+//! files also work with `lupdate -idbased`. `{{name}}` placeholders become
+//! Qt's `%1`, `%2`… numbered by position in the base string. Missing or
+//! empty translations are written `unfinished`; base descriptions become
+//! `extracomment`s. `qt import` reads the finished translations back by
+//! `id`, or by context and source text for messages without one; plural
+//! (numerus) forms are skipped.
+
+use crate::config::Config;
+use crate::interop::{self, placeholder_names, read_base};
+use crate::locales::LocaleSet;
+use crate::rules::{self, placeholders};
+use crate::{layout, meta, report, write};
+use clap::ArgMatches;
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// `value` with its placeholders numbered after those of `base`.
+fn to_qt(value: &str, base: &str) -> String {
+    let mut order = placeholder_names(base);
+    let mut out = String::new();
+    let mut from = 0;
+    for (start, end) in placeholders(value) {
+        let name = value[start + 2..end - 2].trim();
+        let n = match order.iter().position(|o| *o == name) {
+            Some(i) => i + 1,
+            None => {
+                order.push(name);
+                order.len()
+            }
+        };
+        out.push_str(&value[from..start]);
+        out.push_str(&format!("%{}", n));
+        from = end;
+    }
+    out.push_str(&value[from..]);
+    out
+}
+
+/// The inverse of [`to_qt`]: `%N` back to the `N`th placeholder of `base`.
+fn from_qt(text: &str, base: &str) -> String {
+    let order = placeholder_names(base);
+    let mut out = String::new();
+    let mut rest = text;
+    while let Some(i) = rest.find('%') {
+        out.push_str(&rest[..i]);
+        rest = &rest[i + 1..];
+        let digits = rest.chars().take_while(char::is_ascii_digit).count();
+        match rest[..digits]
+            .parse::<usize>()
+            .ok()
+            .and_then(|n| order.get(n.wrapping_sub(1)))
+        {
+            Some(name) => {
+                out.push_str(&format!("{{{{{}}}}}", name));
+                rest = &rest[digits..];
+            }
+            None => out.push('%'),
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+/// The context of a dotted key: everything before its last segment.
+fn context_of(key: &str) -> &str {
+    key.rsplit_once('.').map(|(c, _)| c).unwrap_or("")
+}
+
+fn escape(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// Qt's locale spelling: `zh_CN` for `zh-CN`.
+fn qt_locale(locale: &str) -> String {
+    locale.replace('-', "_")
+}
+
+/// The `.ts` document of one locale. `base` holds the base strings by key
+/// in order.
+fn document(
+    locale: &str,
+    source_locale: &str,
+    base: &[(String, String)],
+    strings: &HashMap<String, &str>,
+    descriptions: &HashMap<&str, &str>,
+) -> String {
+    let mut contexts: Vec<(&str, Vec<&(String, String)>)> = Vec::new();
+    for entry in base {
+        let context = context_of(&entry.0);
+        match contexts.iter_mut().find(|(c, _)| *c == context) {
+            Some((_, entries)) => entries.push(entry),
+            None => contexts.push((context, vec![entry])),
+        }
+    }
+    let mut out = format!(
+        "<?xml version=\"1.0\" encoding=\"utf-8\"?>\n<!DOCTYPE TS>\n<TS version=\"2.1\" language=\"{}\" sourcelanguage=\"{}\">\n",
+        escape(&qt_locale(locale)),
+        escape(&qt_locale(source_locale))
+    );
+    for (context, entries) in contexts {
+        out.push_str("<context>\n");
+        out.push_str(&format!("    <name>{}</name>\n", escape(context)));
+        for (key, b) in entries {
+            out.push_str(&format!("    <message id=\"{}\">\n", escape(key)));
+            out.push_str(&format!(
+                "        <source>{}</source>\n",
+                escape(&to_qt(b, b))
+            ));
+            if let Some(d) = descriptions.get(key.as_str()) {
+                out.push_str(&format!(
+                    "        <extracomment>{}</extracomment>\n",
+                    escape(d)
+                ));
+            }
+            match strings.get(key).filter(|s| !s.is_empty()) {
+                Some(s) => out.push_str(&format!(
+                    "        <translation>{}</translation>\n",
+                    escape(&to_qt(s, b))
+                )),
+                None => out.push_str("        <translation type=\"unfinished\"></translation>\n"),
+            }
+            out.push_str("    </message>\n");
+        }
+        out.push_str("</context>\n");
+    }
+    out.push_str("</TS>\n");
+    out
+}
+
+/// `qt export <DIR>`: writes `<DIR>/<locale>.ts` for every locale but the
+/// base.
+pub fn run_export(dir: &Path, base: &Path, m: &ArgMatches, locale_set: &mut LocaleSet) -> i32 {
+    if let Err(e) = layout::require_flat("qt") {
+        eprintln!("{}", e);
+        return 2;
+    }
+    let out_dir = Path::new(m.get_one::<String>("dir").unwrap());
+    let (base_strings, metadata) = match read_base(base, locale_set)
+        .and_then(|b| meta::load(base, locale_set).map(|meta| (b, meta)))
+    {
+        Ok(loaded) => loaded,
+        Err(e) => {
+            eprintln!("Failed to read {}: {}", base.display(), e);
+            return 2;
+        }
+    };
+    let descriptions: HashMap<&str, &str> = metadata
+        .iter()
+        .filter(|(_, m)| !m.description.is_empty())
+        .map(|(k, m)| (k.as_str(), m.description.as_str()))
+        .collect();
+    let source_locale = layout::locale_of(base);
+    let mut failed = 0;
+    for p in crate::target_files(dir, None) {
+        if layout::is_base(base, &p) {
+            continue;
+        }
+        let v = match locale_set.value(&p) {
+            Ok(v) => v,
+            Err(e) => {
+                report::error(&p, e);
+                failed += 1;
+                continue;
+            }
+        };
+        let strings: HashMap<String, &str> = rules::string_entries(&v).into_iter().collect();
+        let locale = layout::locale_of(&p);
+        let text = document(
+            &locale,
+            &source_locale,
+            &base_strings,
+            &strings,
+            &descriptions,
+        );
+        let target = out_dir.join(format!("{}.ts", qt_locale(&locale)));
+        if !interop::export(&target, &text, "") {
+            failed += 1;
+        }
+    }
+    if failed > 0 { 2 } else { 0 }
+}
+
+/// An element of a parsed `.ts` file. Text is kept only for elements
+/// without child elements, which is all `.ts` files need.
+#[derive(Default)]
+struct Element {
+    name: String,
+    attrs: Vec<(String, String)>,
+    children: Vec<Element>,
+    text: String,
+}
+
+impl Element {
+    fn attr(&self, name: &str) -> Option<&str> {
+        self.attrs
+            .iter()
+            .find(|(k, _)| k == name)
+            .map(|(_, v)| v.as_str())
+    }
+
+    fn child(&self, name: &str) -> Option<&Element> {
+        self.children.iter().find(|c| c.name == name)
+    }
+
+    fn all<'a>(&'a self, name: &'a str) -> impl Iterator<Item = &'a Element> {
+        self.children.iter().filter(move |c| c.name == name)
+    }
+}
+
+fn unescape(s: &str) -> Result<String, String> {
+    let mut out = String::new();
+    let mut rest = s;
+    while let Some(i) = rest.find('&') {
+        out.push_str(&rest[..i]);
+        let end = rest[i..]
+            .find(';')
+            .ok_or_else(|| format!("unterminated entity in {:?}", s))?;
+        let entity = &rest[i + 1..i + end];
+        let c = match entity {
+            "lt" => '<',
+            "gt" => '>',
+            "amp" => '&',
+            "quot" => '"',
+            "apos" => '\'',
+            _ => entity
+                .strip_prefix("#x")
+                .map(|h| u32::from_str_radix(h, 16))
+                .or_else(|| entity.strip_prefix('#').map(str::parse))
+                .and_then(Result::ok)
+                .and_then(char::from_u32)
+                .ok_or_else(|| format!("unknown entity &{};", entity))?,
+        };
+        out.push(c);
+        rest = &rest[i + end + 1..];
+    }
+    out.push_str(rest);
+    Ok(out)
+}
+
+/// Parses the attributes of a start tag, given what follows its name.
+fn attributes(s: &str) -> Result<Vec<(String, String)>, String> {
+    let mut out = Vec::new();
+    let mut rest = s.trim();
+    while !rest.is_empty() {
+        let (name, after) = rest
+            .split_once('=')
+            .ok_or_else(|| format!("bad attribute in <{}>", s.trim()))?;
+        let after = after.trim_start();
+        let quote = after
+            .chars()
+            .next()
+            .filter(|q| *q == '"' || *q == '\'')
+            .ok_or_else(|| format!("unquoted attribute {}", name.trim()))?;
+        let end = after[1..]
+            .find(quote)
+            .ok_or_else(|| format!("unterminated attribute {}", name.trim()))?;
+        out.push((name.trim().to_string(), unescape(&after[1..1 + end])?));
+        rest = after[end + 2..].trim_start();
+    }
+    Ok(out)
+}
+
+/// Parses `text` into its root element. Handles what `.ts` files use:
+/// elements, attributes, character and entity references, comments,
+/// CDATA sections and the XML and DOCTYPE declarations.
+fn parse(text: &str) -> Result<Element, String> {
+    let mut stack = vec![Element::default()];
+    let mut rest = text;
+    while let Some(i) = rest.find('<') {
+        let content = &rest[..i];
+        if !content.trim().is_empty() || stack.len() > 1 {
+            stack.last_mut().unwrap().text.push_str(&unescape(content)?);
+        }
+        rest = &rest[i..];
+        let skip = |rest: &str, close: &str| {
+            rest.find(close)
+                .map(|end| end + close.len())
+                .ok_or_else(|| {
+                    format!("unterminated {}", rest.chars().take(12).collect::<String>())
+                })
+        };
+        if rest.starts_with("<!--") {
+            rest = &rest[skip(rest, "-->")?..];
+        } else if let Some(cdata) = rest.strip_prefix("<![CDATA[") {
+            let end = cdata.find("]]>").ok_or("unterminated CDATA section")?;
+            stack.last_mut().unwrap().text.push_str(&cdata[..end]);
+            rest = &cdata[end + 3..];
+        } else if rest.starts_with("<?") {
+            rest = &rest[skip(rest, "?>")?..];
+        } else if rest.starts_with("<!") {
+            rest = &rest[skip(rest, ">")?..];
+        } else {
+            let end = skip(rest, ">")?;
+            let tag = &rest[1..end - 1];
+            rest = &rest[end..];
+            if let Some(name) = tag.strip_prefix('/') {
+                let done = stack
+                    .pop()
+                    .filter(|_| !stack.is_empty())
+                    .ok_or("unbalanced tags")?;
+                if done.name != name.trim() {
+                    return Err(format!("</{}> closes <{}>", name.trim(), done.name));
+                }
+                stack.last_mut().unwrap().children.push(done);
+                continue;
+            }
+            let (tag, empty) = match tag.strip_suffix('/') {
+                Some(t) => (t, true),
+                None => (tag, false),
+            };
+            let (name, attrs) = tag.split_once(char::is_whitespace).unwrap_or((tag, ""));
+            let element = Element {
+                name: name.to_string(),
+                attrs: attributes(attrs)?,
+                ..Element::default()
+            };
+            if empty {
+                stack.last_mut().unwrap().children.push(element);
+            } else {
+                stack.push(element);
+            }
+        }
+    }
+    if stack.len() != 1 {
+        return Err(format!("<{}> is not closed", stack.last().unwrap().name));
+    }
+    stack
+        .pop()
+        .and_then(|doc| doc.children.into_iter().next())
+        .ok_or_else(|| "no root element".to_string())
+}
+
+/// `qt import <FILE>...`: merges the finished translations of each `.ts`
+/// file into the locale file of its `language`.
+/// The locale file an imported `.ts` file goes to: its `language`, or else
+/// its file name, which must be a plain locale code.
+fn target(dir: &Path, root: &Element, src: &Path) -> Result<PathBuf, String> {
+    let code = match root.attr("language") {
+        Some(l) if !l.is_empty() => l.to_string(),
+        _ => src
+            .file_stem()
+            .map(|s| s.to_string_lossy().into_owned())
+            .unwrap_or_default(),
+    };
+    layout::file_for_code(dir, &code)
+}
+
+pub fn run_import(
+    dir: &Path,
+    base: &Path,
+    m: &ArgMatches,
+    config: &Config,
+    locale_set: &mut LocaleSet,
+) -> i32 {
+    if let Err(e) = layout::require_flat("qt") {
+        eprintln!("{}", e);
+        return 2;
+    }
+    let (base_v, base_strings) = match locale_set
+        .value(base)
+        .and_then(|v| read_base(base, locale_set).map(|s| (v, s)))
+    {
+        Ok(loaded) => loaded,
+        Err(e) => {
+            eprintln!("Failed to read {}: {}", base.display(), e);
+            return 2;
+        }
+    };
+    let by_key: HashMap<&str, &str> = base_strings
+        .iter()
+        .map(|(k, s)| (k.as_str(), s.as_str()))
+        .collect();
+    let by_source: HashMap<(&str, String), &str> = base_strings
+        .iter()
+        .map(|(k, s)| ((context_of(k), to_qt(s, s)), k.as_str()))
+        .collect();
+    let (mut failed, mut unknown_total) = (0, 0);
+    for f in m.get_many::<String>("files").unwrap() {
+        let src = PathBuf::from(f);
+        let root = match fs::read_to_string(&src)
+            .map_err(|e| e.to_string())
+            .and_then(|t| parse(&t))
+        {
+            Ok(root) if root.name == "TS" => root,
+            Ok(root) => {
+                report::error(&src, format!("root element is <{}>, not <TS>", root.name));
+                failed += 1;
+                continue;
+            }
+            Err(e) => {
+                report::error(&src, e);
+                failed += 1;
+                continue;
+            }
+        };
+        let path = match target(dir, &root, &src) {
+            Ok(path) => path,
+            Err(e) => {
+                report::error(&src, e);
+                failed += 1;
+                continue;
+            }
+        };
+        if layout::is_base(base, &path) {
+            report::detail(format!("Skipped {} (the base locale)", src.display()));
+            continue;
+        }
+        let (mut values, mut unfinished, mut unknown) = (Vec::new(), 0, Vec::new());
+        for context in root.all("context") {
+            let context_name = context.child("name").map(|n| n.text.as_str()).unwrap_or("");
+            for message in context.all("message") {
+                // Plural (numerus) forms have no counterpart in the locales.
+                if message.attr("numerus") == Some("yes") {
+                    continue;
+                }
+                let source = message
+                    .child("source")
+                    .map(|s| s.text.as_str())
+                    .unwrap_or("");
+                let key = match message.attr("id") {
+                    Some(id) => by_key.get_key_value(id).map(|(k, _)| *k),
+                    None => by_source.get(&(context_name, source.to_string())).copied(),
+                };
+                let Some(key) = key else {
+                    unknown.push(message.attr("id").unwrap_or(source).to_string());
+                    continue;
+                };
+                let Some(translation) = message.child("translation") else {
+                    continue;
+                };
+                if translation.attr("type").is_some() || translation.text.is_empty() {
+                    unfinished += 1;
+                    continue;
+                }
+                values.push((key.to_string(), from_qt(&translation.text, by_key[key])));
+            }
+        }
+        unknown_total += unknown.len();
+        interop::report_unknown(&src, "UNKNOWN MESSAGES", &unknown);
+        match interop::merge(&path, &base_v, values, config, locale_set) {
+            Ok(merged) => report::info(format!(
+                "{} {} translations into {}, {} unfinished",
+                write::verb("Merged", "merge"),
+                merged,
+                path.display(),
+                unfinished
+            )),
+            Err(e) => {
+                report::error(&path, e);
+                failed += 1;
+            }
+        }
+    }
+    interop::exit_code(failed, unknown_total)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unterminated_multibyte_text_is_cut_at_a_char_boundary() {
+        let Err(e) = parse("<TS><!-- 中文中文中文中文") else {
+            panic!("parsed");
+        };
+        assert_eq!(e, "unterminated <!-- 中文中文中文中");
+    }
+
+    #[test]
+    fn languages_that_are_not_locale_codes_are_refused() {
+        let dir = Path::new("locales");
+        let src = Path::new("in/app_zh.ts");
+        let root = parse("<TS language=\"../../evil\"></TS>").unwrap();
+        assert!(target(dir, &root, src).is_err());
+        let root = parse("<TS language=\"zh_CN\"></TS>").unwrap();
+        assert_eq!(target(dir, &root, src), layout::file_for_code(dir, "zh_CN"));
+        let root = parse("<TS></TS>").unwrap();
+        assert!(target(dir, &root, Path::new("in/../..ts")).is_err());
+    }
+}
@@ -0,0 +1,101 @@
+//! `--regional`: keys one regional variant of a language has and a sibling
+//! lacks (`zh-CN` and `zh-TW`, `pt-BR` and `pt-PT`), which usually means a
+//! change updated only one of them.
+
+use crate::layout;
+use crate::locales::LocaleSet;
+use crate::report::{self, Thresholds};
+use crate::rules::{self, Finding, Severity};
+use std::collections::{BTreeMap, HashSet};
+use std::path::{Path, PathBuf};
+
+/// The gaps between sibling locales of every language except the base's,
+/// namespace by namespace, and how many files could not be read.
+pub fn findings(dir: &Path, base: &Path, locale_set: &mut LocaleSet) -> (Vec<Finding>, usize) {
+    // (language, namespace) -> files of its locales
+    let mut groups: BTreeMap<(String, Option<String>), Vec<PathBuf>> = BTreeMap::new();
+    for p in crate::target_files(dir, None) {
+        if layout::is_base(base, &p) {
+            continue;
+        }
+        let language = rules::language_of(&rules::locale_of(&p));
+        groups
+            .entry((language, layout::namespace_of(&p)))
+            .or_default()
+            .push(p);
+    }
+    let mut out = Vec::new();
+    let mut unreadable = 0;
+    for mut files in groups.into_values().filter(|g| g.len() > 1) {
+        files.sort();
+        let mut keys: Vec<(PathBuf, HashSet<String>)> = Vec::new();
+        for p in files {
+            match locale_set.value(&p) {
+                Ok(v) => {
+                    let set = rules::string_entries(&v)
+                        .into_iter()
+                        .map(|(k, _)| k)
+                        .collect();
+                    keys.push((p, set));
+                }
+                Err(e) => {
+                    unreadable += 1;
+                    report::error(&p, e);
+                }
+            }
+        }
+        let mut all: Vec<&String> = keys.iter().flat_map(|(_, s)| s).collect();
+        all.sort();
+        all.dedup();
+        for (p, own) in &keys {
+            for k in all.iter().filter(|k| !own.contains(**k)) {
+                let having: Vec<String> = keys
+                    .iter()
+                    .filter(|(_, s)| s.contains(*k))
+                    .map(|(q, _)| rules::locale_of(q))
+                    .collect();
+                out.push(Finding::new(
+                    p,
+                    "regional-variant",
+                    (*k).clone(),
+                    Severity::Warning,
+                    format!("missing (present in {})", having.join(", ")),
+                ));
+            }
+        }
+    }
+    (out, unreadable)
+}
+
+/// Prints `findings` file by file, in file order.
+pub fn print(findings: &[Finding]) {
+    let mut by_file: BTreeMap<&Path, Vec<&Finding>> = BTreeMap::new();
+    for f in findings {
+        by_file.entry(&f.file).or_default().push(f);
+    }
+    for (p, found) in by_file {
+        report::header(p, "VARIANT GAPS", found.len());
+        for f in found {
+            report::detail(format!("  {}: {}", f.key, f.message));
+        }
+    }
+}
+
+pub fn run(dir: &Path, base: &Path, locale_set: &mut LocaleSet, thresholds: &Thresholds) -> i32 {
+    let (found, unreadable) = findings(dir, base, locale_set);
+    print(&found);
+    report::emit(&found);
+    report::headline(
+        crate::target_files(dir, None).len(),
+        &format!(
+            "{} keys missing from a regional variant in {} files",
+            found.len(),
+            report::files_with(&found)
+        ),
+        unreadable,
+    );
+    if unreadable > 0 {
+        return 2;
+    }
+    thresholds.exit_code(0, found.len())
+}
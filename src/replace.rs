@@ -0,0 +1,126 @@
+//! `replace`: a regex find-and-replace over the string values of the
+//! locale files, for product renames and terminology sweeps. Only the
+//! changed literals are rewritten, escaped in the configured style, so the
+//! files keep their layout.
+
+use crate::changelog;
+use crate::config::Config;
+use crate::edit;
+use crate::layout;
+use crate::locales::LocaleSet;
+use crate::report;
+use crate::rules;
+use crate::scan;
+use crate::write;
+use clap::ArgMatches;
+use globset::{Glob, GlobSet, GlobSetBuilder};
+use regex::{NoExpand, Regex, RegexBuilder};
+use std::path::Path;
+
+fn pattern(m: &ArgMatches) -> Result<Regex, String> {
+    let pattern = m.get_one::<String>("pattern").unwrap();
+    let pattern = if m.get_flag("fixed_strings") {
+        regex::escape(pattern)
+    } else {
+        pattern.clone()
+    };
+    RegexBuilder::new(&pattern)
+        .case_insensitive(m.get_flag("ignore_case"))
+        .build()
+        .map_err(|e| format!("Invalid pattern: {}", e))
+}
+
+/// The `--keys` globs, `None` when every key is fair game.
+fn key_globs(m: &ArgMatches) -> Result<Option<GlobSet>, String> {
+    let Some(globs) = m.get_many::<String>("keys") else {
+        return Ok(None);
+    };
+    let mut builder = GlobSetBuilder::new();
+    for g in globs {
+        builder.add(Glob::new(g).map_err(|e| format!("keys {}: {}", g, e))?);
+    }
+    builder.build().map(Some).map_err(|e| e.to_string())
+}
+
+pub fn run(dir: &Path, m: &ArgMatches, config: &Config, locale_set: &mut LocaleSet) -> i32 {
+    let (re, keys) = match pattern(m).and_then(|re| key_globs(m).map(|k| (re, k))) {
+        Ok(r) => r,
+        Err(e) => {
+            eprintln!("{}", e);
+            return 2;
+        }
+    };
+    let with = m.get_one::<String>("with").unwrap();
+    let fixed = m.get_flag("fixed_strings");
+    // `all`, the default, includes the base.
+    let wanted: Vec<&String> = m
+        .get_many::<String>("locale")
+        .map_or_else(Vec::new, |l| l.filter(|l| *l != "all").collect());
+    let (mut strings, mut files, mut failed) = (0, 0, false);
+    for path in layout::files(dir) {
+        if !wanted.is_empty() && !wanted.contains(&&rules::locale_of(&path)) {
+            continue;
+        }
+        let text = match locale_set.text(&path) {
+            Ok(t) => t,
+            Err(e) => {
+                report::error(&path, e);
+                failed = true;
+                continue;
+            }
+        };
+        let ns = layout::namespace_of(&path);
+        let mut out = String::new();
+        let mut copied = 0;
+        let mut changes = Vec::new();
+        for lit in scan::literals(&text).filter(|l| !l.is_key) {
+            let key = match &ns {
+                Some(ns) => format!("{}:{}", ns, lit.path),
+                None => lit.path.clone(),
+            };
+            if keys.as_ref().is_some_and(|g| !g.is_match(&key)) {
+                continue;
+            }
+            let old = scan::unescape(lit.raw);
+            let new = if fixed {
+                re.replace_all(&old, NoExpand(with))
+            } else {
+                re.replace_all(&old, with.as_str())
+            };
+            if new == old {
+                continue;
+            }
+            out.push_str(&text[copied..lit.offset]);
+            out.push_str(&edit::literal(&new, config));
+            copied = lit.offset + lit.raw.len();
+            changes.push((key, old.clone(), new.into_owned()));
+        }
+        if changes.is_empty() {
+            continue;
+        }
+        out.push_str(&text[copied..]);
+        report::header(&path, "CHANGES", changes.len());
+        for (k, old, new) in &changes {
+            report::detail(format!(
+                "  {}: {} -> {}",
+                k,
+                changelog::quoted(old),
+                changelog::quoted(new)
+            ));
+        }
+        if let Err(e) = write::write_file(&path, &out) {
+            eprintln!("Failed to write {}: {}", path.display(), e);
+            failed = true;
+            continue;
+        }
+        strings += changes.len();
+        files += 1;
+    }
+    report::info(format!(
+        "{} {} strings in {} files",
+        write::verb("Replaced", "replace"),
+        strings,
+        files
+    ));
+    if failed { 2 } else { 0 }
+}
@@ -0,0 +1,457 @@
+use crate::rules::{Finding, Severity};
+use crate::scan;
+use clap::ArgMatches;
+use indicatif::{ProgressBar, ProgressStyle};
+use serde_json::json;
+use std::collections::HashMap;
+use std::fmt::Display;
+use std::io::IsTerminal;
+use std::path::Path;
+use std::sync::{Mutex, OnceLock};
+use std::time::Instant;
+
+/// Decides the exit code from finding counts: errors fail once there are
+/// more than `max_errors` of them, warnings only fail with `--deny warnings`
+/// or once there are more than `max_warnings`.
+pub struct Thresholds {
+    pub deny_warnings: bool,
+    pub max_errors: usize,
+    pub max_warnings: Option<usize>,
+}
+
+impl Thresholds {
+    pub fn from_matches(m: &ArgMatches) -> Self {
+        Thresholds {
+            deny_warnings: m
+                .get_many::<String>("deny")
+                .is_some_and(|mut d| d.any(|s| s == "warnings")),
+            max_errors: m.get_one::<usize>("max_errors").copied().unwrap_or(0),
+            max_warnings: m.get_one::<usize>("max_warnings").copied(),
+        }
+    }
+
+    pub fn exit_code(&self, errors: usize, warnings: usize) -> i32 {
+        if errors > self.max_errors
+            || (self.deny_warnings && warnings > 0)
+            || self.max_warnings.is_some_and(|max| warnings > max)
+        {
+            1
+        } else {
+            0
+        }
+    }
+}
+
+/// How much to print and whether to use ANSI colors, set once from the
+/// command line.
+pub struct Style {
+    /// -1 with `-q`, 0 by default, 1 with `-v`, 2 with `-vv`.
+    pub verbosity: i8,
+    pub color: bool,
+    /// Whether to draw a progress bar over multi-file runs; only when both
+    /// stdout and stderr are terminals.
+    pub progress: bool,
+    pub format: Format,
+    /// Print per-file counts and a totals table instead of per-key lines.
+    pub summary: bool,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    Text,
+    /// GitLab Code Quality (Code Climate) JSON.
+    CodeClimate,
+    /// One row per finding, for spreadsheets.
+    Csv,
+}
+
+static STYLE: OnceLock<Style> = OnceLock::new();
+static PROGRESS: Mutex<Option<ProgressBar>> = Mutex::new(None);
+
+/// `--exit-zero` and `--detailed-exit-codes`.
+static EXIT: OnceLock<(bool, bool)> = OnceLock::new();
+/// The `--detailed-exit-codes` bits of every finding reported so far.
+static REPORTED: Mutex<i32> = Mutex::new(0);
+
+/// Bits of `--detailed-exit-codes`, one per class of finding; `2` stays the
+/// bit of unreadable files and failed runs.
+pub const EXIT_OTHER: i32 = 1;
+pub const EXIT_DUPLICATES: i32 = 4;
+pub const EXIT_MISSING: i32 = 8;
+pub const EXIT_FORMAT: i32 = 16;
+
+fn exit_bit(rule: &str) -> i32 {
+    match rule {
+        "duplicate-key" => EXIT_DUPLICATES,
+        "missing-key" | "missing-file" => EXIT_MISSING,
+        "format" => EXIT_FORMAT,
+        _ => EXIT_OTHER,
+    }
+}
+
+/// The exit code a run ending with `code` exits with: 0 for findings under
+/// `--exit-zero`, and with `--detailed-exit-codes` the bits of what was
+/// reported, so `1` becomes e.g. `12` for duplicate and missing keys and
+/// `2` keeps its bit next to them.
+pub fn exit_code(code: i32) -> i32 {
+    let (zero, detailed) = EXIT.get().copied().unwrap_or((false, false));
+    if code == 0 || (zero && code == 1) {
+        return 0;
+    }
+    if !detailed {
+        return code;
+    }
+    let reported = *REPORTED.lock().unwrap();
+    match code {
+        1 if reported != 0 => reported,
+        2 => 2 | reported,
+        _ => code,
+    }
+}
+
+pub fn init(m: &ArgMatches) {
+    let verbosity = if m.get_flag("quiet") {
+        -1
+    } else {
+        m.get_count("verbose").min(2) as i8
+    };
+    let format = match m.get_one::<String>("format").map(String::as_str) {
+        Some("codeclimate") => Format::CodeClimate,
+        Some("csv") => Format::Csv,
+        _ => Format::Text,
+    };
+    let tty = std::io::stdout().is_terminal();
+    let color =
+        !m.get_flag("no_color") && std::env::var_os("NO_COLOR").is_none_or(|v| v.is_empty()) && tty;
+    let progress =
+        tty && std::io::stderr().is_terminal() && verbosity >= 0 && format == Format::Text;
+    let _ = STYLE.set(Style {
+        verbosity,
+        color,
+        progress,
+        format,
+        summary: m.get_flag("summary"),
+    });
+    let _ = EXIT.set((m.get_flag("exit_zero"), m.get_flag("detailed_exit_codes")));
+}
+
+pub fn style() -> &'static Style {
+    STYLE.get_or_init(|| Style {
+        verbosity: 0,
+        color: false,
+        progress: false,
+        format: Format::Text,
+        summary: false,
+    })
+}
+
+pub fn verbosity() -> i8 {
+    style().verbosity
+}
+
+const RED: &str = "31";
+const YELLOW: &str = "33";
+const GREEN: &str = "32";
+
+fn paint(s: &str, code: &str) -> String {
+    if style().color {
+        format!("\x1b[{}m{}\x1b[0m", code, s)
+    } else {
+        s.to_string()
+    }
+}
+
+pub fn severity(s: Severity) -> String {
+    match s {
+        Severity::Error => paint(s.as_str(), RED),
+        Severity::Warning => paint(s.as_str(), YELLOW),
+    }
+}
+
+/// Starts a progress bar over `len` files, if enabled and worthwhile.
+pub fn progress_start(len: usize) {
+    if !style().progress || len < 2 {
+        return;
+    }
+    let bar = ProgressBar::new(len as u64);
+    if let Ok(s) = ProgressStyle::with_template("{bar:30} {pos}/{len} {wide_msg}") {
+        bar.set_style(s);
+    }
+    *PROGRESS.lock().unwrap() = Some(bar);
+}
+
+/// Shows `path` as the file currently being processed.
+pub fn progress_file(path: &Path) {
+    if let Some(bar) = PROGRESS.lock().unwrap().as_ref() {
+        bar.set_message(path.display().to_string());
+    }
+}
+
+/// Marks the current file as done.
+pub fn progress_inc() {
+    if let Some(bar) = PROGRESS.lock().unwrap().as_ref() {
+        bar.inc(1);
+    }
+}
+
+pub fn progress_finish() {
+    if let Some(bar) = PROGRESS.lock().unwrap().take() {
+        bar.finish_and_clear();
+    }
+}
+
+/// Prints a line on stdout without tearing an active progress bar. Text
+/// output is suppressed entirely for machine-readable formats.
+pub fn line(s: impl Display) {
+    if style().format != Format::Text {
+        return;
+    }
+    match PROGRESS.lock().unwrap().as_ref() {
+        Some(bar) => bar.suspend(|| println!("{}", s)),
+        None => println!("{}", s),
+    }
+}
+
+/// `path: OK`, suppressed with `-q`.
+pub fn ok(path: &Path) {
+    if verbosity() >= 0 {
+        line(format!("{}: {}", path.display(), paint("OK", GREEN)));
+    }
+}
+
+/// `path: LABEL` for a file-level problem with nothing to list.
+pub fn flag(path: &Path, label: &str) {
+    line(format!("{}: {}", path.display(), paint(label, RED)));
+}
+
+/// `path: LABEL:` introducing a list of `count` problems in that file; with
+/// `--summary` the count stands in for the list.
+pub fn header(path: &Path, label: &str, count: usize) {
+    if style().summary {
+        line(format!(
+            "{}: {}: {}",
+            path.display(),
+            paint(label, RED),
+            count
+        ));
+    } else {
+        line(format!("{}: {}:", path.display(), paint(label, RED)));
+    }
+}
+
+/// A per-key line under a `header`, suppressed with `--summary`.
+pub fn detail(s: impl Display) {
+    if !style().summary {
+        line(s);
+    }
+}
+
+/// A per-key line under an `info` line, suppressed with `-q` like it.
+pub fn info_detail(s: impl Display) {
+    if verbosity() >= 0 {
+        detail(s);
+    }
+}
+
+/// A line of a key-level diff under a `header`: `+` added, `-` removed,
+/// `~` changed, `>` moved.
+pub fn change(sign: char, s: impl Display) {
+    let code = match sign {
+        '+' => GREEN,
+        '-' => RED,
+        _ => YELLOW,
+    };
+    detail(paint(&format!("  {} {}", sign, s), code));
+}
+
+/// `path: ERROR: message` on stderr.
+pub fn error(path: &Path, e: impl Display) {
+    let msg = format!("{}: {}: {}", path.display(), paint("ERROR", RED), e);
+    match PROGRESS.lock().unwrap().as_ref() {
+        Some(bar) => bar.suspend(|| eprintln!("{}", msg)),
+        None => eprintln!("{}", msg),
+    }
+}
+
+/// Informational progress such as "Sorted x.json", suppressed with `-q`.
+pub fn info(msg: impl Display) {
+    if verbosity() >= 0 {
+        line(msg);
+    }
+}
+
+/// With `-vv`, how long processing `path` took.
+pub fn timing(path: &Path, start: Instant) {
+    if verbosity() >= 2 {
+        line(format!(
+            "{}: took {:.1} ms",
+            path.display(),
+            start.elapsed().as_secs_f64() * 1000.0
+        ));
+    }
+}
+
+/// 1-based line of each key path in `path`, from the raw text.
+fn key_lines(path: &Path) -> HashMap<String, usize> {
+    let Ok(text) = std::fs::read_to_string(path) else {
+        return HashMap::new();
+    };
+    let mut out = HashMap::new();
+    for lit in scan::literals(&text) {
+        if lit.is_key {
+            out.entry(lit.path)
+                .or_insert_with(|| scan::line_col(&text, lit.offset).0);
+        }
+    }
+    out
+}
+
+/// Stable 64-bit FNV-1a hash, hex encoded.
+fn fingerprint(parts: &[&str]) -> String {
+    let mut h: u64 = 0xcbf29ce484222325;
+    for p in parts {
+        for b in p.bytes().chain([0]) {
+            h ^= b as u64;
+            h = h.wrapping_mul(0x100000001b3);
+        }
+    }
+    format!("{:016x}", h)
+}
+
+fn codeclimate(findings: &[Finding]) -> serde_json::Value {
+    let mut lines: HashMap<&Path, HashMap<String, usize>> = HashMap::new();
+    let issues: Vec<_> = findings
+        .iter()
+        .map(|f| {
+            let file_lines = lines
+                .entry(f.file.as_path())
+                .or_insert_with(|| key_lines(&f.file));
+            let line = file_lines.get(&f.key).copied().unwrap_or(1);
+            let path = f.file.to_string_lossy();
+            json!({
+                "type": "issue",
+                "check_name": f.rule,
+                "description": format!("{}: {}", f.key, f.message),
+                "categories": ["Style"],
+                "severity": match f.severity {
+                    Severity::Error => "major",
+                    Severity::Warning => "minor",
+                },
+                "fingerprint": fingerprint(&[&path, f.rule, &f.key, &f.message]),
+                "location": { "path": path, "lines": { "begin": line } },
+            })
+        })
+        .collect();
+    serde_json::Value::Array(issues)
+}
+
+pub fn csv_field(s: &str) -> String {
+    if s.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", s.replace('"', "\"\""))
+    } else {
+        s.to_string()
+    }
+}
+
+fn csv(findings: &[Finding]) -> String {
+    let mut out = String::from("file,locale,rule,key,severity,message\n");
+    for f in findings {
+        let row = [
+            f.file.to_string_lossy().as_ref(),
+            &f.locale,
+            f.rule,
+            &f.key,
+            f.severity.as_str(),
+            &f.message,
+        ]
+        .map(csv_field)
+        .join(",");
+        out.push_str(&row);
+        out.push('\n');
+    }
+    out
+}
+
+/// With `--summary`, a table of findings per rule.
+fn totals(findings: &[Finding]) {
+    if !style().summary {
+        return;
+    }
+    // rule -> (files, errors, warnings)
+    let mut by_rule: Vec<(&str, Vec<&Path>, usize, usize)> = Vec::new();
+    for f in findings {
+        let row = match by_rule.iter().position(|r| r.0 == f.rule) {
+            Some(i) => &mut by_rule[i],
+            None => {
+                by_rule.push((f.rule, Vec::new(), 0, 0));
+                by_rule.last_mut().unwrap()
+            }
+        };
+        if !row.1.contains(&f.file.as_path()) {
+            row.1.push(&f.file);
+        }
+        match f.severity {
+            Severity::Error => row.2 += 1,
+            Severity::Warning => row.3 += 1,
+        }
+    }
+    let width = by_rule.iter().map(|r| r.0.len()).max().unwrap_or(0).max(5);
+    line("");
+    line(format!(
+        "{:<width$}  {:>6}  {:>7}  {:>8}",
+        "rule", "files", "errors", "warnings"
+    ));
+    for (rule, files, errors, warnings) in &by_rule {
+        line(format!(
+            "{:<width$}  {:>6}  {:>7}  {:>8}",
+            rule,
+            files.len(),
+            errors,
+            warnings
+        ));
+    }
+    let errors: usize = by_rule.iter().map(|r| r.2).sum();
+    let warnings: usize = by_rule.iter().map(|r| r.3).sum();
+    line(format!(
+        "{:<width$}  {:>6}  {:>7}  {:>8}",
+        "total", "", errors, warnings
+    ));
+}
+
+/// Writes the collected findings in the selected machine-readable format;
+/// for text output, which is printed as files are processed, only the
+/// `--summary` totals remain.
+pub fn emit(findings: &[Finding]) {
+    crate::notify::record(findings);
+    *REPORTED.lock().unwrap() |= findings.iter().fold(0, |bits, f| bits | exit_bit(f.rule));
+    match style().format {
+        Format::Text => totals(findings),
+        Format::CodeClimate => println!(
+            "{}",
+            serde_json::to_string_pretty(&codeclimate(findings)).unwrap_or_default()
+        ),
+        Format::Csv => print!("{}", csv(findings)),
+    }
+}
+
+/// Number of distinct files with at least one finding.
+pub fn files_with(findings: &[Finding]) -> usize {
+    let mut files: Vec<&Path> = findings.iter().map(|f| f.file.as_path()).collect();
+    files.sort();
+    files.dedup();
+    files.len()
+}
+
+/// The closing one-line summary of a text run, e.g. `Checked 13 files: 241
+/// missing keys across 12 locales, 0 unreadable files`; also sent to the
+/// `--webhook`.
+pub fn headline(checked: usize, problems: &str, unreadable: usize) {
+    crate::notify::send(checked, problems, unreadable);
+    if verbosity() < 0 {
+        return;
+    }
+    line(format!(
+        "Checked {} files: {}, {} unreadable files",
+        checked, problems, unreadable
+    ));
+}
@@ -0,0 +1,72 @@
+use super::{Entry, Hit, language_of};
+
+/// Languages whose menus mark the accelerator as `文件(&F)`, since the
+/// letter is not part of the text.
+const PAREN_FORM: &[&str] = &["zh", "ja", "ko"];
+
+/// One `&X` accelerator marker: its byte offset and whether it is written
+/// as a `(&X)` mnemonic.
+struct Marker {
+    offset: usize,
+    paren: bool,
+}
+
+/// The accelerator markers of `s`. `&&` is a literal ampersand, and
+/// `&amp;`-style entities are not markers.
+fn markers(s: &str) -> Vec<Marker> {
+    let mut out = Vec::new();
+    let mut i = 0;
+    while let Some(at) = s[i..].find('&').map(|a| a + i) {
+        let rest = &s[at + 1..];
+        if rest.starts_with('&') {
+            i = at + 2;
+            continue;
+        }
+        i = at + 1;
+        let Some(c) = rest.chars().next().filter(|c| c.is_alphanumeric()) else {
+            continue;
+        };
+        let word = rest
+            .find(|c: char| !(c.is_ascii_alphanumeric() || c == '#'))
+            .unwrap_or(rest.len());
+        if word > 0 && rest[word..].starts_with(';') {
+            continue;
+        }
+        out.push(Marker {
+            offset: at,
+            paren: s[..at].ends_with('(') && rest[c.len_utf8()..].starts_with(')'),
+        });
+    }
+    out
+}
+
+pub fn check(e: &Entry) -> Vec<Hit> {
+    let Some(base) = e.base else {
+        return Vec::new();
+    };
+    if markers(base).is_empty() {
+        return Vec::new();
+    }
+    let got = markers(e.value);
+    let mut hits = Vec::new();
+    match got.len() {
+        0 => hits.push(Hit::warning(
+            "base has an & accelerator but translation has none",
+        )),
+        1 => {}
+        n => hits.push(Hit::warning(format!(
+            "{} & accelerators (a menu item has exactly one)",
+            n
+        ))),
+    }
+    let paren_form = PAREN_FORM.contains(&language_of(e.locale).as_str());
+    if paren_form && let Some(m) = got.iter().find(|m| !m.paren) {
+        let letter: String = e.value[m.offset + 1..].chars().take(1).collect();
+        hits.push(Hit::warning(format!(
+            "&{} inside the text; {} menus use the (&X) form",
+            letter,
+            language_of(e.locale)
+        )));
+    }
+    hits
+}
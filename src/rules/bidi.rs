@@ -0,0 +1,107 @@
+use super::{Entry, Hit, language_of, placeholders};
+
+const RTL_LANGUAGES: &[&str] = &["ar", "fa", "he", "ur", "ps", "yi", "ckb", "dv", "sd", "ug"];
+
+const LRM: char = '\u{200E}';
+const RLM: char = '\u{200F}';
+const ALM: char = '\u{061C}';
+const EMBEDDINGS: &[char] = &['\u{202A}', '\u{202B}', '\u{202D}', '\u{202E}'];
+const PDF: char = '\u{202C}';
+const ISOLATES: &[char] = &['\u{2066}', '\u{2067}', '\u{2068}'];
+const PDI: char = '\u{2069}';
+
+pub fn is_rtl(locale: &str) -> bool {
+    RTL_LANGUAGES.contains(&language_of(locale).as_str())
+}
+
+fn is_rtl_char(c: char) -> bool {
+    matches!(c as u32, 0x0590..=0x08FF | 0xFB1D..=0xFDFF | 0xFE70..=0xFEFF)
+}
+
+fn is_bidi_control(c: char) -> bool {
+    matches!(c, LRM | RLM | ALM | PDF | PDI) || EMBEDDINGS.contains(&c) || ISOLATES.contains(&c)
+}
+
+pub fn check(e: &Entry) -> Vec<Hit> {
+    let mut hits = Vec::new();
+    if !is_rtl(e.locale) {
+        for (i, c) in e.value.char_indices() {
+            if is_bidi_control(c) {
+                hits.push(Hit::warning(format!(
+                    "bidi control U+{:04X} at {} in a left-to-right locale (remove it)",
+                    c as u32, i
+                )));
+            }
+        }
+        return hits;
+    }
+
+    let chars: Vec<(usize, char)> = e.value.char_indices().collect();
+    for (n, &(i, c)) in chars.iter().enumerate() {
+        if EMBEDDINGS.contains(&c) {
+            hits.push(Hit::warning(format!(
+                "embedding/override U+{:04X} at {} (use an isolate U+2066..U+2068 instead)",
+                c as u32, i
+            )));
+        } else if matches!(c, LRM | RLM | ALM) {
+            let at_edge = n == 0 || n == chars.len() - 1;
+            let doubled = n > 0 && matches!(chars[n - 1].1, LRM | RLM | ALM);
+            if at_edge || doubled {
+                hits.push(Hit::warning(format!(
+                    "stray mark U+{:04X} at {} (remove it)",
+                    c as u32, i
+                )));
+            }
+        }
+    }
+
+    let mut embed_depth = 0i32;
+    let mut isolate_depth = 0i32;
+    for &(i, c) in &chars {
+        if EMBEDDINGS.contains(&c) {
+            embed_depth += 1;
+        } else if c == PDF {
+            embed_depth -= 1;
+        } else if ISOLATES.contains(&c) {
+            isolate_depth += 1;
+        } else if c == PDI {
+            isolate_depth -= 1;
+        }
+        if embed_depth < 0 || isolate_depth < 0 {
+            hits.push(Hit::error(format!(
+                "U+{:04X} at {} closes nothing (remove it)",
+                c as u32, i
+            )));
+            embed_depth = embed_depth.max(0);
+            isolate_depth = isolate_depth.max(0);
+        }
+    }
+    if isolate_depth > 0 {
+        hits.push(Hit::error(format!(
+            "{} unterminated isolate(s) (append U+2069 POP DIRECTIONAL ISOLATE)",
+            isolate_depth
+        )));
+    }
+    if embed_depth > 0 {
+        hits.push(Hit::error(format!(
+            "{} unterminated embedding(s) (append U+202C POP DIRECTIONAL FORMATTING)",
+            embed_depth
+        )));
+    }
+
+    if e.value.chars().any(is_rtl_char) {
+        for (start, end) in placeholders(e.value) {
+            let before = e.value[..start].chars().next_back();
+            let after = e.value[end..].chars().next();
+            let isolated = before.is_some_and(|c| ISOLATES.contains(&c)) && after == Some(PDI);
+            if !isolated {
+                hits.push(Hit::warning(format!(
+                    "placeholder {} is not isolated (wrap it as U+2068{}U+2069)",
+                    &e.value[start..end],
+                    &e.value[start..end]
+                )));
+            }
+        }
+    }
+    hits
+}
@@ -0,0 +1,76 @@
+use super::{Entry, Hit, language_of, protected_ranges};
+use crate::config::Casing;
+
+/// Words Title Case leaves lowercase unless they start the string.
+const MINOR_WORDS: &[&str] = &[
+    "a", "an", "and", "as", "at", "but", "by", "for", "from", "in", "nor", "of", "on", "or", "the",
+    "to", "via", "vs", "with",
+];
+
+/// The words of `s` outside placeholders and markup, stripped of the
+/// punctuation around them.
+fn words(s: &str) -> Vec<&str> {
+    let mut protected = protected_ranges(s);
+    protected.sort_unstable();
+    let mut out = Vec::new();
+    let mut from = 0;
+    for (start, end) in protected.into_iter().chain([(s.len(), s.len())]) {
+        if start < from {
+            from = from.max(end);
+            continue;
+        }
+        out.extend(
+            s[from..start]
+                .split_whitespace()
+                .map(|w| w.trim_matches(|c: char| !c.is_alphanumeric()))
+                .filter(|w| !w.is_empty()),
+        );
+        from = end;
+    }
+    out
+}
+
+/// Whether `word` starts with a lowercase letter and has no uppercase one,
+/// so brands like `iOS` or `macOS` are left alone.
+fn starts_lower(word: &str) -> bool {
+    word.chars().next().is_some_and(char::is_lowercase) && !word.chars().any(char::is_uppercase)
+}
+
+/// Whether `word` is capitalized like an ordinary word (`Profile`), as
+/// opposed to an acronym or a brand like `DNS` or `iOS`.
+fn capitalized(word: &str) -> bool {
+    let mut chars = word.chars();
+    chars.next().is_some_and(char::is_uppercase) && chars.all(|c| !c.is_uppercase())
+}
+
+pub fn check(e: &Entry) -> Vec<Hit> {
+    let Some(casing) = e.config.casing_for(e.locale, &language_of(e.locale), e.key) else {
+        return Vec::new();
+    };
+    let words = words(e.value);
+    let Some(first) = words.first() else {
+        return Vec::new();
+    };
+    if starts_lower(first) {
+        return vec![Hit::warning(format!("{:?} should be capitalized", first))];
+    }
+    match casing {
+        Casing::Title => words[1..]
+            .iter()
+            .filter(|w| starts_lower(w))
+            .filter(|w| !MINOR_WORDS.contains(&w.to_lowercase().as_str()))
+            .map(|w| Hit::warning(format!("{:?} should be capitalized in Title Case", w)))
+            .collect(),
+        Casing::Sentence => {
+            let rest: Vec<&&str> = words[1..]
+                .iter()
+                .filter(|w| !MINOR_WORDS.contains(&w.to_lowercase().as_str()))
+                .collect();
+            if !rest.is_empty() && rest.iter().all(|w| capitalized(w)) {
+                vec![Hit::warning("in Title Case, expected sentence case")]
+            } else {
+                Vec::new()
+            }
+        }
+    }
+}
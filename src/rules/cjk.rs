@@ -0,0 +1,123 @@
+use super::{Entry, Hit, is_protected, language_of, protected_ranges};
+use crate::config::Spacing;
+
+fn is_cjk(c: char) -> bool {
+    matches!(c as u32,
+        0x3040..=0x30FF | 0x3400..=0x4DBF | 0x4E00..=0x9FFF | 0xAC00..=0xD7AF | 0xF900..=0xFAFF)
+}
+
+fn is_latin(c: char) -> bool {
+    c.is_ascii_alphanumeric()
+}
+
+fn fullwidth(c: char, language: &str) -> Option<char> {
+    Some(match c {
+        ',' if language == "ja" => '、',
+        ',' => '，',
+        '.' => '。',
+        ':' => '：',
+        ';' => '；',
+        '!' => '！',
+        '?' => '？',
+        '(' => '（',
+        ')' => '）',
+        _ => return None,
+    })
+}
+
+/// A mechanical edit: replace `start..end` with `with`.
+struct Edit {
+    start: usize,
+    end: usize,
+    with: String,
+    message: String,
+}
+
+fn edits(e: &Entry) -> Vec<Edit> {
+    let language = language_of(e.locale);
+    let Some(style) = e.config.cjk_for(e.locale, &language) else {
+        return Vec::new();
+    };
+    let ranges = protected_ranges(e.value);
+    let chars: Vec<(usize, char)> = e.value.char_indices().collect();
+    let prev = |n: usize| n.checked_sub(1).map(|p| chars[p].1);
+    let next = |n: usize| chars.get(n + 1).map(|&(_, c)| c);
+    let mut out = Vec::new();
+    for (n, &(i, c)) in chars.iter().enumerate() {
+        if is_protected(&ranges, i) {
+            continue;
+        }
+        if style.fullwidth
+            && let Some(wide) = fullwidth(c, &language)
+        {
+            let after_cjk = prev(n).is_some_and(is_cjk);
+            let before_cjk = next(n).is_some_and(is_cjk)
+                || (next(n) == Some(' ') && chars.get(n + 2).is_some_and(|&(_, c)| is_cjk(c)));
+            let applies = match c {
+                '(' => before_cjk,
+                ')' => after_cjk,
+                // Leave `...` and decimals alone.
+                '.' => after_cjk && next(n) != Some('.'),
+                _ => after_cjk,
+            };
+            if applies {
+                let end = if next(n) == Some(' ') { i + 2 } else { i + 1 };
+                out.push(Edit {
+                    start: i,
+                    end,
+                    with: wide.to_string(),
+                    message: format!("half-width {} at {} (use {})", c, i, wide),
+                });
+                continue;
+            }
+        }
+        let Some(p) = prev(n) else {
+            continue;
+        };
+        if is_protected(&ranges, chars[n - 1].0) {
+            continue;
+        }
+        let boundary = (is_cjk(p) && is_latin(c)) || (is_latin(p) && is_cjk(c));
+        if boundary && style.spacing == Spacing::Require {
+            out.push(Edit {
+                start: i,
+                end: i,
+                with: " ".to_string(),
+                message: format!("missing space between CJK and Latin text at {}", i),
+            });
+        }
+        if style.spacing == Spacing::Forbid && c == ' ' {
+            let after = next(n);
+            let spaced = (is_cjk(p) && after.is_some_and(is_latin))
+                || (is_latin(p) && after.is_some_and(is_cjk));
+            if spaced {
+                out.push(Edit {
+                    start: i,
+                    end: i + 1,
+                    with: String::new(),
+                    message: format!("space between CJK and Latin text at {}", i),
+                });
+            }
+        }
+    }
+    out
+}
+
+pub fn check(e: &Entry) -> Vec<Hit> {
+    edits(e)
+        .into_iter()
+        .map(|ed| Hit::warning(ed.message))
+        .collect()
+}
+
+pub fn fix(e: &Entry) -> Option<String> {
+    let edits = edits(e);
+    if edits.is_empty() {
+        return None;
+    }
+    let mut out = e.value.to_string();
+    for ed in edits.iter().rev() {
+        out.replace_range(ed.start..ed.end, &ed.with);
+    }
+    Some(out)
+}
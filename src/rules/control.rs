@@ -0,0 +1,31 @@
+use super::{Entry, Hit};
+
+const C0_NAMES: [&str; 32] = [
+    "NUL", "SOH", "STX", "ETX", "EOT", "ENQ", "ACK", "BEL", "BS", "HT", "LF", "VT", "FF", "CR",
+    "SO", "SI", "DLE", "DC1", "DC2", "DC3", "DC4", "NAK", "SYN", "ETB", "CAN", "EM", "SUB", "ESC",
+    "FS", "GS", "RS", "US",
+];
+
+fn control_name(c: char) -> &'static str {
+    match c as u32 {
+        n @ 0..=0x1F => C0_NAMES[n as usize],
+        0x7F => "DEL",
+        _ => "C1 CONTROL",
+    }
+}
+
+pub fn check(e: &Entry) -> Vec<Hit> {
+    e.value
+        .char_indices()
+        .filter(|&(_, c)| c.is_control() && c != '\n' && c != '\t')
+        .map(|(i, c)| {
+            Hit::error(format!(
+                "control character U+{:04X} {} at {} in {}",
+                c as u32,
+                control_name(c),
+                i,
+                e.locale
+            ))
+        })
+        .collect()
+}
@@ -0,0 +1,67 @@
+use super::{Entry, Hit, language_of};
+use regex::Regex;
+use std::sync::LazyLock;
+
+/// dayjs tokens, longest first so `YYYY` is not read as two `YY`s.
+const TOKEN: &str =
+    "(?:YYYY|YY|Q|MMMM|MMM|MM|M|Do|DD|D|dddd|ddd|dd|d|HH|H|hh|h|kk|k|mm|m|ss|s|SSS|A|a|ZZ|Z|X|x)";
+
+/// Two or more dayjs tokens joined by separators (`YYYY-MM-DD`, `h:mm A`).
+static DAYJS: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(&format!(
+        r"(?:^|[^\p{{L}}])({t}(?:[-/.:, ]+{t})+)(?:$|[^\p{{L}}])",
+        t = TOKEN
+    ))
+    .unwrap()
+});
+
+/// ICU date and time arguments (`{val, date, short}`).
+static ICU: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"\{\s*\w+\s*,\s*(?:date|time)\s*(?:,[^{}]*)?\}").unwrap());
+
+/// The date patterns of `s`: ICU arguments without their spaces, and dayjs
+/// patterns with at least one token a word would not be mistaken for.
+fn patterns(s: &str) -> Vec<String> {
+    let icu = ICU
+        .find_iter(s)
+        .map(|m| m.as_str().split_whitespace().collect());
+    let dayjs = DAYJS
+        .captures_iter(s)
+        .map(|c| c[1].trim_end_matches([',', ' ', '.']).to_string())
+        .filter(|p| {
+            ["YY", "MM", "DD", "HH", "hh", "mm", "ss"]
+                .iter()
+                .any(|t| p.contains(t))
+        });
+    icu.chain(dayjs).collect()
+}
+
+pub fn check(e: &Entry) -> Vec<Hit> {
+    let Some(base) = e.base else {
+        return Vec::new();
+    };
+    let want = patterns(base);
+    let got = patterns(e.value);
+    let allowed = e.config.date_formats_for(e.locale, &language_of(e.locale));
+    // A locale's own pattern stands in for a dayjs pattern of the base;
+    // ICU arguments are never localized.
+    let localized = allowed.iter().any(|a| e.value.contains(a.as_str()));
+    let mut hits = Vec::new();
+    for p in &want {
+        if !got.contains(p) && (!localized || p.starts_with('{')) {
+            hits.push(Hit::error(format!(
+                "date format {} from the base is missing or changed",
+                p
+            )));
+        }
+    }
+    for p in &got {
+        if !want.contains(p) && !allowed.iter().any(|a| p.contains(a.as_str())) {
+            hits.push(Hit::error(format!(
+                "date format {} is not in the base (allow it under date-formats)",
+                p
+            )));
+        }
+    }
+    hits
+}
@@ -0,0 +1,46 @@
+use super::{Entry, Hit, language_of};
+
+fn ends_with_ellipsis(s: &str) -> bool {
+    let s = s.trim_end();
+    s.ends_with("...") || s.ends_with('…')
+}
+
+fn style(e: &Entry) -> Option<String> {
+    e.config
+        .punctuation_for(e.locale, &language_of(e.locale))
+        .and_then(|p| p.ellipsis)
+}
+
+pub fn check(e: &Entry) -> Vec<Hit> {
+    let mut hits = Vec::new();
+    if let Some(base) = e.base {
+        match (ends_with_ellipsis(base), ends_with_ellipsis(e.value)) {
+            (true, false) => hits.push(Hit::warning(
+                "base ends with an ellipsis but translation does not",
+            )),
+            (false, true) => hits.push(Hit::warning(
+                "translation ends with an ellipsis but base does not",
+            )),
+            _ => {}
+        }
+    }
+    match style(e).as_deref() {
+        Some("...") if e.value.contains('…') => {
+            hits.push(Hit::warning("… (U+2026) instead of three dots"));
+        }
+        Some(s) if s != "..." && e.value.contains("...") => {
+            hits.push(Hit::warning(format!("three dots instead of {}", s)));
+        }
+        _ => {}
+    }
+    hits
+}
+
+pub fn fix(e: &Entry) -> Option<String> {
+    let out = match style(e).as_deref() {
+        Some("...") => e.value.replace('…', "..."),
+        Some(s) => e.value.replace("...", s),
+        None => return None,
+    };
+    (out != e.value).then_some(out)
+}
@@ -0,0 +1,8 @@
+use super::{Entry, Hit};
+
+pub fn check(e: &Entry) -> Vec<Hit> {
+    if !e.value.trim().is_empty() || e.base.is_some_and(|b| b.trim().is_empty()) {
+        return Vec::new();
+    }
+    vec![Hit::warning("empty translation")]
+}
@@ -0,0 +1,232 @@
+use super::{Hit, Severity, is_invisible};
+use crate::config::Config;
+use crate::scan::{self, line_col};
+use std::sync::OnceLock;
+
+/// What the configured style escapes: `/` (`fmt.escape-slash`), and
+/// whether escapes of printable non-ASCII characters are needless
+/// (`unescape-unicode`, unless `fmt.escape-unicode` asks for them).
+struct Style {
+    slash: bool,
+    unescape: bool,
+}
+
+/// Set once by the command line from its config; [`crate::api`] never sets
+/// it, so the library, WebAssembly and napi checks escape as by default.
+static STYLE: OnceLock<Style> = OnceLock::new();
+
+pub fn init(config: &Config) {
+    let _ = STYLE.set(Style {
+        slash: config.fmt.escape_slash,
+        unescape: config.unescape_unicode && !config.fmt.escape_unicode,
+    });
+}
+
+fn escapes_slash() -> bool {
+    STYLE.get().is_some_and(|s| s.slash)
+}
+
+fn unescape() -> bool {
+    STYLE.get().is_some_and(|s| s.unescape)
+}
+
+/// Suspicious escapes in one literal: byte offset of the backslash within
+/// the file, the escape text, severity, message and (for the unambiguous
+/// cases) its replacement.
+struct Escape {
+    offset: usize,
+    len: usize,
+    severity: Severity,
+    message: String,
+    fix: Option<String>,
+}
+
+fn escapes_in(lit: &scan::Literal) -> Vec<Escape> {
+    let raw = lit.raw.as_bytes();
+    let mut out = Vec::new();
+    let mut i = 0;
+    while i < raw.len() {
+        if raw[i] != b'\\' {
+            i += 1;
+            continue;
+        }
+        let Some(&next) = raw.get(i + 1) else {
+            break;
+        };
+        let offset = lit.offset + i;
+        match next {
+            b'/' if escapes_slash() => {}
+            b'/' => out.push(Escape {
+                offset,
+                len: 2,
+                severity: Severity::Warning,
+                message: "unnecessary escape \\/".to_string(),
+                fix: Some("/".to_string()),
+            }),
+            b'\\' => {
+                if let Some(&c) = raw.get(i + 2).filter(|c| c.is_ascii_alphabetic()) {
+                    let hint = if b"nrt".contains(&c) {
+                        format!(" (did you mean \\{}?)", c as char)
+                    } else {
+                        String::new()
+                    };
+                    out.push(Escape {
+                        offset,
+                        len: 2,
+                        severity: Severity::Warning,
+                        message: format!("literal backslash before '{}'{}", c as char, hint),
+                        fix: None,
+                    });
+                }
+            }
+            b'u' => {
+                let Some(unit) = hex_at(raw, i + 2) else {
+                    out.push(Escape {
+                        offset,
+                        len: 2,
+                        severity: Severity::Error,
+                        message: "invalid \\u escape (needs four hex digits)".to_string(),
+                        fix: None,
+                    });
+                    i += 2;
+                    continue;
+                };
+                let len = match unit {
+                    0xD800..=0xDBFF => match low_surrogate(raw, i + 6) {
+                        Some(_) => 12,
+                        None => {
+                            out.push(Escape {
+                                offset,
+                                len: 6,
+                                severity: Severity::Error,
+                                message: format!(
+                                    "unpaired high surrogate {} (needs a \\uDC00-\\uDFFF after it)",
+                                    &lit.raw[i..i + 6]
+                                ),
+                                fix: None,
+                            });
+                            6
+                        }
+                    },
+                    0xDC00..=0xDFFF => {
+                        out.push(Escape {
+                            offset,
+                            len: 6,
+                            severity: Severity::Error,
+                            message: format!(
+                                "unpaired low surrogate {} (needs a \\uD800-\\uDBFF before it)",
+                                &lit.raw[i..i + 6]
+                            ),
+                            fix: None,
+                        });
+                        6
+                    }
+                    _ => 6,
+                };
+                if let Some(esc) = needless(&lit.raw[i..i + len], offset) {
+                    out.push(esc);
+                }
+                i += len;
+                continue;
+            }
+            b'"' | b'b' | b'f' | b'n' | b'r' | b't' => {}
+            b'\'' => out.push(Escape {
+                offset,
+                len: 2,
+                severity: Severity::Error,
+                message: "invalid escape \\' (JSON strings do not escape ')".to_string(),
+                fix: Some("'".to_string()),
+            }),
+            c => out.push(Escape {
+                offset,
+                len: 1 + lit.raw[i + 1..].chars().next().map_or(1, char::len_utf8),
+                severity: Severity::Error,
+                message: format!(
+                    "invalid escape \\{}",
+                    lit.raw[i + 1..].chars().next().unwrap_or(c as char)
+                ),
+                fix: None,
+            }),
+        }
+        i += 2;
+    }
+    out
+}
+
+/// The code unit of the four hex digits at `at`.
+fn hex_at(raw: &[u8], at: usize) -> Option<u32> {
+    let hex = raw.get(at..at + 4)?;
+    if !hex.iter().all(u8::is_ascii_hexdigit) {
+        return None;
+    }
+    u32::from_str_radix(std::str::from_utf8(hex).ok()?, 16).ok()
+}
+
+/// The low surrogate escaped at `at`, completing a pair.
+fn low_surrogate(raw: &[u8], at: usize) -> Option<u32> {
+    if raw.get(at..at + 2) != Some(b"\\u") {
+        return None;
+    }
+    hex_at(raw, at + 2).filter(|u| (0xDC00..=0xDFFF).contains(u))
+}
+
+/// `escaped` (one `\uXXXX`, or a surrogate pair) when it could be written
+/// as the character itself: always for printable ASCII, and for other
+/// visible characters with `unescape-unicode`. Quotes, backslashes, control
+/// characters, whitespace and invisible characters stay escaped, and so do
+/// `<`, `>` and `&`, which HTML-safe encoders escape on purpose.
+fn needless(escaped: &str, offset: usize) -> Option<Escape> {
+    let decoded: String = serde_json::from_str(&format!("\"{}\"", escaped)).ok()?;
+    let c = decoded.chars().next()?;
+    let printable = if c.is_ascii() {
+        c == ' ' || c.is_ascii_graphic()
+    } else {
+        unescape() && !c.is_control() && !c.is_whitespace() && !is_invisible(c)
+    };
+    if !printable || matches!(c, '"' | '\\' | '<' | '>' | '&') {
+        return None;
+    }
+    Some(Escape {
+        offset,
+        len: escaped.len(),
+        severity: Severity::Warning,
+        message: format!("needless escape {} for '{}'", escaped, c),
+        fix: Some(decoded),
+    })
+}
+
+pub fn check(text: &str) -> Vec<(String, Hit)> {
+    let mut out = Vec::new();
+    for lit in scan::literals(text) {
+        for esc in escapes_in(&lit) {
+            let (line, col) = line_col(text, esc.offset);
+            let place = if lit.is_key { "key name" } else { "value" };
+            let message = format!(
+                "{} in {} at line {}, column {}",
+                esc.message, place, line, col
+            );
+            let hit = match esc.severity {
+                Severity::Warning => Hit::warning(message),
+                Severity::Error => Hit::error(message),
+            };
+            out.push((lit.path.clone(), hit));
+        }
+    }
+    out
+}
+
+pub fn fix(text: &str) -> Option<String> {
+    let mut edits: Vec<Escape> = scan::literals(text)
+        .flat_map(|lit| escapes_in(&lit))
+        .filter(|e| e.fix.is_some())
+        .collect();
+    if edits.is_empty() {
+        return None;
+    }
+    edits.sort_by_key(|e| std::cmp::Reverse(e.offset));
+    let mut out = text.to_string();
+    for e in edits {
+        out.replace_range(e.offset..e.offset + e.len, &e.fix.unwrap_or_default());
+    }
+    Some(out)
+}
@@ -0,0 +1,56 @@
+use super::{Entry, Hit};
+
+const ZWNJ: char = '\u{200C}';
+const ZWJ: char = '\u{200D}';
+
+pub fn invisible_name(c: char) -> Option<&'static str> {
+    Some(match c {
+        '\u{00AD}' => "SOFT HYPHEN",
+        '\u{180E}' => "MONGOLIAN VOWEL SEPARATOR",
+        '\u{200B}' => "ZERO WIDTH SPACE",
+        ZWNJ => "ZERO WIDTH NON-JOINER",
+        ZWJ => "ZERO WIDTH JOINER",
+        '\u{2060}' => "WORD JOINER",
+        '\u{2061}'..='\u{2064}' => "INVISIBLE OPERATOR",
+        '\u{FEFF}' => "ZERO WIDTH NO-BREAK SPACE",
+        '\u{115F}' | '\u{1160}' | '\u{3164}' | '\u{FFA0}' => "HANGUL FILLER",
+        '\u{E0000}'..='\u{E007F}' => "TAG CHARACTER",
+        _ => return None,
+    })
+}
+
+// Scripts where ZWNJ/ZWJ shape joining and are legitimately typed.
+fn needs_joiners(c: char) -> bool {
+    matches!(c as u32,
+        0x0600..=0x06FF | 0x0750..=0x077F | 0x08A0..=0x08FF | 0xFB50..=0xFDFF | 0xFE70..=0xFEFF
+        | 0x0900..=0x0DFF | 0x0F00..=0x0FFF | 0x1000..=0x109F | 0x1780..=0x17FF)
+}
+
+fn is_emoji(c: char) -> bool {
+    matches!(c as u32, 0x2600..=0x27BF | 0x1F000..=0x1FAFF)
+}
+
+pub fn check(e: &Entry) -> Vec<Hit> {
+    let chars: Vec<(usize, char)> = e.value.char_indices().collect();
+    let mut hits = Vec::new();
+    for (n, &(i, c)) in chars.iter().enumerate() {
+        let Some(name) = invisible_name(c) else {
+            continue;
+        };
+        if c == ZWNJ || c == ZWJ {
+            let prev = n.checked_sub(1).map(|p| chars[p].1);
+            let next = chars.get(n + 1).map(|&(_, c)| c);
+            let joined_script = prev.is_some_and(needs_joiners) || next.is_some_and(needs_joiners);
+            let emoji_sequence =
+                c == ZWJ && prev.is_some_and(is_emoji) && next.is_some_and(is_emoji);
+            if joined_script || emoji_sequence {
+                continue;
+            }
+        }
+        hits.push(Hit::warning(format!(
+            "invisible U+{:04X} {} at {} (remove it)",
+            c as u32, name, i
+        )));
+    }
+    hits
+}
@@ -0,0 +1,98 @@
+use super::{Hit, KeyedHits};
+use crate::config::Config;
+use serde_json::{Map, Value};
+
+fn child_key(prefix: &str, k: &str) -> String {
+    if prefix.is_empty() {
+        k.to_string()
+    } else {
+        format!("{}.{}", prefix, k)
+    }
+}
+
+fn walk(prefix: &str, v: &Value, out: &mut KeyedHits) {
+    let Value::Object(map) = v else {
+        return;
+    };
+    for (k, child) in map {
+        let key = child_key(prefix, k);
+        let trimmed = k.trim();
+        if trimmed != k {
+            let message = if map.contains_key(trimmed) {
+                format!(
+                    "key {:?} has surrounding whitespace; {:?} also exists",
+                    k, trimmed
+                )
+            } else {
+                format!("key {:?} has surrounding whitespace", k)
+            };
+            out.push((key.clone(), Hit::error(message)));
+        }
+        walk(&key, child, out);
+    }
+}
+
+/// Keys like `" mode"` or `"mode "`, which the code never looks up.
+pub fn check(doc: &Value, _base: Option<&Value>) -> KeyedHits {
+    let mut out = Vec::new();
+    walk("", doc, &mut out);
+    out
+}
+
+/// Folds `spaced` into `clean`: objects key by key, and a string only over
+/// an empty one, so a translation under the clean key is never lost.
+fn merge(clean: &mut Value, spaced: Value) {
+    match (clean, spaced) {
+        (Value::Object(clean), Value::Object(spaced)) => {
+            for (k, v) in spaced {
+                match clean.get_mut(&k) {
+                    Some(c) => merge(c, v),
+                    None => {
+                        clean.insert(k, v);
+                    }
+                }
+            }
+        }
+        (clean @ Value::String(_), spaced @ Value::String(_))
+            if clean.as_str().is_some_and(|s| s.trim().is_empty()) =>
+        {
+            *clean = spaced;
+        }
+        _ => {}
+    }
+}
+
+fn fix_object(prefix: &str, map: &mut Map<String, Value>, changed: &mut Vec<String>) {
+    let spaced: Vec<String> = map
+        .keys()
+        .filter(|k| k.trim() != k.as_str())
+        .cloned()
+        .collect();
+    for k in spaced {
+        let at = map.keys().position(|m| *m == k).unwrap();
+        let v = map.shift_remove(&k).unwrap();
+        let trimmed = k.trim();
+        match map.get_mut(trimmed) {
+            Some(clean) => merge(clean, v),
+            None => {
+                map.shift_insert(at, trimmed.to_string(), v);
+            }
+        }
+        changed.push(child_key(prefix, &k));
+    }
+    for (k, child) in map.iter_mut() {
+        if let Value::Object(inner) = child {
+            fix_object(&child_key(prefix, k), inner, changed);
+        }
+    }
+}
+
+/// Trims the keys, merging each into the clean-named key when the object
+/// already has one.
+pub fn fix(doc: &mut Value, _config: &Config) -> Vec<String> {
+    let mut changed = Vec::new();
+    if let Value::Object(map) = doc {
+        fix_object("", map, &mut changed);
+    }
+    changed
+}
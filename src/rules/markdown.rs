@@ -0,0 +1,66 @@
+use super::{Entry, Hit};
+
+/// Targets of inline `[text](target)` links.
+fn link_targets(s: &str) -> Vec<&str> {
+    let mut out = Vec::new();
+    let mut from = 0;
+    while let Some(i) = s[from..].find("](").map(|i| i + from) {
+        match s[i + 2..].find(')') {
+            Some(len) => {
+                out.push(&s[i + 2..i + 2 + len]);
+                from = i + 2 + len;
+            }
+            None => break,
+        }
+    }
+    out
+}
+
+fn balanced(s: &str, open: char, close: char) -> bool {
+    let mut depth = 0i32;
+    for c in s.chars() {
+        if c == open {
+            depth += 1;
+        } else if c == close {
+            depth -= 1;
+            if depth < 0 {
+                return false;
+            }
+        }
+    }
+    depth == 0
+}
+
+pub fn check(e: &Entry) -> Vec<Hit> {
+    let Some(base) = e.base else {
+        return Vec::new();
+    };
+    let mut hits = Vec::new();
+    let targets = link_targets(e.value);
+    for t in link_targets(base) {
+        if !targets.contains(&t) {
+            hits.push(Hit::error(format!(
+                "link target ({}) is missing or changed",
+                t
+            )));
+        }
+    }
+    for (open, close) in [('[', ']'), ('(', ')')] {
+        if balanced(base, open, close) && !balanced(e.value, open, close) {
+            hits.push(Hit::error(format!("unbalanced {}{}", open, close)));
+        }
+    }
+    for marker in ["**", "__", "`"] {
+        let want = base.matches(marker).count();
+        let got = e.value.matches(marker).count();
+        if want.is_multiple_of(2) && !got.is_multiple_of(2) {
+            hits.push(Hit::warning(format!("unpaired {} emphasis marker", marker)));
+        } else if want != got && want > 0 {
+            hits.push(Hit::warning(format!(
+                "{} {} markers in base but {} in translation",
+                want, marker, got
+            )));
+        }
+    }
+    hits
+}
@@ -0,0 +1,28 @@
+use super::{Entry, Hit};
+
+/// Markers looked for when the config sets none.
+const DEFAULT_MARKERS: &[&str] = &["TODO", "FIXME", "XXX", "__MISSING__"];
+
+/// Whether `marker` occurs in `s` as a word of its own, so `TODOS` or
+/// `XXXL` do not count.
+fn contains_word(s: &str, marker: &str) -> bool {
+    let is_word = |c: Option<char>| c.is_some_and(|c| c.is_alphanumeric());
+    s.match_indices(marker).any(|(i, m)| {
+        !is_word(s[..i].chars().next_back()) && !is_word(s[i + m.len()..].chars().next())
+    })
+}
+
+pub fn check(e: &Entry) -> Vec<Hit> {
+    let configured = e.config.markers.as_deref();
+    let markers: Vec<&str> = match configured {
+        Some(list) => list.iter().map(String::as_str).collect(),
+        None => DEFAULT_MARKERS.to_vec(),
+    };
+    markers
+        .into_iter()
+        .filter(|m| !m.is_empty() && contains_word(e.value, m))
+        // The base may legitimately say "TODO list".
+        .filter(|m| !e.base.is_some_and(|b| contains_word(b, m)))
+        .map(|m| Hit::error(format!("contains the marker {}", m)))
+        .collect()
+}
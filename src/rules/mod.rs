@@ -0,0 +1,587 @@
+mod accelerators;
+mod bidi;
+mod casing;
+mod cjk;
+mod control;
+mod dates;
+mod ellipsis;
+mod empty;
+mod escapes;
+mod invisible;
+mod key_whitespace;
+mod markdown;
+mod markers;
+mod newlines;
+mod null;
+mod numbers;
+mod placeholders;
+mod printf;
+mod punctuation;
+mod repeats;
+mod trailing;
+mod types;
+mod urls;
+mod whitespace;
+
+use crate::config::Config;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub enum Severity {
+    Warning,
+    Error,
+}
+
+impl Severity {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Severity::Warning => "warning",
+            Severity::Error => "error",
+        }
+    }
+}
+
+/// A single translation string being checked, together with the base
+/// language string for the same key (absent when checking the base file
+/// itself or when the key is missing from the base).
+pub struct Entry<'a> {
+    pub locale: &'a str,
+    /// Dotted key path of the value.
+    pub key: &'a str,
+    pub value: &'a str,
+    pub base: Option<&'a str>,
+    pub config: &'a Config,
+}
+
+/// What a check reports for one entry; the runner attaches file, locale,
+/// rule and key.
+pub struct Hit {
+    pub severity: Severity,
+    pub message: String,
+}
+
+impl Hit {
+    pub fn warning(message: impl Into<String>) -> Self {
+        Hit {
+            severity: Severity::Warning,
+            message: message.into(),
+        }
+    }
+
+    pub fn error(message: impl Into<String>) -> Self {
+        Hit {
+            severity: Severity::Error,
+            message: message.into(),
+        }
+    }
+}
+
+/// Hits with the key path each belongs to.
+pub type KeyedHits = Vec<(String, Hit)>;
+
+pub enum Check {
+    /// Runs on every string value of a parsed file.
+    Value(fn(&Entry) -> Vec<Hit>),
+    /// Runs on the raw file text, before (and regardless of) parsing;
+    /// reports the key path each hit belongs to.
+    Text(fn(&str) -> KeyedHits),
+    /// Runs once on a parsed file with its base, for problems that are not
+    /// in string values; reports the key path each hit belongs to.
+    Doc(fn(&Value, Option<&Value>) -> KeyedHits),
+}
+
+/// Rewrites its input so that the rule's check no longer reports the
+/// mechanical cases; `None` when there is nothing to change.
+pub enum Fix {
+    Value(fn(&Entry) -> Option<String>),
+    Text(fn(&str) -> Option<String>),
+    /// Rewrites a parsed file as a whole, returning the keys it changed.
+    Doc(fn(&mut Value, &Config) -> Vec<String>),
+}
+
+pub struct Rule {
+    pub id: &'static str,
+    /// The most severe level the rule reports.
+    pub severity: Severity,
+    /// One line for `rules`.
+    pub description: &'static str,
+    pub check: Check,
+    pub fix: Option<Fix>,
+}
+
+pub struct Finding {
+    pub file: PathBuf,
+    pub locale: String,
+    pub rule: &'static str,
+    pub key: String,
+    pub severity: Severity,
+    pub message: String,
+    /// The offending value, when the check ran on a parsed string.
+    pub value: Option<String>,
+}
+
+impl Finding {
+    pub fn new(
+        file: &Path,
+        rule: &'static str,
+        key: String,
+        severity: Severity,
+        message: String,
+    ) -> Self {
+        Finding {
+            file: file.to_path_buf(),
+            locale: locale_of(file),
+            rule,
+            key,
+            severity,
+            message,
+            value: None,
+        }
+    }
+}
+
+pub const RULES: &[Rule] = &[
+    Rule {
+        id: "bidi",
+        severity: Severity::Error,
+        description: "Stray or unbalanced bidi controls and unisolated placeholders in RTL locales",
+        check: Check::Value(bidi::check),
+        fix: None,
+    },
+    Rule {
+        id: "invisible",
+        severity: Severity::Warning,
+        description: "Zero-width and other invisible characters",
+        check: Check::Value(invisible::check),
+        fix: None,
+    },
+    Rule {
+        id: "control",
+        severity: Severity::Error,
+        description: "Raw control characters other than newline and tab",
+        check: Check::Value(control::check),
+        fix: None,
+    },
+    Rule {
+        id: "punctuation",
+        severity: Severity::Warning,
+        description: "Quotation marks and apostrophes in the locale's style",
+        check: Check::Value(punctuation::check),
+        fix: Some(Fix::Value(punctuation::fix)),
+    },
+    Rule {
+        id: "cjk",
+        severity: Severity::Warning,
+        description: "Full-width punctuation and CJK/Latin spacing",
+        check: Check::Value(cjk::check),
+        fix: Some(Fix::Value(cjk::fix)),
+    },
+    Rule {
+        id: "ellipsis",
+        severity: Severity::Warning,
+        description: "Ellipsis kept from the base and spelled in the locale's style",
+        check: Check::Value(ellipsis::check),
+        fix: Some(Fix::Value(ellipsis::fix)),
+    },
+    Rule {
+        id: "casing",
+        severity: Severity::Warning,
+        description: "Title or sentence case as configured for the key's prefix",
+        check: Check::Value(casing::check),
+        fix: None,
+    },
+    Rule {
+        id: "printf",
+        severity: Severity::Error,
+        description: "printf-style specifiers match the base",
+        check: Check::Value(printf::check),
+        fix: None,
+    },
+    Rule {
+        id: "markdown",
+        severity: Severity::Error,
+        description: "Markdown links, brackets and emphasis survive translation",
+        check: Check::Value(markdown::check),
+        fix: None,
+    },
+    Rule {
+        id: "newlines",
+        severity: Severity::Warning,
+        description: "Line break count matches the base",
+        check: Check::Value(newlines::check),
+        fix: None,
+    },
+    Rule {
+        id: "placeholders",
+        severity: Severity::Error,
+        description: "{{placeholder}} interpolations match the base",
+        check: Check::Value(placeholders::check),
+        fix: None,
+    },
+    Rule {
+        id: "numbers",
+        severity: Severity::Warning,
+        description: "Unit placeholders losing their unit, hard-coded native digits and changed numbers",
+        check: Check::Value(numbers::check),
+        fix: None,
+    },
+    Rule {
+        id: "date-format",
+        severity: Severity::Error,
+        description: "dayjs and ICU date/time patterns changed in translation",
+        check: Check::Value(dates::check),
+        fix: None,
+    },
+    Rule {
+        id: "urls",
+        severity: Severity::Error,
+        description: "URLs of the base missing, truncated or altered in translation",
+        check: Check::Value(urls::check),
+        fix: None,
+    },
+    Rule {
+        id: "accelerators",
+        severity: Severity::Warning,
+        description: "& accelerator markers lost or doubled, or not (&X) in CJK locales",
+        check: Check::Value(accelerators::check),
+        fix: None,
+    },
+    Rule {
+        id: "repeated-punctuation",
+        severity: Severity::Warning,
+        description: "Doubled punctuation such as ?? or ,, left over from editing",
+        check: Check::Value(repeats::check),
+        fix: Some(Fix::Value(repeats::fix)),
+    },
+    Rule {
+        id: "whitespace",
+        severity: Severity::Warning,
+        description: "Leading or trailing whitespace differing from the base",
+        check: Check::Value(whitespace::check),
+        fix: Some(Fix::Value(whitespace::fix)),
+    },
+    Rule {
+        id: "empty",
+        severity: Severity::Warning,
+        description: "Empty translations",
+        check: Check::Value(empty::check),
+        fix: None,
+    },
+    Rule {
+        id: "markers",
+        severity: Severity::Error,
+        description: "TODO, FIXME and other placeholder markers left in values",
+        check: Check::Value(markers::check),
+        fix: None,
+    },
+    Rule {
+        id: "non-string",
+        severity: Severity::Error,
+        description: "Numbers, booleans, arrays or objects where the base has a string",
+        check: Check::Doc(types::check),
+        fix: None,
+    },
+    Rule {
+        id: "null",
+        severity: Severity::Warning,
+        description: "null values, which make i18next fall back",
+        check: Check::Doc(null::check),
+        fix: Some(Fix::Doc(null::fix)),
+    },
+    Rule {
+        id: "key-whitespace",
+        severity: Severity::Error,
+        description: "Keys with leading or trailing whitespace",
+        check: Check::Doc(key_whitespace::check),
+        fix: Some(Fix::Doc(key_whitespace::fix)),
+    },
+    Rule {
+        id: "trailing-variant",
+        severity: Severity::Warning,
+        description: "Base strings differing only by a trailing colon, period or ellipsis",
+        check: Check::Doc(trailing::check),
+        fix: None,
+    },
+    Rule {
+        id: "escapes",
+        severity: Severity::Error,
+        description: "Invalid or needless escape sequences and unpaired surrogates in the raw file",
+        check: Check::Text(escapes::check),
+        fix: Some(Fix::Text(escapes::fix)),
+    },
+];
+
+pub fn find_rule(id: &str) -> Option<&'static Rule> {
+    RULES.iter().find(|r| r.id == id)
+}
+
+/// Locale code of a locale file (see [`crate::layout::locale_of`]).
+pub fn locale_of(path: &Path) -> String {
+    crate::layout::locale_of(path)
+}
+
+/// Reads the settings of rules that have no config parameter.
+pub fn init(config: &Config) {
+    escapes::init(config);
+}
+
+/// Whether `c` renders as nothing: zero-width characters, soft hyphens,
+/// fillers and tags.
+pub fn is_invisible(c: char) -> bool {
+    invisible::invisible_name(c).is_some()
+}
+
+/// Primary language subtag of a locale code (`zh` for `zh-CN`).
+pub fn language_of(locale: &str) -> String {
+    locale
+        .split(['-', '_'])
+        .next()
+        .unwrap_or("")
+        .to_ascii_lowercase()
+}
+
+fn collect_strings<'a>(prefix: &str, v: &'a Value, out: &mut Vec<(String, &'a str)>) {
+    match v {
+        Value::String(s) => out.push((prefix.to_string(), s)),
+        Value::Object(map) => {
+            for (k, child) in map {
+                let key = if prefix.is_empty() {
+                    k.clone()
+                } else {
+                    format!("{}.{}", prefix, k)
+                };
+                collect_strings(&key, child, out);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// All string leaves of a locale document, keyed by dotted path.
+pub fn string_entries(v: &Value) -> Vec<(String, &str)> {
+    let mut out = Vec::new();
+    collect_strings("", v, &mut out);
+    out
+}
+
+/// Byte ranges of `{{placeholder}}` interpolations in `s`.
+pub fn placeholders(s: &str) -> Vec<(usize, usize)> {
+    let mut out = Vec::new();
+    let mut from = 0;
+    while let Some(start) = s[from..].find("{{").map(|i| i + from) {
+        match s[start + 2..].find("}}") {
+            Some(len) => {
+                let end = start + 2 + len + 2;
+                out.push((start, end));
+                from = end;
+            }
+            None => break,
+        }
+    }
+    out
+}
+
+/// Byte ranges of markup tags and placeholders, whose contents must not be
+/// treated as prose.
+pub fn protected_ranges(s: &str) -> Vec<(usize, usize)> {
+    let mut out = placeholders(s);
+    let mut from = 0;
+    while let Some(start) = s[from..].find('<').map(|i| i + from) {
+        match s[start..].find('>') {
+            Some(len) => {
+                out.push((start, start + len + 1));
+                from = start + len + 1;
+            }
+            None => break,
+        }
+    }
+    out
+}
+
+pub fn is_protected(ranges: &[(usize, usize)], i: usize) -> bool {
+    ranges.iter().any(|&(s, e)| i >= s && i < e)
+}
+
+/// Runs `rules` over every string in `doc`, and over `doc` as a whole,
+/// comparing against `base` when given.
+pub fn run_rules(
+    rules: &[&Rule],
+    file: &Path,
+    doc: &Value,
+    base: Option<&Value>,
+    config: &Config,
+) -> Vec<Finding> {
+    let locale = locale_of(file);
+    let base_entries: HashMap<String, &str> = base
+        .map(|b| string_entries(b).into_iter().collect())
+        .unwrap_or_default();
+    let mut out = Vec::new();
+    for (key, value) in string_entries(doc) {
+        let entry = Entry {
+            locale: &locale,
+            key: &key,
+            value,
+            base: base_entries.get(&key).copied(),
+            config,
+        };
+        for rule in rules {
+            let Check::Value(check) = rule.check else {
+                continue;
+            };
+            for hit in check(&entry) {
+                out.push(Finding {
+                    value: Some(value.to_string()),
+                    ..Finding::new(file, rule.id, key.clone(), hit.severity, hit.message)
+                });
+            }
+        }
+    }
+    for rule in rules {
+        if let Check::Doc(check) = rule.check {
+            for (key, hit) in check(doc, base) {
+                out.push(Finding::new(file, rule.id, key, hit.severity, hit.message));
+            }
+        }
+    }
+    out
+}
+
+fn fix_strings(
+    rules: &[&Rule],
+    locale: &str,
+    key: &str,
+    v: &mut Value,
+    base: &HashMap<String, &str>,
+    config: &Config,
+    changed: &mut Vec<String>,
+) {
+    match v {
+        Value::String(s) => {
+            let mut any = false;
+            for rule in rules {
+                let Some(Fix::Value(fix)) = rule.fix else {
+                    continue;
+                };
+                let entry = Entry {
+                    locale,
+                    key,
+                    value: s,
+                    base: base.get(key).copied(),
+                    config,
+                };
+                if let Some(fixed) = fix(&entry) {
+                    *s = fixed;
+                    any = true;
+                }
+            }
+            if any {
+                changed.push(key.to_string());
+            }
+        }
+        Value::Object(map) => {
+            for (k, child) in map.iter_mut() {
+                let key = if key.is_empty() {
+                    k.clone()
+                } else {
+                    format!("{}.{}", key, k)
+                };
+                fix_strings(rules, locale, &key, child, base, config, changed);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Applies the fixes of `rules` to every string in `doc`, returning the keys
+/// whose values changed.
+pub fn apply_fixes(
+    rules: &[&Rule],
+    file: &Path,
+    doc: &mut Value,
+    base: Option<&Value>,
+    config: &Config,
+) -> Vec<String> {
+    let base_entries: HashMap<String, &str> = base
+        .map(|b| string_entries(b).into_iter().collect())
+        .unwrap_or_default();
+    let mut changed = Vec::new();
+    for rule in rules {
+        if let Some(Fix::Doc(fix)) = rule.fix {
+            changed.extend(fix(doc, config));
+        }
+    }
+    fix_strings(
+        rules,
+        &locale_of(file),
+        "",
+        doc,
+        &base_entries,
+        config,
+        &mut changed,
+    );
+    changed
+}
+
+/// Runs `rules` over one locale file, whose source is `text`: text checks on
+/// the raw source, then value checks on the parsed document. With `fix`,
+/// fixable problems are rewritten first and the file is saved. Returns the findings and what was
+/// fixed (changed keys, or `[rule]` for raw-text fixes); a parse failure is
+/// an error only after text findings have been collected.
+pub fn lint_file(
+    rules: &[&Rule],
+    path: &Path,
+    text: &str,
+    base: Option<&Value>,
+    config: &Config,
+    fix: bool,
+) -> (Vec<Finding>, Vec<String>, Option<String>) {
+    let mut text = text.to_string();
+    let mut fixed = Vec::new();
+    if fix {
+        for rule in rules {
+            if let Some(Fix::Text(f)) = rule.fix
+                && let Some(t) = f(&text)
+            {
+                text = t;
+                fixed.push(format!("[{}]", rule.id));
+            }
+        }
+    }
+    let mut findings = Vec::new();
+    for rule in rules {
+        if let Check::Text(check) = rule.check {
+            for (key, hit) in check(&text) {
+                findings.push(Finding::new(path, rule.id, key, hit.severity, hit.message));
+            }
+        }
+    }
+    let mut doc: Value = match serde_json::from_str(&text) {
+        Ok(v) => v,
+        Err(e) => {
+            // Keep the text fixes even though the file still does not parse.
+            if !fixed.is_empty()
+                && let Err(e) = crate::write::write_file(path, &text)
+            {
+                return (findings, Vec::new(), Some(e));
+            }
+            return (
+                findings,
+                fixed,
+                Some(format!("parse {}: {}", path.display(), e)),
+            );
+        }
+    };
+    if fix {
+        fixed.extend(apply_fixes(rules, path, &mut doc, base, config));
+    }
+    if !fixed.is_empty() {
+        let written = crate::write::write_file(path, &crate::json::to_string(&doc, &config.fmt));
+        if let Err(e) = written {
+            return (findings, Vec::new(), Some(e));
+        }
+    }
+    findings.extend(run_rules(rules, path, &doc, base, config));
+    (findings, fixed, None)
+}
@@ -0,0 +1,16 @@
+use super::{Entry, Hit};
+
+pub fn check(e: &Entry) -> Vec<Hit> {
+    let Some(base) = e.base else {
+        return Vec::new();
+    };
+    let want = base.matches('\n').count();
+    let got = e.value.matches('\n').count();
+    if want.abs_diff(got) <= e.config.newline_tolerance {
+        return Vec::new();
+    }
+    vec![Hit::warning(format!(
+        "{} line break(s) in base but {} in translation",
+        want, got
+    ))]
+}
@@ -0,0 +1,66 @@
+use super::{Hit, KeyedHits};
+use crate::config::{Config, NullFix};
+use serde_json::Value;
+
+fn child_key(prefix: &str, k: &str) -> String {
+    if prefix.is_empty() {
+        k.to_string()
+    } else {
+        format!("{}.{}", prefix, k)
+    }
+}
+
+fn walk(prefix: &str, v: &Value, out: &mut KeyedHits) {
+    let Value::Object(map) = v else {
+        return;
+    };
+    for (k, child) in map {
+        let key = child_key(prefix, k);
+        if child.is_null() {
+            out.push((key, Hit::warning("null value (i18next falls back)")));
+        } else {
+            walk(&key, child, out);
+        }
+    }
+}
+
+/// `null` values, which translators use to mean "TODO" but which make
+/// i18next fall back to another locale or the key.
+pub fn check(doc: &Value, _base: Option<&Value>) -> KeyedHits {
+    let mut out = Vec::new();
+    walk("", doc, &mut out);
+    out
+}
+
+fn fix_walk(prefix: &str, v: &mut Value, how: NullFix, changed: &mut Vec<String>) {
+    let Value::Object(map) = v else {
+        return;
+    };
+    let nulls: Vec<String> = map
+        .iter()
+        .filter(|(_, c)| c.is_null())
+        .map(|(k, _)| k.clone())
+        .collect();
+    for k in nulls {
+        match how {
+            NullFix::Remove => {
+                map.shift_remove(&k);
+            }
+            NullFix::Blank => {
+                map.insert(k.clone(), Value::String(String::new()));
+            }
+        }
+        changed.push(child_key(prefix, &k));
+    }
+    for (k, child) in map.iter_mut() {
+        fix_walk(&child_key(prefix, k), child, how, changed);
+    }
+}
+
+/// Removes `null` values or makes them empty strings, as the config's
+/// `null-fix` says.
+pub fn fix(doc: &mut Value, config: &Config) -> Vec<String> {
+    let mut changed = Vec::new();
+    fix_walk("", doc, config.null_fix, &mut changed);
+    changed
+}
@@ -0,0 +1,128 @@
+use super::{Entry, Hit, placeholders, protected_ranges};
+
+/// Units a number placeholder of the base may be followed by.
+const UNITS: &[&str] = &[
+    "B", "KB", "MB", "GB", "TB", "PB", "kB", "KiB", "MiB", "GiB", "TiB", "B/s", "KB/s", "MB/s",
+    "GB/s", "kB/s", "KiB/s", "MiB/s", "GiB/s", "bps", "Kbps", "kbps", "Mbps", "Gbps", "ms", "s",
+    "min", "h", "%", "px",
+];
+
+/// Decimal digit blocks other than ASCII: their zero and their name.
+const DIGITS: &[(char, &str)] = &[
+    ('\u{0660}', "Arabic-Indic"),
+    ('\u{06F0}', "Persian"),
+    ('\u{0966}', "Devanagari"),
+    ('\u{09E6}', "Bengali"),
+    ('\u{0E50}', "Thai"),
+    ('\u{FF10}', "full-width"),
+];
+
+fn native_digit(c: char) -> Option<(u32, &'static str)> {
+    DIGITS.iter().find_map(|&(zero, name)| {
+        let d = (c as u32).checked_sub(zero as u32)?;
+        (d < 10).then_some((d, name))
+    })
+}
+
+/// What follows the placeholder ending at `end`, past spaces.
+fn after(s: &str, end: usize) -> &str {
+    s[end..].trim_start_matches([' ', '\u{a0}', '\u{202f}'])
+}
+
+fn name(s: &str, (start, end): (usize, usize)) -> &str {
+    s[start + 2..end - 2].trim()
+}
+
+/// Names of the placeholders of `s` followed by a unit, with the unit.
+fn with_units(s: &str) -> Vec<(&str, &str)> {
+    placeholders(s)
+        .into_iter()
+        .filter_map(|(start, end)| {
+            let rest = after(s, end);
+            let len = rest
+                .find(|c: char| !(c.is_alphanumeric() || c == '/' || c == '%'))
+                .unwrap_or(rest.len());
+            let unit = UNITS.iter().find(|u| **u == &rest[..len])?;
+            Some((name(s, (start, end)), *unit))
+        })
+        .collect()
+}
+
+/// The digit runs of `s` outside placeholders and tags, native digits read
+/// as ASCII, so `1,000`, `1.000` and `1 000` all give `1` and `000`.
+fn numbers(s: &str) -> Vec<String> {
+    let protected = protected_ranges(s);
+    let mut out = Vec::new();
+    let mut run = String::new();
+    for (i, c) in s.char_indices() {
+        let digit = if protected.iter().any(|&(a, b)| (a..b).contains(&i)) {
+            None
+        } else if c.is_ascii_digit() {
+            Some(c)
+        } else {
+            native_digit(c).and_then(|(d, _)| char::from_digit(d, 10))
+        };
+        match digit {
+            Some(d) => run.push(d),
+            None if !run.is_empty() => out.push(std::mem::take(&mut run)),
+            None => {}
+        }
+    }
+    if !run.is_empty() {
+        out.push(run);
+    }
+    out
+}
+
+pub fn check(e: &Entry) -> Vec<Hit> {
+    let mut hits = Vec::new();
+    let base = e.base.unwrap_or_default();
+    if let Some((c, name)) = e
+        .value
+        .chars()
+        .find_map(|c| native_digit(c).map(|(_, name)| (c, name)))
+        .filter(|_| !base.chars().any(|c| native_digit(c).is_some()))
+    {
+        hits.push(Hit::warning(format!(
+            "hard-coded {} digit '{}' (numbers are formatted by the app)",
+            name, c
+        )));
+    }
+    if e.base.is_none() {
+        return hits;
+    }
+    for (placeholder, unit) in with_units(base) {
+        let found: Vec<(usize, usize)> = placeholders(e.value)
+            .into_iter()
+            .filter(|&p| name(e.value, p) == placeholder)
+            .collect();
+        let adjacent = found.iter().any(|&(_, end)| {
+            after(e.value, end)
+                .chars()
+                .next()
+                .is_some_and(|c| c.is_alphabetic() || c == '%')
+        });
+        // A missing placeholder is the `placeholders` rule's to report.
+        if !adjacent && !found.is_empty() {
+            hits.push(Hit::warning(format!(
+                "{{{{{}}}}} has no unit after it (the base has {{{{{}}}}} {})",
+                placeholder, placeholder, unit
+            )));
+        }
+    }
+    let want = numbers(base);
+    for n in numbers(e.value) {
+        if !want.contains(&n) {
+            hits.push(Hit::warning(format!(
+                "number {} is not in the base{}",
+                n,
+                if want.is_empty() {
+                    String::new()
+                } else {
+                    format!(" ({})", want.join(", "))
+                }
+            )));
+        }
+    }
+    hits
+}
@@ -0,0 +1,34 @@
+use super::{Entry, Hit, placeholders};
+
+fn names(s: &str) -> Vec<&str> {
+    placeholders(s)
+        .into_iter()
+        .map(|(start, end)| s[start + 2..end - 2].trim())
+        .collect()
+}
+
+pub fn check(e: &Entry) -> Vec<Hit> {
+    let Some(base) = e.base else {
+        return Vec::new();
+    };
+    let want = names(base);
+    let got = names(e.value);
+    let mut hits = Vec::new();
+    for name in &want {
+        if !got.contains(name) {
+            hits.push(Hit::error(format!(
+                "placeholder {{{{{}}}}} from the base is missing",
+                name
+            )));
+        }
+    }
+    for name in &got {
+        if !want.contains(name) {
+            hits.push(Hit::error(format!(
+                "placeholder {{{{{}}}}} is not in the base",
+                name
+            )));
+        }
+    }
+    hits
+}
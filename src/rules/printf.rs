@@ -0,0 +1,71 @@
+use super::{Entry, Hit};
+
+/// Conversion characters of printf-style specifiers in `s`, with `i`
+/// folded into `d`. The space flag is not accepted so that prose such as
+/// "100% done" is not mistaken for `% d`.
+pub fn specifiers(s: &str) -> Vec<char> {
+    let bytes = s.as_bytes();
+    let mut out = Vec::new();
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] != b'%' {
+            i += 1;
+            continue;
+        }
+        let mut j = i + 1;
+        if bytes.get(j) == Some(&b'%') {
+            i = j + 1;
+            continue;
+        }
+        // Optional `n$` position.
+        let digits = bytes[j..].iter().take_while(|b| b.is_ascii_digit()).count();
+        if digits > 0 && bytes.get(j + digits) == Some(&b'$') {
+            j += digits + 1;
+        }
+        while j < bytes.len() && b"-+0#".contains(&bytes[j]) {
+            j += 1;
+        }
+        while j < bytes.len() && (bytes[j].is_ascii_digit() || bytes[j] == b'.' || bytes[j] == b'*')
+        {
+            j += 1;
+        }
+        while j < bytes.len() && b"hlLqjzt".contains(&bytes[j]) {
+            j += 1;
+        }
+        match bytes.get(j) {
+            Some(&c) if b"diouxXeEfFgGaAcsp".contains(&c) => {
+                out.push(if c == b'i' { 'd' } else { c as char });
+                i = j + 1;
+            }
+            _ => i += 1,
+        }
+    }
+    out
+}
+
+pub fn check(e: &Entry) -> Vec<Hit> {
+    let Some(base) = e.base else {
+        return Vec::new();
+    };
+    let mut want = specifiers(base);
+    let mut got = specifiers(e.value);
+    if want.is_empty() && got.is_empty() {
+        return Vec::new();
+    }
+    let fmt = |v: &[char]| {
+        v.iter()
+            .map(|c| format!("%{}", c))
+            .collect::<Vec<_>>()
+            .join(" ")
+    };
+    let described = (fmt(&want), fmt(&got));
+    want.sort_unstable();
+    got.sort_unstable();
+    if want == got {
+        return Vec::new();
+    }
+    vec![Hit::error(format!(
+        "printf specifiers differ from base: expected [{}], found [{}]",
+        described.0, described.1
+    ))]
+}
@@ -0,0 +1,91 @@
+use super::{Entry, Hit, is_protected, language_of, protected_ranges};
+
+const DOUBLE_QUOTES: &[char] = &['"', '“', '”', '„', '«', '»'];
+
+fn single_char(s: &str) -> Option<char> {
+    let mut it = s.chars();
+    it.next().filter(|_| it.next().is_none())
+}
+
+/// Double quotes in the value that are not part of markup or placeholders.
+fn quote_positions(value: &str) -> Vec<(usize, char)> {
+    let ranges = protected_ranges(value);
+    value
+        .char_indices()
+        .filter(|&(i, c)| DOUBLE_QUOTES.contains(&c) && !is_protected(&ranges, i))
+        .collect()
+}
+
+/// Word-internal apostrophes (`don't`, `l’heure`).
+fn apostrophe_positions(value: &str) -> Vec<(usize, char)> {
+    let chars: Vec<(usize, char)> = value.char_indices().collect();
+    let mut out = Vec::new();
+    for n in 1..chars.len().saturating_sub(1) {
+        let c = chars[n].1;
+        if (c == '\'' || c == '’')
+            && chars[n - 1].1.is_alphabetic()
+            && chars[n + 1].1.is_alphabetic()
+        {
+            out.push(chars[n]);
+        }
+    }
+    out
+}
+
+pub fn check(e: &Entry) -> Vec<Hit> {
+    let Some(style) = e.config.punctuation_for(e.locale, &language_of(e.locale)) else {
+        return Vec::new();
+    };
+    let mut hits = Vec::new();
+    if let Some((open, close)) = &style.quotes {
+        let quotes = quote_positions(e.value);
+        for (n, &(i, c)) in quotes.iter().enumerate() {
+            let expected = if n % 2 == 0 { open } else { close };
+            if single_char(expected) != Some(c) {
+                hits.push(Hit::warning(format!(
+                    "quotation mark {} at {} (expected {})",
+                    c, i, expected
+                )));
+            }
+        }
+        if !quotes.len().is_multiple_of(2) {
+            hits.push(Hit::warning("unbalanced quotation marks"));
+        }
+    }
+    if let Some(apostrophe) = &style.apostrophe {
+        for (i, c) in apostrophe_positions(e.value) {
+            if single_char(apostrophe) != Some(c) {
+                hits.push(Hit::warning(format!(
+                    "apostrophe {} at {} (expected {})",
+                    c, i, apostrophe
+                )));
+            }
+        }
+    }
+    hits
+}
+
+pub fn fix(e: &Entry) -> Option<String> {
+    let style = e.config.punctuation_for(e.locale, &language_of(e.locale))?;
+    let mut replacements: Vec<(usize, char, &str)> = Vec::new();
+    if let Some((open, close)) = &style.quotes {
+        let quotes = quote_positions(e.value);
+        // Alternating open/close is only a safe guess when quotes pair up.
+        if quotes.len().is_multiple_of(2) {
+            for (n, &(i, c)) in quotes.iter().enumerate() {
+                replacements.push((i, c, if n % 2 == 0 { open } else { close }));
+            }
+        }
+    }
+    if let Some(apostrophe) = &style.apostrophe {
+        for (i, c) in apostrophe_positions(e.value) {
+            replacements.push((i, c, apostrophe));
+        }
+    }
+    let mut out = e.value.to_string();
+    replacements.sort_by_key(|r| std::cmp::Reverse(r.0));
+    for (i, c, with) in replacements {
+        out.replace_range(i..i + c.len_utf8(), with);
+    }
+    (out != e.value).then_some(out)
+}
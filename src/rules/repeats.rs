@@ -0,0 +1,91 @@
+use super::{Entry, Hit, is_protected, protected_ranges};
+
+/// Patterns looked for when the config sets none. Three dots are an
+/// ellipsis, so `..` only matches runs of two or of four and more.
+const DEFAULT_PATTERNS: &[&str] = &[
+    "??", "!!", ",,", ";;", "..", "？？", "！！", "，，", "。。", "、、", "；；",
+];
+
+/// One occurrence of a pattern: its byte range, and the mark it collapses
+/// to when that is certainly what was meant.
+struct Repeat {
+    start: usize,
+    end: usize,
+    fix: Option<char>,
+}
+
+fn repeats(e: &Entry) -> Vec<Repeat> {
+    let patterns: Vec<&str> = match e.config.repeated_punctuation.as_deref() {
+        Some(list) => list.iter().map(String::as_str).collect(),
+        None => DEFAULT_PATTERNS.to_vec(),
+    };
+    let s = e.value;
+    let protected = protected_ranges(s);
+    // The base may repeat a mark on purpose ("Done!!").
+    let in_base = |r: &str| e.base.is_some_and(|b| b.contains(r));
+    let mut out = Vec::new();
+    for p in patterns {
+        let Some(mark) = p.chars().next() else {
+            continue;
+        };
+        if p.chars().any(|c| c != mark) {
+            for (start, m) in s.match_indices(p) {
+                if !is_protected(&protected, start) && !in_base(m) {
+                    out.push(Repeat {
+                        start,
+                        end: start + m.len(),
+                        fix: None,
+                    });
+                }
+            }
+            continue;
+        }
+        let min = p.chars().count();
+        let mut i = 0;
+        while let Some(start) = s[i..].find(mark).map(|a| a + i) {
+            let len = s[start..].chars().take_while(|&c| c == mark).count();
+            let end = start + len * mark.len_utf8();
+            i = end;
+            if len < min || (mark == '.' && len == 3) {
+                continue;
+            }
+            if is_protected(&protected, start) || in_base(&s[start..end]) {
+                continue;
+            }
+            out.push(Repeat {
+                start,
+                end,
+                // Two dots may be a short ellipsis as much as a stray dot.
+                fix: (mark != '.').then_some(mark),
+            });
+        }
+    }
+    out.sort_by_key(|r| r.start);
+    out.dedup_by_key(|r| r.start);
+    out
+}
+
+pub fn check(e: &Entry) -> Vec<Hit> {
+    repeats(e)
+        .into_iter()
+        .map(|r| Hit::warning(format!("repeated punctuation {}", &e.value[r.start..r.end])))
+        .collect()
+}
+
+pub fn fix(e: &Entry) -> Option<String> {
+    let mut out = String::new();
+    let mut from = 0;
+    for r in repeats(e) {
+        let Some(mark) = r.fix else {
+            continue;
+        };
+        out.push_str(&e.value[from..r.start]);
+        out.push(mark);
+        from = r.end;
+    }
+    if from == 0 {
+        return None;
+    }
+    out.push_str(&e.value[from..]);
+    Some(out)
+}
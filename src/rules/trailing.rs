@@ -0,0 +1,46 @@
+use super::{Hit, KeyedHits, string_entries};
+use serde_json::Value;
+use std::collections::HashMap;
+
+/// `s` without trailing colons, periods and ellipses.
+fn stem(s: &str) -> &str {
+    let mut s = s.trim_end();
+    while let Some(rest) = ["...", "…", ":", "：", ".", "。"]
+        .iter()
+        .find_map(|p| s.strip_suffix(p))
+    {
+        s = rest.trim_end();
+    }
+    s
+}
+
+/// Base strings identical but for a trailing colon, period or ellipsis
+/// (`Port` and `Port:`), each reported against the first of its group.
+/// Runs on base files only: translations follow whatever the base does.
+pub fn check(doc: &Value, base: Option<&Value>) -> KeyedHits {
+    if base.is_some() {
+        return Vec::new();
+    }
+    let mut first: HashMap<&str, (String, &str)> = HashMap::new();
+    let mut out = Vec::new();
+    for (key, value) in string_entries(doc) {
+        let s = stem(value);
+        if s.is_empty() {
+            continue;
+        }
+        match first.get(s) {
+            Some((k, v)) if *v != value => out.push((
+                key,
+                Hit::warning(format!(
+                    "{:?} differs from {} ({:?}) only by trailing punctuation",
+                    value, k, v
+                )),
+            )),
+            Some(_) => {}
+            None => {
+                first.insert(s, (key, value));
+            }
+        }
+    }
+    out
+}
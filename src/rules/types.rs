@@ -0,0 +1,45 @@
+use super::Hit;
+use serde_json::Value;
+
+fn kind(v: &Value) -> &'static str {
+    match v {
+        Value::Null => "null",
+        Value::Bool(_) => "a boolean",
+        Value::Number(_) => "a number",
+        Value::String(_) => "a string",
+        Value::Array(_) => "an array",
+        Value::Object(_) => "an object",
+    }
+}
+
+fn walk(prefix: &str, v: &Value, base: &Value, out: &mut Vec<(String, Hit)>) {
+    let (Value::Object(map), Value::Object(base_map)) = (v, base) else {
+        return;
+    };
+    for (k, child) in map {
+        let key = if prefix.is_empty() {
+            k.clone()
+        } else {
+            format!("{}.{}", prefix, k)
+        };
+        match base_map.get(k) {
+            // null has its own rule.
+            Some(Value::String(_)) if !child.is_string() && !child.is_null() => {
+                let message = format!("{} where the base has a string", kind(child));
+                out.push((key, Hit::error(message)));
+            }
+            Some(b @ Value::Object(_)) => walk(&key, child, b, out),
+            _ => {}
+        }
+    }
+}
+
+/// Numbers, booleans, arrays and objects where the base has a plain string,
+/// which the frontend renders as `[object Object]` or the like.
+pub fn check(doc: &Value, base: Option<&Value>) -> Vec<(String, Hit)> {
+    let mut out = Vec::new();
+    if let Some(base) = base {
+        walk("", doc, base, &mut out);
+    }
+    out
+}
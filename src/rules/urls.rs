@@ -0,0 +1,65 @@
+use super::{Entry, Hit};
+use globset::{Glob, GlobMatcher};
+use regex::Regex;
+use std::sync::LazyLock;
+
+/// Printable ASCII only, so full-width punctuation after a link in CJK
+/// text is not taken for part of it.
+static URL: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r#"https?://[\x21-\x7e&&[^"'<>()\[\]{}]]+"#).unwrap());
+
+/// The URLs of `s`, without punctuation that ends the sentence around them.
+fn urls(s: &str) -> Vec<&str> {
+    URL.find_iter(s)
+        .map(|m| m.as_str().trim_end_matches(['.', ',', ';', ':', '!', '?']))
+        .collect()
+}
+
+/// The index of the `localized-urls` pattern `url` falls under.
+fn localized(url: &str, patterns: &[GlobMatcher]) -> Option<usize> {
+    patterns.iter().position(|g| g.is_match(url))
+}
+
+pub fn check(e: &Entry) -> Vec<Hit> {
+    let Some(base) = e.base else {
+        return Vec::new();
+    };
+    let want = urls(base);
+    let got = urls(e.value);
+    if want.is_empty() && got.is_empty() {
+        return Vec::new();
+    }
+    let patterns: Vec<GlobMatcher> = e
+        .config
+        .localized_urls
+        .iter()
+        .filter_map(|p| Glob::new(p).ok())
+        .map(|g| g.compile_matcher())
+        .collect();
+    let mut hits = Vec::new();
+    for url in &want {
+        if got.contains(url) {
+            continue;
+        }
+        // A localized link replaces the base's under the same pattern.
+        if let Some(i) = localized(url, &patterns)
+            && got.iter().any(|g| localized(g, &patterns) == Some(i))
+        {
+            continue;
+        }
+        let message = match got.iter().find(|g| url.starts_with(**g)) {
+            Some(g) => format!("URL {} from the base is truncated to {}", url, g),
+            None => format!("URL {} from the base is missing or altered", url),
+        };
+        hits.push(Hit::error(message));
+    }
+    for url in &got {
+        let known = want.contains(url)
+            || want.iter().any(|w| w.starts_with(*url))
+            || localized(url, &patterns).is_some();
+        if !known {
+            hits.push(Hit::error(format!("URL {} is not in the base", url)));
+        }
+    }
+    hits
+}
@@ -0,0 +1,34 @@
+use super::{Entry, Hit};
+
+/// `s` split into its leading whitespace, the rest and its trailing
+/// whitespace.
+fn split(s: &str) -> (&str, &str, &str) {
+    let core = s.trim();
+    let start = s.len() - s.trim_start().len();
+    (&s[..start], core, &s[start + core.len()..])
+}
+
+pub fn check(e: &Entry) -> Vec<Hit> {
+    let Some(base) = e.base else {
+        return Vec::new();
+    };
+    let (lead, core, trail) = split(e.value);
+    let (base_lead, _, base_trail) = split(base);
+    // Blank values are the empty rule's.
+    if core.is_empty() || (lead, trail) == (base_lead, base_trail) {
+        return Vec::new();
+    }
+    vec![Hit::warning(format!(
+        "whitespace around the text ({:?}, {:?}) differs from the base ({:?}, {:?})",
+        lead, trail, base_lead, base_trail
+    ))]
+}
+
+/// Gives the value the base's leading and trailing whitespace.
+pub fn fix(e: &Entry) -> Option<String> {
+    let base = e.base?;
+    let (_, core, _) = split(e.value);
+    let (base_lead, _, base_trail) = split(base);
+    let out = format!("{}{}{}", base_lead, core, base_trail);
+    (!core.is_empty() && out != e.value).then_some(out)
+}
@@ -0,0 +1,151 @@
+//! A tolerant tokenizer over raw JSON text. It does not validate; it only
+//! locates string literals and the dotted key path they belong to, so checks
+//! can look at escapes and duplicate keys that `serde_json` hides or rejects.
+
+/// A string literal in the source text.
+pub struct Literal<'a> {
+    /// Dotted key path of the value (or, for keys, of the key itself).
+    pub path: String,
+    /// Byte offset of the literal's content, just after the opening quote.
+    pub offset: usize,
+    /// Content between the quotes, escapes untouched.
+    pub raw: &'a str,
+    pub is_key: bool,
+    /// Which object the literal sits in, numbered in source order, so keys
+    /// of the same object can be told from same-named keys elsewhere.
+    pub object: usize,
+}
+
+enum Frame {
+    Object {
+        id: usize,
+        key: Option<String>,
+        expect_key: bool,
+    },
+    Array {
+        index: usize,
+    },
+}
+
+fn path_of(stack: &[Frame]) -> String {
+    let mut parts = Vec::new();
+    for f in stack {
+        match f {
+            Frame::Object { key: Some(k), .. } => parts.push(k.clone()),
+            Frame::Object { key: None, .. } => {}
+            Frame::Array { index } => parts.push(index.to_string()),
+        }
+    }
+    parts.join(".")
+}
+
+/// Decodes a raw literal, falling back to the raw text when it contains
+/// invalid escapes.
+pub fn unescape(raw: &str) -> String {
+    serde_json::from_str::<String>(&format!("\"{}\"", raw)).unwrap_or_else(|_| raw.to_string())
+}
+
+/// Iterator over the string literals of a text; see [`literals`].
+pub struct Literals<'a> {
+    text: &'a str,
+    i: usize,
+    stack: Vec<Frame>,
+    objects: usize,
+}
+
+/// All string literals in `text`, in source order. Literals are produced
+/// one at a time, so scanning a large file never holds more than the
+/// current key path.
+pub fn literals(text: &str) -> Literals<'_> {
+    Literals {
+        text,
+        i: 0,
+        stack: Vec::new(),
+        objects: 0,
+    }
+}
+
+impl<'a> Iterator for Literals<'a> {
+    type Item = Literal<'a>;
+
+    fn next(&mut self) -> Option<Literal<'a>> {
+        let bytes = self.text.as_bytes();
+        let stack = &mut self.stack;
+        while self.i < bytes.len() {
+            let i = self.i;
+            self.i += 1;
+            match bytes[i] {
+                b'{' => {
+                    self.objects += 1;
+                    stack.push(Frame::Object {
+                        id: self.objects,
+                        key: None,
+                        expect_key: true,
+                    });
+                }
+                b'[' => stack.push(Frame::Array { index: 0 }),
+                b'}' | b']' => {
+                    stack.pop();
+                }
+                b':' => {
+                    if let Some(Frame::Object { expect_key, .. }) = stack.last_mut() {
+                        *expect_key = false;
+                    }
+                }
+                b',' => match stack.last_mut() {
+                    Some(Frame::Object { expect_key, .. }) => *expect_key = true,
+                    Some(Frame::Array { index }) => *index += 1,
+                    None => {}
+                },
+                b'"' => {
+                    let start = i + 1;
+                    let mut j = start;
+                    while j < bytes.len() && bytes[j] != b'"' {
+                        j += if bytes[j] == b'\\' { 2 } else { 1 };
+                    }
+                    let end = j.min(bytes.len());
+                    let raw = &self.text[start..end];
+                    let is_key = matches!(
+                        stack.last(),
+                        Some(Frame::Object {
+                            expect_key: true,
+                            ..
+                        })
+                    );
+                    if is_key && let Some(Frame::Object { key, .. }) = stack.last_mut() {
+                        *key = Some(unescape(raw));
+                    }
+                    let object = stack
+                        .iter()
+                        .rev()
+                        .find_map(|f| match f {
+                            Frame::Object { id, .. } => Some(*id),
+                            Frame::Array { .. } => None,
+                        })
+                        .unwrap_or(0);
+                    self.i = end + 1;
+                    return Some(Literal {
+                        path: path_of(stack),
+                        offset: start,
+                        raw,
+                        is_key,
+                        object,
+                    });
+                }
+                _ => {}
+            }
+        }
+        None
+    }
+}
+
+/// 1-based line and column of byte `offset` in `text`.
+pub fn line_col(text: &str, offset: usize) -> (usize, usize) {
+    let before = &text[..offset];
+    let line = before.matches('\n').count() + 1;
+    let col = before[before.rfind('\n').map_or(0, |n| n + 1)..]
+        .chars()
+        .count()
+        + 1;
+    (line, col)
+}
@@ -0,0 +1,95 @@
+//! `--schema`: validating the shape of every locale file against a JSON
+//! Schema, e.g. that values are only strings or objects of strings.
+
+use crate::locales::LocaleSet;
+use crate::report::{self, Thresholds};
+use crate::rules::{Finding, Severity};
+use jsonschema::Validator;
+use serde_json::Value;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Instant;
+
+pub fn load(path: &Path) -> Result<Validator, String> {
+    let text = fs::read_to_string(path).map_err(|e| format!("read {}: {}", path.display(), e))?;
+    let schema: Value =
+        serde_json::from_str(&text).map_err(|e| format!("parse {}: {}", path.display(), e))?;
+    jsonschema::validator_for(&schema).map_err(|e| format!("{}: {}", path.display(), e))
+}
+
+/// A JSON pointer as the dotted key path the other checks report.
+fn dotted(pointer: &str) -> String {
+    pointer
+        .split('/')
+        .skip(1)
+        .map(|s| s.replace("~1", "/").replace("~0", "~"))
+        .collect::<Vec<_>>()
+        .join(".")
+}
+
+/// Where `doc`, the content of `path`, breaks the schema.
+pub fn findings(validator: &Validator, path: &Path, doc: &Value) -> Vec<Finding> {
+    validator
+        .iter_errors(doc)
+        .map(|e| {
+            Finding::new(
+                path,
+                "schema",
+                dotted(e.instance_path().as_str()),
+                Severity::Error,
+                e.to_string(),
+            )
+        })
+        .collect()
+}
+
+pub fn run(
+    files: Vec<PathBuf>,
+    schema: &Path,
+    locale_set: &mut LocaleSet,
+    thresholds: &Thresholds,
+) -> i32 {
+    let validator = match load(schema) {
+        Ok(v) => v,
+        Err(e) => {
+            eprintln!("Invalid schema: {}", e);
+            return 2;
+        }
+    };
+    let mut unreadable = 0;
+    let mut all = Vec::new();
+    let checked = files.len();
+    report::progress_start(files.len());
+    for p in files {
+        let start = Instant::now();
+        report::progress_file(&p);
+        match locale_set.value(&p) {
+            Ok(doc) => {
+                let found = findings(&validator, &p, &doc);
+                crate::print_findings(&p, "SCHEMA VIOLATIONS", &found);
+                all.extend(found);
+            }
+            Err(e) => {
+                unreadable += 1;
+                report::error(&p, e);
+            }
+        }
+        report::timing(&p, start);
+        report::progress_inc();
+    }
+    report::progress_finish();
+    report::emit(&all);
+    report::headline(
+        checked,
+        &format!(
+            "{} schema violations in {} files",
+            all.len(),
+            report::files_with(&all)
+        ),
+        unreadable,
+    );
+    if unreadable > 0 {
+        return 2;
+    }
+    thresholds.exit_code(all.len(), 0)
+}
@@ -0,0 +1,120 @@
+//! The frontend source tree that uses the locale keys.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+const SOURCE_EXTENSIONS: &[&str] = &["ts", "tsx", "js", "jsx", "mjs", "cjs", "vue", "svelte"];
+
+fn walk(dir: &Path, out: &mut Vec<PathBuf>) {
+    let Ok(read) = fs::read_dir(dir) else {
+        return;
+    };
+    for p in read.flatten().map(|e| e.path()) {
+        let name = p.file_name().and_then(|s| s.to_str()).unwrap_or("");
+        if p.is_dir() {
+            if name != "node_modules" && name != "dist" && !name.starts_with('.') {
+                walk(&p, out);
+            }
+        } else if p
+            .extension()
+            .and_then(|e| e.to_str())
+            .is_some_and(|e| SOURCE_EXTENSIONS.contains(&e))
+        {
+            out.push(p);
+        }
+    }
+}
+
+/// Script and component files below `dir`, skipping `node_modules`, `dist`
+/// and hidden directories.
+pub fn files(dir: &Path) -> Vec<PathBuf> {
+    let mut out = Vec::new();
+    walk(dir, &mut out);
+    out.sort();
+    out
+}
+
+/// Replaces string literals that are exactly `from` (in any quote style)
+/// with `to`.
+pub fn replace_literal(text: &str, from: &str, to: &str) -> String {
+    let mut s = text.to_string();
+    for q in ['"', '\'', '`'] {
+        s = s.replace(&format!("{q}{from}{q}"), &format!("{q}{to}{q}"));
+    }
+    s
+}
+
+/// The string literal `s` starts with (after whitespace), unless it is
+/// empty, spans lines or is built from escapes or `${}` interpolation.
+fn literal_at(s: &str) -> Option<&str> {
+    let s = s.trim_start();
+    let q = s.chars().next().filter(|c| matches!(c, '"' | '\'' | '`'))?;
+    let lit = &s[1..1 + s[1..].find(q)?];
+    if lit.is_empty() || lit.contains(['\\', '\n']) || lit.contains("${") {
+        return None;
+    }
+    Some(lit)
+}
+
+/// Keys passed as literals to `t(...)`, `$t(...)`, `i18n.t(...)` or a
+/// `i18nKey="..."` attribute in `text`, with the 1-based line of each.
+pub fn key_calls(text: &str) -> Vec<(String, usize)> {
+    let newlines: Vec<usize> = text.match_indices('\n').map(|(i, _)| i).collect();
+    let bytes = text.as_bytes();
+    let calls = text.match_indices("t(").filter(|(i, _)| {
+        *i == 0 || !(bytes[i - 1].is_ascii_alphanumeric() || bytes[i - 1] == b'_')
+    });
+    let attrs = text.match_indices("i18nKey=");
+    let mut out: Vec<(usize, String)> = calls
+        .chain(attrs)
+        .filter_map(|(i, m)| literal_at(&text[i + m.len()..]).map(|k| (i, k.to_string())))
+        .collect();
+    out.sort();
+    out.into_iter()
+        .map(|(i, k)| (k, newlines.partition_point(|&n| n < i) + 1))
+        .collect()
+}
+
+/// The description a developer left for the call on 1-based line `line`:
+/// a `// i18n:` comment at the end of that line or on the line above, or a
+/// JSDoc block (`/** ... */`) ending right above it, without its `@` tags.
+fn description_at(lines: &[&str], line: usize) -> Option<String> {
+    const MARK: &str = "// i18n:";
+    let own = lines[line - 1];
+    if let Some(i) = own.find(MARK) {
+        return Some(own[i + MARK.len()..].trim().to_string()).filter(|d| !d.is_empty());
+    }
+    let above = lines.get(line.checked_sub(2)?)?.trim();
+    if let Some(d) = above.strip_prefix(MARK) {
+        return Some(d.trim().to_string()).filter(|d| !d.is_empty());
+    }
+    if !above.ends_with("*/") {
+        return None;
+    }
+    let end = line - 2;
+    let start = (0..=end).rev().find(|&i| lines[i].contains("/**"))?;
+    let block: Vec<&str> = lines[start..=end]
+        .iter()
+        .map(|l| {
+            let l = l.trim();
+            let l = l.split_once("/**").map_or(l, |(_, rest)| rest);
+            let l = l.strip_suffix("*/").unwrap_or(l).trim();
+            l.strip_prefix('*').unwrap_or(l).trim()
+        })
+        .take_while(|l| !l.starts_with('@'))
+        .filter(|l| !l.is_empty())
+        .collect();
+    let text = block.join(" ");
+    let text = text.strip_prefix("i18n:").unwrap_or(&text).trim();
+    Some(text.to_string()).filter(|d| !d.is_empty())
+}
+
+/// The [`key_calls`] of `text` that have a description next to them, with
+/// the line and the description.
+pub fn described_calls(text: &str) -> Vec<(String, usize, String)> {
+    let lines: Vec<&str> = text.lines().collect();
+    key_calls(text)
+        .into_iter()
+        .filter_map(|(k, line)| description_at(&lines, line).map(|d| (k, line, d)))
+        .collect()
+}
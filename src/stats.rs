@@ -0,0 +1,315 @@
+//! `stats`: translation progress of every locale, optionally recorded to a
+//! history file (`--record`) and compared with earlier records (`--trend`).
+
+use crate::api;
+use crate::layout;
+use crate::locales::LocaleSet;
+use crate::report::{self, Format};
+use crate::rules;
+use crate::write;
+use clap::ArgMatches;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+pub const DEFAULT_HISTORY: &str = "cvr-i18n-history.json";
+
+/// Progress of one locale: base strings translated out of all of them.
+#[derive(Serialize, Deserialize, Clone, Copy, Default)]
+struct Progress {
+    translated: usize,
+    total: usize,
+}
+
+impl Progress {
+    fn coverage(self) -> f64 {
+        if self.total == 0 {
+            100.0
+        } else {
+            self.translated as f64 * 100.0 / self.total as f64
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct Snapshot {
+    /// ISO 8601 UTC time of the record.
+    time: String,
+    locales: BTreeMap<String, Progress>,
+}
+
+/// `secs` since the Unix epoch as an ISO 8601 UTC timestamp.
+pub fn timestamp(secs: u64) -> String {
+    // Days to civil date, after Howard Hinnant's `civil_from_days`.
+    let z = (secs / 86400) as i64 + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z - era * 146097;
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    let rem = secs % 86400;
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z",
+        year,
+        month,
+        day,
+        rem / 3600,
+        rem % 3600 / 60,
+        rem % 60
+    )
+}
+
+/// Progress of every locale but the base, summed over its namespace files.
+fn current(dir: &Path, base: &Path, locale_set: &mut LocaleSet) -> BTreeMap<String, Progress> {
+    let mut out: BTreeMap<String, Progress> = BTreeMap::new();
+    for p in crate::target_files(dir, None) {
+        if layout::is_base(base, &p) {
+            continue;
+        }
+        let bp = layout::base_for(base, &p);
+        let (doc, base_v) = match (locale_set.value(&p), locale_set.value(&bp)) {
+            (Ok(d), Ok(b)) => (d, b),
+            (Err(e), _) | (_, Err(e)) => {
+                report::error(&p, e);
+                continue;
+            }
+        };
+        let s = api::stats_of(&doc, Some(&base_v));
+        let progress = out.entry(rules::locale_of(&p)).or_default();
+        progress.translated += s.translated;
+        progress.total += s.translated + s.missing + s.empty;
+    }
+    out
+}
+
+fn is_csv(path: &Path) -> bool {
+    path.extension().is_some_and(|e| e == "csv")
+}
+
+/// Whether `time` starts with a `YYYY-MM-DD` date, as [`timestamp`] writes.
+fn is_dated(time: &str) -> bool {
+    let b = time.as_bytes();
+    b.len() >= 10
+        && b[..10].iter().enumerate().all(|(i, c)| {
+            if i == 4 || i == 7 {
+                *c == b'-'
+            } else {
+                c.is_ascii_digit()
+            }
+        })
+}
+
+fn load_history(path: &Path) -> Result<Vec<Snapshot>, String> {
+    let history = read_history(path)?;
+    match history.iter().find(|snap| !is_dated(&snap.time)) {
+        Some(bad) => Err(format!(
+            "{}: time {:?} does not start with a YYYY-MM-DD date",
+            path.display(),
+            bad.time
+        )),
+        None => Ok(history),
+    }
+}
+
+fn read_history(path: &Path) -> Result<Vec<Snapshot>, String> {
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+    let text = fs::read_to_string(path).map_err(|e| format!("read {}: {}", path.display(), e))?;
+    if !is_csv(path) {
+        return serde_json::from_str(&text).map_err(|e| format!("parse {}: {}", path.display(), e));
+    }
+    let mut out: Vec<Snapshot> = Vec::new();
+    for (i, line) in text.lines().enumerate().skip(1) {
+        let fields: Vec<&str> = line.split(',').collect();
+        let [time, locale, translated, total, ..] = fields[..] else {
+            return Err(format!(
+                "{}:{}: expected time,locale,translated,total",
+                path.display(),
+                i + 1
+            ));
+        };
+        let number = |s: &str| {
+            s.parse::<usize>()
+                .map_err(|e| format!("{}:{}: {}", path.display(), i + 1, e))
+        };
+        let progress = Progress {
+            translated: number(translated)?,
+            total: number(total)?,
+        };
+        match out.last_mut() {
+            Some(last) if last.time == time => {
+                last.locales.insert(locale.to_string(), progress);
+            }
+            _ => out.push(Snapshot {
+                time: time.to_string(),
+                locales: BTreeMap::from([(locale.to_string(), progress)]),
+            }),
+        }
+    }
+    Ok(out)
+}
+
+fn save_history(path: &Path, history: &[Snapshot]) -> Result<(), String> {
+    let text = if is_csv(path) {
+        let mut s = String::from("time,locale,translated,total,coverage\n");
+        for snap in history {
+            for (locale, p) in &snap.locales {
+                s.push_str(&format!(
+                    "{},{},{},{},{:.1}\n",
+                    snap.time,
+                    locale,
+                    p.translated,
+                    p.total,
+                    p.coverage()
+                ));
+            }
+        }
+        s
+    } else {
+        serde_json::to_string_pretty(history).map_err(|e| e.to_string())?
+    };
+    write::write_file(path, &text)
+}
+
+fn print_table(locales: &BTreeMap<String, Progress>) {
+    if report::style().format == Format::Csv {
+        println!("locale,translated,total,coverage");
+        for (locale, p) in locales {
+            println!(
+                "{},{},{},{:.1}",
+                report::csv_field(locale),
+                p.translated,
+                p.total,
+                p.coverage()
+            );
+        }
+        return;
+    }
+    let width = locales.keys().map(String::len).max().unwrap_or(0).max(6);
+    report::line(format!(
+        "{:width$}  {:>11}  {:>8}",
+        "LOCALE", "TRANSLATED", "COVERAGE"
+    ));
+    for (locale, p) in locales {
+        report::line(format!(
+            "{:width$}  {:>11}  {:>7.1}%",
+            locale,
+            format!("{}/{}", p.translated, p.total),
+            p.coverage()
+        ));
+    }
+}
+
+fn print_trend(from: &Snapshot, to: &BTreeMap<String, Progress>, to_label: &str) {
+    report::line(format!("Since {} (to {}):", from.time, to_label));
+    let width = to.keys().map(String::len).max().unwrap_or(0).max(6);
+    for (locale, p) in to {
+        let change = match from.locales.get(locale) {
+            Some(before) => format!(
+                "{:+.1} ({:+} strings)",
+                p.coverage() - before.coverage(),
+                p.translated as i64 - before.translated as i64
+            ),
+            None => "new".to_string(),
+        };
+        report::line(format!(
+            "{:width$}  {:>7.1}%  {}",
+            locale,
+            p.coverage(),
+            change
+        ));
+    }
+}
+
+/// A `YYYY-MM-DD` date argument.
+fn date_arg(m: &ArgMatches, id: &str) -> Result<Option<String>, String> {
+    let Some(d) = m.get_one::<String>(id) else {
+        return Ok(None);
+    };
+    let b = d.as_bytes();
+    let valid = b.len() == 10
+        && b[4] == b'-'
+        && b[7] == b'-'
+        && b.iter()
+            .enumerate()
+            .all(|(i, c)| i == 4 || i == 7 || c.is_ascii_digit());
+    if valid {
+        Ok(Some(d.clone()))
+    } else {
+        Err(format!("--{}: expected YYYY-MM-DD, got {}", id, d))
+    }
+}
+
+pub fn run(dir: &Path, base: &Path, m: &ArgMatches, locale_set: &mut LocaleSet) -> i32 {
+    let (since, until) = match date_arg(m, "since").and_then(|s| Ok((s, date_arg(m, "until")?))) {
+        Ok(r) => r,
+        Err(e) => {
+            eprintln!("{}", e);
+            return 2;
+        }
+    };
+    let locales = current(dir, base, locale_set);
+    let history_path = Path::new(m.get_one::<String>("history").unwrap());
+    let record = m.get_flag("record");
+    let trend = m.get_flag("trend");
+    if !record && !trend {
+        print_table(&locales);
+        return 0;
+    }
+    let mut history = match load_history(history_path) {
+        Ok(h) => h,
+        Err(e) => {
+            eprintln!("Failed to read history: {}", e);
+            return 2;
+        }
+    };
+    if trend {
+        // From the first record on or after --since, else the latest; to
+        // the last record on or before --until, else now.
+        let from = match &since {
+            Some(s) => history.iter().find(|snap| snap.time.as_str() >= s.as_str()),
+            None => history.last(),
+        };
+        let to = until.as_ref().and_then(|u| {
+            history
+                .iter()
+                .rev()
+                .find(|snap| snap.time[..10] <= *u.as_str())
+        });
+        match (from, &until, to) {
+            (None, _, _) => {
+                report::info(format!("No earlier record in {}", history_path.display()))
+            }
+            (Some(_), Some(u), None) => report::info(format!("No record on or before {}", u)),
+            (Some(from), _, Some(to)) => print_trend(from, &to.locales, &to.time),
+            (Some(from), None, None) => print_trend(from, &locales, "now"),
+        }
+    } else {
+        print_table(&locales);
+    }
+    if record {
+        let time = timestamp(
+            SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map_or(0, |d| d.as_secs()),
+        );
+        history.push(Snapshot { time, locales });
+        if let Err(e) = save_history(history_path, &history) {
+            eprintln!("Failed to write history: {}", e);
+            return 2;
+        }
+        report::info(format!(
+            "{} snapshot {} to {}",
+            write::verb("Recorded", "record"),
+            history.len(),
+            history_path.display()
+        ));
+    }
+    0
+}
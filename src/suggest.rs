@@ -0,0 +1,70 @@
+//! `--suggest`: a starting point for a missing key, the locale's translation
+//! of the base string most similar to the missing one (`Save profile` for
+//! a missing `Save profiles`).
+
+use crate::rules;
+use serde_json::Value;
+use std::collections::HashMap;
+
+/// Below this similarity a translation is more misleading than helpful.
+const MIN_SIMILARITY: f64 = 0.6;
+
+pub struct Suggestion {
+    /// The similar base key the translation belongs to.
+    pub key: String,
+    pub translation: String,
+    /// 0 to 1.
+    pub similarity: f64,
+}
+
+fn levenshtein(a: &[char], b: &[char]) -> usize {
+    let mut prev: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.iter().enumerate() {
+        let mut cur = vec![i + 1];
+        for (j, cb) in b.iter().enumerate() {
+            let cost = usize::from(ca != cb);
+            cur.push((prev[j] + cost).min(prev[j + 1] + 1).min(cur[j] + 1));
+        }
+        prev = cur;
+    }
+    prev[b.len()]
+}
+
+/// How alike two strings are, ignoring case: 1 minus their edit distance
+/// over the longer length.
+pub fn similarity(a: &str, b: &str) -> f64 {
+    let a: Vec<char> = a.to_lowercase().chars().collect();
+    let b: Vec<char> = b.to_lowercase().chars().collect();
+    let longest = a.len().max(b.len());
+    if longest == 0 {
+        return 1.0;
+    }
+    let shortest = a.len().min(b.len());
+    if (shortest as f64) / (longest as f64) < MIN_SIMILARITY {
+        return 0.0;
+    }
+    1.0 - levenshtein(&a, &b) as f64 / longest as f64
+}
+
+/// The translation in `locale` of the base string most like the base text
+/// of the missing `key`, if one is similar enough. Translations still in
+/// the base language are not suggested.
+pub fn best(key: &str, base: &Value, locale: &Value) -> Option<Suggestion> {
+    let Some(Value::String(english)) = base.get(key) else {
+        return None;
+    };
+    let own: HashMap<String, &str> = rules::string_entries(locale).into_iter().collect();
+    rules::string_entries(base)
+        .into_iter()
+        .filter(|(k, _)| k != key)
+        .filter_map(|(k, en)| {
+            let t = own.get(&k).filter(|t| !t.trim().is_empty() && **t != en)?;
+            let similarity = similarity(english, en);
+            (similarity >= MIN_SIMILARITY).then(|| Suggestion {
+                translation: t.to_string(),
+                key: k,
+                similarity,
+            })
+        })
+        .max_by(|a, b| a.similarity.total_cmp(&b.similarity))
+}
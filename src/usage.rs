@@ -0,0 +1,152 @@
+//! `usage`: how many call sites in the frontend source reference each key
+//! of the base locale, most used first.
+
+use crate::layout;
+use crate::locales::LocaleSet;
+use crate::meta::Metadata;
+use crate::report::{self, Format};
+use crate::rules;
+use clap::ArgMatches;
+use serde_json::{Map, Value, json};
+use std::collections::HashMap;
+use std::ffi::OsString;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Where each key is used in the source: file and 1-based line.
+pub type Locations = HashMap<String, Vec<(PathBuf, usize)>>;
+
+/// The source directory of `m`'s `--src`, by default the parent of the
+/// locales directory.
+pub fn src_dir(dir: &Path, m: &ArgMatches) -> PathBuf {
+    match m.get_one::<OsString>("src") {
+        Some(s) => PathBuf::from(s),
+        None => dir.parent().unwrap_or(Path::new(".")).to_path_buf(),
+    }
+}
+
+/// Every key literal passed to a translation call below `src`, and how many
+/// source files were read.
+pub fn scan(src: &Path) -> (Locations, usize) {
+    let mut out = Locations::new();
+    let files = crate::source::files(src);
+    for f in &files {
+        let Ok(text) = fs::read_to_string(f) else {
+            continue;
+        };
+        for (key, line) in crate::source::key_calls(&text) {
+            out.entry(key).or_default().push((f.clone(), line));
+        }
+    }
+    (out, files.len())
+}
+
+/// The `missing` keys of a locale file in namespace `ns` as an export that
+/// gives translators context: each key's base value and the `file:line`
+/// call sites using it or, for a section, any key inside it, and its
+/// description and context URLs from the base's metadata sidecar.
+pub fn annotate(
+    missing: &[String],
+    base: &Value,
+    ns: Option<&str>,
+    locations: &Locations,
+    metadata: &Metadata,
+) -> Value {
+    let mut out = Map::new();
+    for k in missing {
+        let full = match ns {
+            Some(ns) => format!("{}:{}", ns, k),
+            None => k.clone(),
+        };
+        let inside = format!("{}.", full);
+        let mut used: Vec<(&PathBuf, usize)> = locations
+            .iter()
+            .filter(|(key, _)| **key == full || key.starts_with(&inside))
+            .flat_map(|(_, locs)| locs.iter().map(|(f, l)| (f, *l)))
+            .collect();
+        used.sort();
+        let used: Vec<String> = used
+            .into_iter()
+            .map(|(f, l)| format!("{}:{}", f.display(), l))
+            .collect();
+        let mut entry = json!({ "base": base.get(k), "locations": used });
+        if let Some(meta) = metadata.get(k) {
+            if !meta.description.is_empty() {
+                entry["description"] = meta.description.clone().into();
+            }
+            if !meta.context.is_empty() {
+                entry["context"] = meta.context.clone().into();
+            }
+        }
+        out.insert(k.clone(), entry);
+    }
+    Value::Object(out)
+}
+
+/// Dotted string keys of the base, as `namespace:key` when namespaced.
+pub fn base_keys(
+    dir: &Path,
+    base: &Path,
+    locale_set: &mut LocaleSet,
+) -> Result<Vec<String>, String> {
+    if !layout::namespaced() {
+        let v = locale_set.value(base)?;
+        return Ok(rules::string_entries(&v)
+            .into_iter()
+            .map(|(k, _)| k)
+            .collect());
+    }
+    let mut keys = Vec::new();
+    let mut files: Vec<PathBuf> = layout::files(dir)
+        .into_iter()
+        .filter(|p| layout::is_base(base, p))
+        .collect();
+    files.sort();
+    for p in files {
+        let v = locale_set.value(&p)?;
+        let ns = layout::namespace_of(&p).unwrap_or_default();
+        keys.extend(
+            rules::string_entries(&v)
+                .into_iter()
+                .map(|(k, _)| format!("{}:{}", ns, k)),
+        );
+    }
+    Ok(keys)
+}
+
+pub fn run(dir: &Path, base: &Path, m: &ArgMatches, locale_set: &mut LocaleSet) -> i32 {
+    let keys = match base_keys(dir, base, locale_set) {
+        Ok(k) => k,
+        Err(e) => {
+            eprintln!("Failed to read {}: {}", base.display(), e);
+            return 2;
+        }
+    };
+    let (locations, scanned) = scan(&src_dir(dir, m));
+    let mut counts: Vec<(usize, String)> = keys
+        .into_iter()
+        .map(|k| (locations.get(&k).map_or(0, Vec::len), k))
+        .collect();
+    counts.sort_by(|a, b| b.0.cmp(&a.0).then_with(|| a.1.cmp(&b.1)));
+    if report::style().format == Format::Csv {
+        println!("key,count");
+        for (n, k) in &counts {
+            println!("{},{}", report::csv_field(k), n);
+        }
+        return 0;
+    }
+    report::line(format!("{:>7}  KEY", "COUNT"));
+    for (n, k) in &counts {
+        report::line(format!("{:>7}  {}", n, k));
+    }
+    let calls: usize = counts.iter().map(|c| c.0).sum();
+    let unused = counts.iter().filter(|c| c.0 == 0).count();
+    report::info(format!(
+        "{} keys, {} call sites in {} source files, {} keys unused",
+        counts.len(),
+        calls,
+        scanned,
+        unused
+    ));
+    0
+}
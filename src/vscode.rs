@@ -0,0 +1,156 @@
+//! `vscode`: VS Code's `package.nls.json` files, so the editor extension
+//! pulls its strings from the validated locales here.
+//!
+//! The base becomes `package.nls.json` and every other locale
+//! `package.nls.<locale>.json`, with VS Code's lower-case locale ids
+//! (`zh-cn`). The files are flat, keyed by dotted key; with `--prefix` only
+//! the keys under it are written, without the prefix, so `ext.command.run`
+//! is `%command.run%` in `package.json`. Base strings with a description
+//! carry it as the `comment` of a `{"message", "comment"}` entry.
+
+use crate::config::Config;
+use crate::locale::canonical_locale;
+use crate::locales::LocaleSet;
+use crate::{interop, json, layout, meta, report, rules};
+use clap::ArgMatches;
+use serde_json::{Map, Value, json};
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+/// The nls file of `locale`, `None` for the base: `zh_CN` and `zh-CN` are
+/// both `package.nls.zh-cn.json`.
+fn file_name(locale: Option<&str>) -> String {
+    match locale {
+        Some(l) => format!("package.nls.{}.json", canonical_locale(l).to_lowercase()),
+        None => "package.nls.json".to_string(),
+    }
+}
+
+/// `key` without `prefix`, or `None` when it is not under it.
+fn strip<'a>(key: &'a str, prefix: Option<&str>) -> Option<&'a str> {
+    match prefix {
+        None => Some(key),
+        Some(p) => key
+            .strip_prefix(p)
+            .and_then(|rest| rest.strip_prefix('.'))
+            .filter(|rest| !rest.is_empty()),
+    }
+}
+
+/// `vscode export <DIR>`: writes the nls files of every locale, leaving
+/// out strings a locale lacks or has empty so VS Code falls back to
+/// `package.nls.json`. With `--check` the files are only compared, and the
+/// ones out of date listed.
+pub fn run_export(
+    dir: &Path,
+    base: &Path,
+    m: &ArgMatches,
+    config: &Config,
+    locale_set: &mut LocaleSet,
+) -> i32 {
+    if let Err(e) = layout::require_flat("vscode") {
+        eprintln!("{}", e);
+        return 2;
+    }
+    let out_dir = Path::new(m.get_one::<String>("dir").unwrap());
+    let prefix = m
+        .get_one::<String>("prefix")
+        .map(|p| p.trim_end_matches('.'));
+    let check = m.get_flag("check");
+    let (base_v, metadata) = match locale_set
+        .value(base)
+        .and_then(|v| meta::load(base, locale_set).map(|meta| (v, meta)))
+    {
+        Ok(loaded) => loaded,
+        Err(e) => {
+            eprintln!("Failed to read {}: {}", base.display(), e);
+            return 2;
+        }
+    };
+    // Each base key under the prefix with its nls key, in base order.
+    let keys: Vec<(String, String)> = rules::string_entries(&base_v)
+        .into_iter()
+        .filter_map(|(k, _)| {
+            let nls = strip(&k, prefix)?.to_string();
+            Some((k, nls))
+        })
+        .collect();
+    if keys.is_empty() {
+        eprintln!(
+            "No keys under {} in {}",
+            prefix.unwrap_or_default(),
+            base.display()
+        );
+        return 2;
+    }
+    let (mut stale, mut failed) = (0, 0);
+    let files = crate::target_files(dir, None);
+    for p in &files {
+        let v = match locale_set.value(p) {
+            Ok(v) => v,
+            Err(e) => {
+                report::error(p, e);
+                failed += 1;
+                continue;
+            }
+        };
+        let is_base = layout::is_base(base, p);
+        let strings: HashMap<String, &str> = rules::string_entries(&v).into_iter().collect();
+        let mut nls = Map::new();
+        for (k, key) in &keys {
+            let Some(s) = strings.get(k).filter(|s| !s.is_empty()) else {
+                continue;
+            };
+            let description = metadata
+                .get(k)
+                .map(|m| m.description.as_str())
+                .filter(|d| is_base && !d.is_empty());
+            let entry = match description {
+                Some(d) => json!({"message": s, "comment": [d]}),
+                None => Value::String(s.to_string()),
+            };
+            nls.insert(key.clone(), entry);
+        }
+        let locale = layout::locale_of(p);
+        let target = out_dir.join(file_name((!is_base).then_some(locale.as_str())));
+        let text = json::to_string(&Value::Object(nls), &config.fmt);
+        if check {
+            if fs::read_to_string(&target).ok().as_deref() == Some(text.as_str()) {
+                report::ok(&target);
+            } else {
+                stale += 1;
+                report::flag(&target, "OUT OF DATE");
+            }
+            continue;
+        }
+        if !interop::export(&target, &text, "") {
+            failed += 1;
+        }
+    }
+    interop::exit_code(failed, stale)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn region_locales_round_trip_through_nls_file_names() {
+        let dir = std::env::temp_dir().join(format!("cvr-i18n-nls-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        for locale in ["zh_CN", "zh-TW", "pt_br"] {
+            let file = dir.join(format!("{}.json", locale));
+            fs::write(&file, "{}").unwrap();
+            let name = file_name(Some(locale));
+            let id = name
+                .strip_prefix("package.nls.")
+                .and_then(|n| n.strip_suffix(".json"))
+                .unwrap();
+            assert_eq!(id, id.to_lowercase().replace('_', "-"));
+            assert_eq!(layout::file_for_code(&dir, id), Ok(file));
+        }
+        assert_eq!(file_name(None), "package.nls.json");
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,30 @@
+//! JavaScript bindings of the WebAssembly build (`wasm-pack build`). Results
+//! are returned as JSON strings; failures are thrown as string errors.
+
+use crate::api;
+use crate::config::Config;
+use wasm_bindgen::prelude::*;
+
+/// [`api::validate`], with the config given as the text of a
+/// `cvr-i18n.json`; returns a JSON array of issues.
+#[wasm_bindgen]
+pub fn validate(
+    locale: &str,
+    text: &str,
+    base: Option<String>,
+    config: Option<String>,
+) -> Result<String, JsValue> {
+    let config: Config = match config {
+        Some(c) => serde_json::from_str(&c).map_err(|e| format!("parse config: {}", e))?,
+        None => Config::default(),
+    };
+    let issues = api::validate(locale, text, base.as_deref(), &config)?;
+    Ok(serde_json::to_string(&issues).expect("issues serialize"))
+}
+
+/// [`api::stats`]; returns a JSON object of counts and coverage.
+#[wasm_bindgen]
+pub fn stats(text: &str, base: Option<String>) -> Result<String, JsValue> {
+    let stats = api::stats(text, base.as_deref())?;
+    Ok(serde_json::to_string(&stats).expect("stats serialize"))
+}
@@ -0,0 +1,126 @@
+//! Every command that rewrites, renames, creates or removes files goes through here so
+//! that `--dry-run` applies uniformly.
+
+use clap::ArgMatches;
+use std::fs::{self, File};
+use std::io::{self, Write};
+use std::path::Path;
+use std::sync::OnceLock;
+
+static DRY_RUN: OnceLock<bool> = OnceLock::new();
+static BACKUP: OnceLock<Option<String>> = OnceLock::new();
+static PREVIEW: OnceLock<fn(&Path, &str)> = OnceLock::new();
+
+/// `--diff` and `--emit-patch` preview changes without writing, like
+/// `--dry-run`.
+pub fn init(m: &ArgMatches) {
+    let _ = DRY_RUN.set(
+        m.get_flag("dry_run") || m.get_flag("diff") || m.get_one::<String>("emit_patch").is_some(),
+    );
+    let _ = BACKUP.set(m.get_one::<String>("backup").cloned());
+}
+
+pub fn dry_run() -> bool {
+    DRY_RUN.get().copied().unwrap_or(false)
+}
+
+/// With `--backup`, copies an existing `path` to `path<suffix>` first.
+fn backup(path: &Path) -> Result<(), String> {
+    let Some(suffix) = BACKUP.get().and_then(|b| b.as_deref()) else {
+        return Ok(());
+    };
+    if !path.exists() {
+        return Ok(());
+    }
+    let mut target = path.as_os_str().to_owned();
+    target.push(suffix);
+    fs::copy(path, &target)
+        .map(|_| ())
+        .map_err(|e| format!("backup {}: {}", path.display(), e))
+}
+
+/// Writes to a temporary file next to `path` and renames it into place, so
+/// a crash mid-write never leaves a truncated file. The original file's
+/// permissions are kept.
+fn write_atomic(path: &Path, contents: &[u8]) -> io::Result<()> {
+    let dir = match path.parent() {
+        Some(d) if !d.as_os_str().is_empty() => d,
+        _ => Path::new("."),
+    };
+    let name = path.file_name().unwrap_or_default().to_string_lossy();
+    let tmp = dir.join(format!(".{}.{}.tmp", name, std::process::id()));
+    let result = (|| {
+        let mut f = File::create(&tmp)?;
+        f.write_all(contents)?;
+        f.sync_all()?;
+        if let Ok(meta) = fs::metadata(path) {
+            fs::set_permissions(&tmp, meta.permissions())?;
+        }
+        fs::rename(&tmp, path)
+    })();
+    if result.is_err() {
+        let _ = fs::remove_file(&tmp);
+    }
+    result
+}
+
+/// Has `preview` shown every text file before it is written, given the
+/// path, still holding the old contents, and the new contents.
+pub fn set_preview(preview: fn(&Path, &str)) {
+    let _ = PREVIEW.set(preview);
+}
+
+/// Writes `contents` to `path`, or does nothing under `--dry-run`.
+pub fn write_file(path: &Path, contents: &str) -> Result<(), String> {
+    if let Some(preview) = PREVIEW.get() {
+        preview(path, contents);
+    }
+    write_bytes(path, contents.as_bytes())
+}
+
+/// [`write_file`] into a directory that may not exist yet, creating it
+/// unless under `--dry-run`.
+pub fn write_creating_dirs(path: &Path, contents: &str) -> Result<(), String> {
+    if !dry_run()
+        && let Some(dir) = path.parent().filter(|d| !d.as_os_str().is_empty())
+    {
+        fs::create_dir_all(dir).map_err(|e| format!("create {}: {}", dir.display(), e))?;
+    }
+    write_file(path, contents)
+}
+
+/// [`write_file`] for binary contents such as archives.
+pub fn write_bytes(path: &Path, contents: &[u8]) -> Result<(), String> {
+    if dry_run() {
+        return Ok(());
+    }
+    backup(path)?;
+    write_atomic(path, contents).map_err(|e| format!("write {}: {}", path.display(), e))
+}
+
+/// Renames `from` to `to`, or does nothing under `--dry-run`.
+pub fn rename(from: &Path, to: &Path) -> Result<(), String> {
+    if dry_run() {
+        return Ok(());
+    }
+    fs::rename(from, to).map_err(|e| format!("rename {}: {}", from.display(), e))
+}
+
+/// Deletes `path`, or does nothing under `--dry-run`. With `--backup` the
+/// file is kept under the backup name.
+pub fn remove(path: &Path) -> Result<(), String> {
+    if dry_run() {
+        return Ok(());
+    }
+    backup(path)?;
+    fs::remove_file(path).map_err(|e| format!("remove {}: {}", path.display(), e))
+}
+
+/// "Sorted" or "Would sort", depending on `--dry-run`.
+pub fn verb(done: &str, would: &str) -> String {
+    if dry_run() {
+        format!("Would {}", would)
+    } else {
+        done.to_string()
+    }
+}